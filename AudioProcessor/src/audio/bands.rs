@@ -0,0 +1,251 @@
+// User-defined frequency bands and the per-band analysis (value, dynamic, velocity, peak hold, log)
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::audio::processor::{dynamic_normalize_with_sharpness, HistoryState};
+
+/// How a band's value follows its input
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BandResponse {
+    /// Double EMA against a maximum that eases up towards louder input and never decays
+    /// (good for broad bands)
+    #[default]
+    Smooth,
+    /// Instantaneous value against a running maximum (good for percussive bands)
+    Transient,
+}
+
+fn default_weighting() -> f32 {
+    1.0
+}
+
+/// A named frequency band, e.g. `{ "name": "sub", "min_hz": 20, "max_hz": 60 }`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BandDefinition {
+    pub name: String,
+    pub min_hz: f32,
+    pub max_hz: f32,
+    /// Gain applied to the band's mean magnitude before normalization
+    #[serde(default = "default_weighting")]
+    pub weighting: f32,
+    #[serde(default)]
+    pub response: BandResponse,
+}
+
+impl BandDefinition {
+    pub fn new(name: &str, min_hz: f32, max_hz: f32, response: BandResponse) -> Self {
+        BandDefinition {
+            name: name.to_string(),
+            min_hz,
+            max_hz,
+            weighting: 1.0,
+            response,
+        }
+    }
+}
+
+/// The classic low/mid/high + kick/snare/hihat split
+pub fn default_bands() -> Vec<BandDefinition> {
+    vec![
+        BandDefinition::new("low", 20.0, 250.0, BandResponse::Smooth),
+        BandDefinition::new("mid", 250.0, 4000.0, BandResponse::Smooth),
+        BandDefinition::new("high", 4000.0, 20000.0, BandResponse::Smooth),
+        BandDefinition::new("kick", 40.0, 100.0, BandResponse::Transient),
+        BandDefinition::new("snare", 120.0, 500.0, BandResponse::Transient),
+        BandDefinition::new("hihat", 2000.0, 10000.0, BandResponse::Transient),
+    ]
+}
+
+#[derive(Debug)]
+pub enum BandConfigError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for BandConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BandConfigError::Io(e) => write!(f, "failed to read band config: {}", e),
+            BandConfigError::Parse(e) => write!(f, "failed to parse band config: {}", e),
+            BandConfigError::Invalid(msg) => write!(f, "invalid band config: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for BandConfigError {}
+
+/// Check names are unique and non-empty, and ranges/weights are sane
+pub fn validate_bands(bands: &[BandDefinition]) -> Result<(), BandConfigError> {
+    if bands.is_empty() {
+        return Err(BandConfigError::Invalid("at least one band is required".to_string()));
+    }
+    for (i, band) in bands.iter().enumerate() {
+        if band.name.trim().is_empty() {
+            return Err(BandConfigError::Invalid(format!("band #{} has an empty name", i)));
+        }
+        if bands[..i].iter().any(|other| other.name == band.name) {
+            return Err(BandConfigError::Invalid(format!("duplicate band name '{}'", band.name)));
+        }
+        if !(band.min_hz >= 0.0 && band.max_hz > band.min_hz) {
+            return Err(BandConfigError::Invalid(format!(
+                "band '{}' needs 0 <= min_hz < max_hz (got {}..{})",
+                band.name, band.min_hz, band.max_hz
+            )));
+        }
+        if !(band.weighting.is_finite() && band.weighting > 0.0) {
+            return Err(BandConfigError::Invalid(format!("band '{}' needs a positive weighting", band.name)));
+        }
+    }
+    Ok(())
+}
+
+/// Load a JSON band list, either `[...]` or `{ "bands": [...] }`
+pub fn load_bands(path: &Path) -> Result<Vec<BandDefinition>, BandConfigError> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum BandFile {
+        List(Vec<BandDefinition>),
+        Wrapped { bands: Vec<BandDefinition> },
+    }
+    let text = fs::read_to_string(path).map_err(BandConfigError::Io)?;
+    let bands = match serde_json::from_str(&text).map_err(BandConfigError::Parse)? {
+        BandFile::List(bands) => bands,
+        BandFile::Wrapped { bands } => bands,
+    };
+    validate_bands(&bands)?;
+    Ok(bands)
+}
+
+/// Mean magnitude of the FFT bins covering `min_hz..max_hz`
+pub fn band_mean(frequency_data: &[f32], bin_width: f32, min_hz: f32, max_hz: f32) -> f32 {
+    let min_bin = (min_hz / bin_width).floor() as usize;
+    let max_bin = (max_hz / bin_width).ceil() as usize;
+    let bins = &frequency_data[min_bin.min(frequency_data.len())..max_bin.min(frequency_data.len())];
+    if bins.is_empty() { 0.0 } else { bins.iter().copied().sum::<f32>() / bins.len() as f32 }
+}
+
+/// Log-scaled value (perceptual, useful for brightness/scale in shaders)
+pub fn log_scale(v: f32) -> f32 {
+    if v > 0.0 { (1.0 + v.ln() / 10.0).clamp(0.0, 1.0) } else { 0.0 }
+}
+
+/// One frame of output for a band
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BandOutput {
+    pub value: f32,
+    pub dynamic: f32,
+    pub velocity: f32,
+    pub peak_hold: f32,
+    pub log: f32,
+}
+
+/// Smoothing/normalization parameters shared by all bands
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BandSmoothing {
+    /// EMA factor applied to the raw band mean
+    pub alpha: f32,
+    /// EMA factor applied after normalization
+    pub post_alpha: f32,
+    /// How quickly the adaptive maximum follows the signal
+    pub max_alpha: f32,
+    /// Contrast of the history-based `dynamic` value
+    pub sharpness: f32,
+    pub dynamic_smoothing: f32,
+    /// Per-frame multiplier for peak hold
+    pub peak_decay: f32,
+}
+
+impl Default for BandSmoothing {
+    fn default() -> Self {
+        BandSmoothing {
+            alpha: 0.1,
+            post_alpha: 0.15,
+            max_alpha: 0.05,
+            sharpness: 0.5,
+            dynamic_smoothing: 0.8,
+            peak_decay: 0.95,
+        }
+    }
+}
+
+/// Running state for one configured band
+pub struct BandTracker {
+    pub definition: BandDefinition,
+    pub value: f32,
+    /// Smoothed raw magnitude, before normalization (smooth response)
+    pub smoothed: f32,
+    pub max: f32,
+    pub history: HistoryState,
+    pub dynamic_smoothed: f32,
+    pub velocity_history: HistoryState,
+    pub peak_hold: f32,
+    pub output: BandOutput,
+}
+
+impl BandTracker {
+    pub fn new(definition: BandDefinition, history_size: usize, velocity_history_size: usize) -> Self {
+        BandTracker {
+            definition,
+            value: 0.0,
+            smoothed: 0.0,
+            max: 1.0,
+            history: HistoryState::new(history_size),
+            dynamic_smoothed: 0.5,
+            velocity_history: HistoryState::new(velocity_history_size),
+            peak_hold: 0.0,
+            output: BandOutput::default(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.definition.name
+    }
+
+    /// Change the history lengths, keeping the most recent values
+    pub fn resize_history(&mut self, history_size: usize, velocity_history_size: usize) {
+        self.history.resize(history_size);
+        self.velocity_history.resize(velocity_history_size);
+    }
+
+    /// Process this frame's spectrum and return the band output
+    pub fn update(&mut self, frequency_data: &[f32], bin_width: f32, delta_time: f32, params: &BandSmoothing) -> BandOutput {
+        let raw = band_mean(frequency_data, bin_width, self.definition.min_hz, self.definition.max_hz) * self.definition.weighting;
+        let previous = self.value;
+        let value = match self.definition.response {
+            BandResponse::Smooth => {
+                self.smoothed = self.smoothed * (1.0 - params.alpha) + raw * params.alpha;
+                self.max = self.max * (1.0 - params.max_alpha) + raw.max(self.max) * params.max_alpha;
+                let normalized = (self.smoothed / self.max).clamp(0.0, 1.0);
+                previous * (1.0 - params.post_alpha) + normalized * params.post_alpha
+            }
+            BandResponse::Transient => {
+                self.max = self.max.max(raw);
+                (raw / self.max).clamp(0.0, 1.0)
+            }
+        };
+        self.value = value;
+
+        self.history.buffer.push(value);
+        let dynamic = dynamic_normalize_with_sharpness(value, &self.history, params.sharpness);
+        self.dynamic_smoothed = self.dynamic_smoothed * params.dynamic_smoothing + dynamic * (1.0 - params.dynamic_smoothing);
+
+        let velocity = (value - previous) / delta_time.max(0.001);
+        self.velocity_history.buffer.push(velocity);
+
+        self.peak_hold = self.peak_hold.max(value) * params.peak_decay;
+
+        self.output = BandOutput {
+            value,
+            dynamic: self.dynamic_smoothed,
+            velocity,
+            peak_hold: self.peak_hold,
+            log: log_scale(value),
+        };
+        self.output
+    }
+}
@@ -0,0 +1,161 @@
+// Snapshot of the processor's adaptive state (running maxima, baselines, gains and history
+// windows) so a restart can warm-start instead of re-learning the room from silence.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_CALIBRATION_FILE: &str = "calibration.json";
+/// Seconds between periodic snapshots
+pub const CALIBRATION_SAVE_INTERVAL_SECS: u64 = 30;
+const CALIBRATION_VERSION: u32 = 1;
+
+/// Adaptive state of one configured band; restored only onto a band with the same name and range
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BandCalibration {
+    pub name: String,
+    pub min_hz: f32,
+    pub max_hz: f32,
+    pub value: f32,
+    /// Smoothed raw magnitude; 0 in snapshots written before it was kept
+    #[serde(default)]
+    pub smoothed: f32,
+    pub max: f32,
+    pub dynamic_smoothed: f32,
+    pub peak_hold: f32,
+    /// Oldest to newest
+    pub history: Vec<f32>,
+    pub velocity_history: Vec<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalibrationSnapshot {
+    pub version: u32,
+    pub bands: Vec<BandCalibration>,
+    pub max_amplitude: f32,
+    pub max_raw_amplitude: f32,
+    pub amplitude_baseline: f32,
+    pub amplitude_peak_tracker: f32,
+    pub activity_smoothing: f32,
+    pub rolling_max_bands: f32,
+    pub amplitude_dynamic_smoothed: f32,
+    pub raw_amplitude_dynamic_smoothed: f32,
+    /// Per-band gains, keyed by band name
+    pub gains: Vec<(String, f32)>,
+    pub kick_average: f32,
+    pub snare_average: f32,
+    pub hihat_average: f32,
+    pub amplitude_history: Vec<f32>,
+    pub raw_amplitude_history: Vec<f32>,
+    pub vocal_history: Vec<f32>,
+    /// Timbre descriptor histories (rolloff 85/95, flatness, bandwidth, zcr, then contrast octaves)
+    pub descriptor_histories: Vec<Vec<f32>>,
+}
+
+impl CalibrationSnapshot {
+    pub fn new() -> Self {
+        CalibrationSnapshot {
+            version: CALIBRATION_VERSION,
+            bands: Vec::new(),
+            max_amplitude: 1.0,
+            max_raw_amplitude: 1.0,
+            amplitude_baseline: 0.001,
+            amplitude_peak_tracker: 0.01,
+            activity_smoothing: 0.0,
+            rolling_max_bands: 1.0,
+            amplitude_dynamic_smoothed: 0.5,
+            raw_amplitude_dynamic_smoothed: 0.5,
+            gains: Vec::new(),
+            kick_average: 0.0,
+            snare_average: 0.0,
+            hihat_average: 0.0,
+            amplitude_history: Vec::new(),
+            raw_amplitude_history: Vec::new(),
+            vocal_history: Vec::new(),
+            descriptor_histories: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, CalibrationError> {
+        let text = fs::read_to_string(path).map_err(CalibrationError::Io)?;
+        let snapshot: CalibrationSnapshot = serde_json::from_str(&text).map_err(CalibrationError::Parse)?;
+        if snapshot.version != CALIBRATION_VERSION {
+            return Err(CalibrationError::Version(snapshot.version));
+        }
+        Ok(snapshot)
+    }
+
+    /// Write atomically (temp file + rename) so a crash mid-save can't leave a truncated snapshot
+    pub fn save(&self, path: &Path) -> Result<(), CalibrationError> {
+        let text = serde_json::to_string(&self.sanitized()).map_err(CalibrationError::Parse)?;
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, text).map_err(CalibrationError::Io)?;
+        fs::rename(&tmp, path).map_err(CalibrationError::Io)
+    }
+
+    /// JSON writes NaN and infinity as null, which would fail the next load: non-finite
+    /// scalars fall back to their cold-start values, and bands and history entries holding
+    /// them are dropped and re-learned
+    fn sanitized(&self) -> Self {
+        let cold = CalibrationSnapshot::new();
+        let finite = |value: f32, fallback: f32| if value.is_finite() { value } else { fallback };
+        let finite_history = |history: &[f32]| history.iter().copied().filter(|value| value.is_finite()).collect();
+        CalibrationSnapshot {
+            version: self.version,
+            bands: self
+                .bands
+                .iter()
+                .filter(|band| {
+                    [band.min_hz, band.max_hz, band.value, band.smoothed, band.max, band.dynamic_smoothed, band.peak_hold]
+                        .iter()
+                        .all(|value| value.is_finite())
+                })
+                .map(|band| BandCalibration {
+                    history: finite_history(&band.history),
+                    velocity_history: finite_history(&band.velocity_history),
+                    ..band.clone()
+                })
+                .collect(),
+            max_amplitude: finite(self.max_amplitude, cold.max_amplitude),
+            max_raw_amplitude: finite(self.max_raw_amplitude, cold.max_raw_amplitude),
+            amplitude_baseline: finite(self.amplitude_baseline, cold.amplitude_baseline),
+            amplitude_peak_tracker: finite(self.amplitude_peak_tracker, cold.amplitude_peak_tracker),
+            activity_smoothing: finite(self.activity_smoothing, cold.activity_smoothing),
+            rolling_max_bands: finite(self.rolling_max_bands, cold.rolling_max_bands),
+            amplitude_dynamic_smoothed: finite(self.amplitude_dynamic_smoothed, cold.amplitude_dynamic_smoothed),
+            raw_amplitude_dynamic_smoothed: finite(self.raw_amplitude_dynamic_smoothed, cold.raw_amplitude_dynamic_smoothed),
+            gains: self.gains.iter().filter(|(_, gain)| gain.is_finite()).cloned().collect(),
+            kick_average: finite(self.kick_average, cold.kick_average),
+            snare_average: finite(self.snare_average, cold.snare_average),
+            hihat_average: finite(self.hihat_average, cold.hihat_average),
+            amplitude_history: finite_history(&self.amplitude_history),
+            raw_amplitude_history: finite_history(&self.raw_amplitude_history),
+            vocal_history: finite_history(&self.vocal_history),
+            descriptor_histories: self.descriptor_histories.iter().map(|history| finite_history(history)).collect(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CalibrationError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+    Version(u32),
+}
+
+impl fmt::Display for CalibrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CalibrationError::Io(e) => write!(f, "failed to access calibration snapshot: {}", e),
+            CalibrationError::Parse(e) => write!(f, "failed to parse calibration snapshot: {}", e),
+            CalibrationError::Version(v) => write!(
+                f,
+                "calibration snapshot version {} is not supported (expected {})",
+                v, CALIBRATION_VERSION
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CalibrationError {}
@@ -0,0 +1,131 @@
+// Channel selection for multichannel interfaces: a stream can analyze some of the device's
+// channels, their mono downmix, or (split) each channel as a stream of its own. Channels are
+// numbered from 1, as printed on the interface.
+
+use std::fmt;
+use serde::Deserialize;
+
+/// Channel selection as written in the streams file, e.g. `{ "pick": [3, 4] }`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelSelection {
+    /// These channels, in this order
+    Pick(Vec<u16>),
+    /// The average of these channels, as one mono channel
+    Downmix(Vec<u16>),
+    /// Each of these channels as its own stream, named `<stream>-<channel>`
+    Split(Vec<u16>),
+}
+
+impl ChannelSelection {
+    pub fn channels(&self) -> &[u16] {
+        match self {
+            ChannelSelection::Pick(channels) | ChannelSelection::Downmix(channels) | ChannelSelection::Split(channels) => {
+                channels
+            }
+        }
+    }
+
+    /// Non-empty, numbered from 1, no channel twice
+    pub fn validate(&self) -> Result<(), String> {
+        let channels = self.channels();
+        if channels.is_empty() {
+            return Err("the channel list is empty".to_string());
+        }
+        for (i, &channel) in channels.iter().enumerate() {
+            if channel == 0 {
+                return Err("channels are numbered from 1".to_string());
+            }
+            if channels[..i].contains(&channel) {
+                return Err(format!("channel {} is listed twice", channel));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How a capture callback's interleaved buffer becomes the samples a stream analyzes
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum ChannelMap {
+    /// Every device channel, as delivered
+    #[default]
+    All,
+    /// These channels (0-based), interleaved in this order
+    Pick(Vec<usize>),
+    /// The average of these channels (0-based)
+    Downmix(Vec<usize>),
+}
+
+impl ChannelMap {
+    /// Map for a stream's selection; a split is expected to be expanded into single-channel picks
+    pub fn new(selection: Option<&ChannelSelection>) -> Self {
+        let zero_based = |channels: &[u16]| channels.iter().map(|&channel| channel.saturating_sub(1) as usize).collect();
+        match selection {
+            None => ChannelMap::All,
+            Some(ChannelSelection::Pick(channels)) | Some(ChannelSelection::Split(channels)) => {
+                ChannelMap::Pick(zero_based(channels))
+            }
+            Some(ChannelSelection::Downmix(channels)) => ChannelMap::Downmix(zero_based(channels)),
+        }
+    }
+
+    /// Check the selection against the device's channel count
+    pub fn validate(&self, device_channels: usize) -> Result<(), String> {
+        let highest = match self {
+            ChannelMap::All => return Ok(()),
+            ChannelMap::Pick(channels) | ChannelMap::Downmix(channels) => channels.iter().max().copied().unwrap_or(0),
+        };
+        if highest >= device_channels {
+            return Err(format!("channel {} was selected but the device has {}", highest + 1, device_channels));
+        }
+        Ok(())
+    }
+
+    /// Channels the stream analyzes
+    pub fn output_channels(&self, device_channels: usize) -> usize {
+        match self {
+            ChannelMap::All => device_channels,
+            ChannelMap::Pick(channels) => channels.len(),
+            ChannelMap::Downmix(_) => 1,
+        }
+    }
+
+    /// Replace `out` with the selected channels of the interleaved `data`
+    pub fn apply(&self, data: &[f32], device_channels: usize, out: &mut Vec<f32>) {
+        out.clear();
+        let frames = data.chunks_exact(device_channels.max(1));
+        match self {
+            ChannelMap::All => out.extend_from_slice(data),
+            ChannelMap::Pick(channels) => {
+                for frame in frames {
+                    out.extend(channels.iter().map(|&channel| frame[channel]));
+                }
+            }
+            ChannelMap::Downmix(channels) => {
+                let scale = 1.0 / channels.len().max(1) as f32;
+                for frame in frames {
+                    out.push(channels.iter().map(|&channel| frame[channel]).sum::<f32>() * scale);
+                }
+            }
+        }
+    }
+
+    /// Noise profiles are kept per device and selection, e.g. `Interface [3+4]`
+    pub fn profile_key(&self, device: &str) -> String {
+        match self {
+            ChannelMap::All => device.to_string(),
+            _ => format!("{} [{}]", device, self),
+        }
+    }
+}
+
+impl fmt::Display for ChannelMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |channels: &[usize]| channels.iter().map(|channel| (channel + 1).to_string()).collect::<Vec<_>>().join("+");
+        match self {
+            ChannelMap::All => write!(f, "all"),
+            ChannelMap::Pick(channels) => write!(f, "{}", join(channels)),
+            ChannelMap::Downmix(channels) => write!(f, "{} mono", join(channels)),
+        }
+    }
+}
@@ -0,0 +1,18 @@
+// Monotonic server clock shared by the audio callback and the control channel. Frames carry
+// capture times on this clock and `ping` replies report it, so clients can map frames onto
+// their own render clock.
+
+use std::sync::OnceLock;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+static ORIGIN: OnceLock<Instant> = OnceLock::new();
+
+/// Seconds since the server clock started (first use); never goes backwards
+pub fn server_time() -> f64 {
+    ORIGIN.get_or_init(Instant::now).elapsed().as_secs_f64()
+}
+
+/// Wall-clock seconds since the UNIX epoch (the clock `beat_times` and `last_beat_time` use)
+pub fn epoch_time() -> f64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0.0, |d| d.as_secs_f64())
+}
@@ -0,0 +1,601 @@
+// Runtime-tunable analysis parameters. Defaults come from CONSTANTS; a config can be
+// loaded/saved as JSON and swapped into a running AudioProcessor.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::audio::bands::{self, BandConfigError, BandDefinition, BandSmoothing};
+use crate::audio::constants::CONSTANTS;
+use crate::audio::playback::PlaybackConfig;
+use crate::audio::lfo::LfoConfig;
+use crate::audio::modulation::{CustomField, ModulationBank};
+use crate::state::{BAND_ATTRIBUTES, FLOAT_FIELDS, UNIT_BAND_ATTRIBUTES, UNIT_FIELDS};
+use crate::audio::segments::SegmentConfig;
+use crate::audio::structure::StructureConfig;
+
+// Longest display pipeline `latency.output_delay` may compensate, in seconds
+const MAX_OUTPUT_DELAY: f32 = 2.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistoryConfig {
+    pub freq_history_window: usize,
+    pub beat_history_window: usize,
+    pub vocal_history_window: usize,
+    pub vocal_variance_window: usize,
+    /// Seconds of beat times used for the BPS estimate
+    pub beat_time_window: f32,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        let windows = &CONSTANTS.history_window_size;
+        HistoryConfig {
+            freq_history_window: windows.freq_history_window,
+            beat_history_window: windows.beat_history_window,
+            vocal_history_window: windows.vocal_history_window,
+            vocal_variance_window: windows.vocal_variance_window,
+            beat_time_window: windows.beat_time_window,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BeatConfig {
+    pub beat_alpha: f32,
+    pub beat_threshold: f32,
+    pub min_beat_interval: f32,
+    pub beat_decay_rate: f32,
+    pub bps_smoothing_factor: f32,
+    pub spectral_flux_threshold: f32,
+    pub min_beat_intensity: f32,
+    /// Contribution of each percussive band to the combined onset ratio
+    pub kick_weight: f32,
+    pub snare_weight: f32,
+    pub hihat_weight: f32,
+}
+
+impl Default for BeatConfig {
+    fn default() -> Self {
+        let beat = &CONSTANTS.beat_detection_parameters;
+        BeatConfig {
+            beat_alpha: beat.beat_alpha,
+            beat_threshold: beat.beat_threshold,
+            min_beat_interval: beat.min_beat_interval,
+            beat_decay_rate: beat.beat_decay_rate,
+            bps_smoothing_factor: beat.bps_smoothing_factor,
+            spectral_flux_threshold: beat.spectral_flux_threshold,
+            min_beat_intensity: beat.min_beat_intensity,
+            kick_weight: 0.6,
+            snare_weight: 0.3,
+            hihat_weight: 0.1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VocalConfig {
+    pub harmonic_weight: f32,
+    pub mid_weight: f32,
+    pub variance_weight: f32,
+    pub freq_min: f32,
+    pub freq_max: f32,
+    pub harmonic_threshold: f32,
+    pub harmonic_count: usize,
+    pub max_variance: f32,
+}
+
+impl Default for VocalConfig {
+    fn default() -> Self {
+        VocalConfig {
+            harmonic_weight: CONSTANTS.vocal_harmonic_weight,
+            mid_weight: CONSTANTS.vocal_mid_weight,
+            variance_weight: CONSTANTS.vocal_variance_weight,
+            freq_min: CONSTANTS.vocal_freq_min,
+            freq_max: CONSTANTS.vocal_freq_max,
+            harmonic_threshold: CONSTANTS.harmonic_threshold,
+            harmonic_count: CONSTANTS.harmonic_count,
+            max_variance: CONSTANTS.vocal_max_variance,
+        }
+    }
+}
+
+/// Envelope follower tuning (see `EnvelopeFollower`), for `amplitude` and any field in `field_envelopes`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EnvelopeConfig {
+    pub attack_rate: f32,
+    pub decay_rate: f32,
+    pub momentum_factor: f32,
+    /// Seconds to hold a peak before decaying
+    pub peak_hold_time: f32,
+    pub adaptive_attack: bool,
+    pub adaptive_decay: bool,
+    pub adaptive_attack_multiplier: f32,
+    pub adaptive_decay_multiplier: f32,
+    pub transient_boost_factor: f32,
+    pub transient_decay_rate: f32,
+    pub silence_threshold: f32,
+    pub peak_detection_threshold: f32,
+}
+
+impl Default for EnvelopeConfig {
+    fn default() -> Self {
+        let params = &CONSTANTS.amplitude_envelope_parameters;
+        EnvelopeConfig {
+            attack_rate: params.default_attack_rate,
+            decay_rate: params.default_decay_rate,
+            momentum_factor: params.default_momentum_factor,
+            peak_hold_time: params.default_peak_hold_time,
+            adaptive_attack: true,
+            adaptive_decay: true,
+            adaptive_attack_multiplier: params.adaptive_attack_multiplier,
+            adaptive_decay_multiplier: params.adaptive_decay_multiplier,
+            transient_boost_factor: params.transient_boost_factor,
+            transient_decay_rate: params.transient_decay_rate,
+            silence_threshold: params.silence_threshold,
+            peak_detection_threshold: params.peak_detection_threshold,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnvelopeProfile {
+    Smooth,      // Very smooth, good for ambient/chill music
+    Responsive,  // Balanced, good for most music
+    Punchy,      // Fast attack, good for electronic/dance music
+    Sustained,   // Slow changes, good for classical/orchestral
+}
+
+impl EnvelopeProfile {
+    /// Set the attack/decay/momentum/peak-hold values for this profile
+    pub fn apply(self, envelope: &mut EnvelopeConfig) {
+        let (attack_rate, decay_rate, momentum_factor, peak_hold_time) = match self {
+            EnvelopeProfile::Smooth => (0.08, 0.03, 0.9, 0.15),
+            EnvelopeProfile::Responsive => (0.25, 0.12, 0.6, 0.05),
+            EnvelopeProfile::Punchy => (0.4, 0.08, 0.4, 0.08),
+            EnvelopeProfile::Sustained => (0.12, 0.02, 0.95, 0.25),
+        };
+        envelope.attack_rate = attack_rate;
+        envelope.decay_rate = decay_rate;
+        envelope.momentum_factor = momentum_factor;
+        envelope.peak_hold_time = peak_hold_time;
+    }
+}
+
+/// An envelope follower on one output field (`kick_dynamic`, `bands.kick.dynamic`, ...)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldEnvelope {
+    pub field: String,
+    /// Named starting point; ignored when `envelope` is given
+    #[serde(default)]
+    pub profile: Option<EnvelopeProfile>,
+    /// Full custom parameters
+    #[serde(default)]
+    pub envelope: Option<EnvelopeConfig>,
+}
+
+impl FieldEnvelope {
+    /// The parameters this follower runs with
+    pub fn params(&self) -> EnvelopeConfig {
+        match (&self.envelope, self.profile) {
+            (Some(envelope), _) => envelope.clone(),
+            (None, Some(profile)) => {
+                let mut envelope = EnvelopeConfig::default();
+                profile.apply(&mut envelope);
+                envelope
+            }
+            (None, None) => EnvelopeConfig::default(),
+        }
+    }
+}
+
+/// Baseline tracking behind the 0-1 `amplitude` output
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AmplitudeConfig {
+    pub silence_threshold: f32,
+    pub baseline_adaptation_rate: f32,
+    pub activity_smoothing_rate: f32,
+    pub peak_tracker_decay: f32,
+    /// Frames of silence before the amplitude fades fully to zero
+    pub max_silence_frames: i32,
+    /// Smoothing of `fade_in_out` towards the dynamic amplitude
+    pub fade_speed: f32,
+}
+
+impl Default for AmplitudeConfig {
+    fn default() -> Self {
+        AmplitudeConfig {
+            silence_threshold: 0.0001,
+            baseline_adaptation_rate: 0.001,
+            activity_smoothing_rate: 0.1,
+            peak_tracker_decay: 0.995,
+            max_silence_frames: 100,
+            fade_speed: 0.02,
+        }
+    }
+}
+
+/// Spectral subtraction and thresholds derived from a measured noise profile
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NoiseConfig {
+    /// Multiple of the measured noise spectrum to subtract
+    pub over_subtraction: f32,
+    /// Fraction of each bin kept even when the noise estimate exceeds it
+    pub spectral_floor: f32,
+    /// Activity thresholds are the residual noise level times this margin
+    pub threshold_margin: f32,
+}
+
+impl Default for NoiseConfig {
+    fn default() -> Self {
+        NoiseConfig {
+            over_subtraction: 1.0,
+            spectral_floor: 0.02,
+            threshold_margin: 1.5,
+        }
+    }
+}
+
+/// Look-ahead applied to the beat fields to hide pipeline delay
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LatencyConfig {
+    /// Seconds from a client receiving a frame to it being visible (renderer, projector).
+    /// `next_beat_time` is moved this much earlier and `beat_phase` this much ahead
+    pub output_delay: f32,
+    /// Also advance `beat_phase` by the measured capture-to-send latency
+    pub compensate_pipeline: bool,
+}
+
+impl Default for LatencyConfig {
+    fn default() -> Self {
+        LatencyConfig {
+            output_delay: 0.0,
+            compensate_pipeline: false,
+        }
+    }
+}
+
+/// Every tunable the processor reads at runtime
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProcessorConfig {
+    pub audio_activity_threshold: f32,
+    pub noise_floor: f32,
+    /// Rolling-max smoothing for the 32 quantized log bands
+    pub quantized_band_alpha: f32,
+    /// Mean bin energy below which the grid map counts as inactive (overridden by a noise profile)
+    pub grid_activity_threshold: f32,
+    pub bands: Vec<BandDefinition>,
+    pub band_smoothing: BandSmoothing,
+    pub history: HistoryConfig,
+    pub beat: BeatConfig,
+    pub vocal: VocalConfig,
+    pub envelope: EnvelopeConfig,
+    pub amplitude: AmplitudeConfig,
+    pub noise: NoiseConfig,
+    pub playback: PlaybackConfig,
+    pub structure: StructureConfig,
+    pub segments: SegmentConfig,
+    pub lfo: LfoConfig,
+    pub latency: LatencyConfig,
+    /// Per-field envelope followers, applied before `custom_fields` are evaluated
+    pub field_envelopes: Vec<FieldEnvelope>,
+    /// Derived outputs computed from the other fields each frame
+    pub custom_fields: Vec<CustomField>,
+}
+
+impl Default for ProcessorConfig {
+    fn default() -> Self {
+        ProcessorConfig {
+            audio_activity_threshold: CONSTANTS.audio_activity_threshold,
+            noise_floor: CONSTANTS.noise_floor,
+            quantized_band_alpha: 0.05,
+            grid_activity_threshold: 0.00001,
+            bands: bands::default_bands(),
+            band_smoothing: BandSmoothing::default(),
+            history: HistoryConfig::default(),
+            beat: BeatConfig::default(),
+            vocal: VocalConfig::default(),
+            envelope: EnvelopeConfig::default(),
+            amplitude: AmplitudeConfig::default(),
+            noise: NoiseConfig::default(),
+            playback: PlaybackConfig::default(),
+            structure: StructureConfig::default(),
+            segments: SegmentConfig::default(),
+            lfo: LfoConfig::default(),
+            latency: LatencyConfig::default(),
+            field_envelopes: Vec::new(),
+            custom_fields: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "failed to access processor config: {}", e),
+            ConfigError::Parse(e) => write!(f, "failed to parse processor config: {}", e),
+            ConfigError::Invalid(msg) => write!(f, "invalid processor config: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<BandConfigError> for ConfigError {
+    fn from(e: BandConfigError) -> Self {
+        match e {
+            BandConfigError::Io(e) => ConfigError::Io(e),
+            BandConfigError::Parse(e) => ConfigError::Parse(e),
+            BandConfigError::Invalid(msg) => ConfigError::Invalid(msg),
+        }
+    }
+}
+
+fn check_unit(name: &str, value: f32) -> Result<(), ConfigError> {
+    if (0.0..=1.0).contains(&value) {
+        Ok(())
+    } else {
+        Err(ConfigError::Invalid(format!("{} must be within 0..=1 (got {})", name, value)))
+    }
+}
+
+fn check_positive(name: &str, value: f32) -> Result<(), ConfigError> {
+    if value.is_finite() && value > 0.0 {
+        Ok(())
+    } else {
+        Err(ConfigError::Invalid(format!("{} must be positive (got {})", name, value)))
+    }
+}
+
+fn check_envelope(prefix: &str, e: &EnvelopeConfig) -> Result<(), ConfigError> {
+    check_unit(&format!("{}.attack_rate", prefix), e.attack_rate)?;
+    check_unit(&format!("{}.decay_rate", prefix), e.decay_rate)?;
+    check_unit(&format!("{}.momentum_factor", prefix), e.momentum_factor)?;
+    check_unit(&format!("{}.transient_decay_rate", prefix), e.transient_decay_rate)?;
+    check_unit(&format!("{}.adaptive_decay_multiplier", prefix), e.adaptive_decay_multiplier)?;
+    if e.peak_hold_time < 0.0 {
+        return Err(ConfigError::Invalid(format!("{}.peak_hold_time must not be negative", prefix)));
+    }
+    Ok(())
+}
+
+fn check_window(name: &str, value: usize) -> Result<(), ConfigError> {
+    if value > 0 {
+        Ok(())
+    } else {
+        Err(ConfigError::Invalid(format!("{} must be at least 1", name)))
+    }
+}
+
+impl ProcessorConfig {
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        bands::validate_bands(&self.bands)?;
+
+        check_unit("audio_activity_threshold", self.audio_activity_threshold)?;
+        check_positive("noise_floor", self.noise_floor)?;
+        check_unit("quantized_band_alpha", self.quantized_band_alpha)?;
+
+        let s = &self.band_smoothing;
+        check_unit("band_smoothing.alpha", s.alpha)?;
+        check_unit("band_smoothing.post_alpha", s.post_alpha)?;
+        check_unit("band_smoothing.max_alpha", s.max_alpha)?;
+        check_unit("band_smoothing.sharpness", s.sharpness)?;
+        check_unit("band_smoothing.dynamic_smoothing", s.dynamic_smoothing)?;
+        check_unit("band_smoothing.peak_decay", s.peak_decay)?;
+
+        let h = &self.history;
+        check_window("history.freq_history_window", h.freq_history_window)?;
+        check_window("history.beat_history_window", h.beat_history_window)?;
+        check_window("history.vocal_history_window", h.vocal_history_window)?;
+        check_window("history.vocal_variance_window", h.vocal_variance_window)?;
+        check_positive("history.beat_time_window", h.beat_time_window)?;
+
+        let b = &self.beat;
+        check_unit("beat.beat_alpha", b.beat_alpha)?;
+        check_positive("beat.beat_threshold", b.beat_threshold)?;
+        check_positive("beat.min_beat_interval", b.min_beat_interval)?;
+        check_positive("beat.beat_decay_rate", b.beat_decay_rate)?;
+        check_unit("beat.bps_smoothing_factor", b.bps_smoothing_factor)?;
+        check_unit("beat.min_beat_intensity", b.min_beat_intensity)?;
+        if b.spectral_flux_threshold < 0.0 {
+            return Err(ConfigError::Invalid("beat.spectral_flux_threshold must not be negative".to_string()));
+        }
+        if b.kick_weight < 0.0 || b.snare_weight < 0.0 || b.hihat_weight < 0.0 {
+            return Err(ConfigError::Invalid("beat weights must not be negative".to_string()));
+        }
+
+        let v = &self.vocal;
+        if !(v.freq_min > 0.0 && v.freq_max > v.freq_min) {
+            return Err(ConfigError::Invalid(format!(
+                "vocal range needs 0 < freq_min < freq_max (got {}..{})",
+                v.freq_min, v.freq_max
+            )));
+        }
+        check_unit("vocal.harmonic_threshold", v.harmonic_threshold)?;
+        check_positive("vocal.max_variance", v.max_variance)?;
+
+        check_envelope("envelope", &self.envelope)?;
+
+        let a = &self.amplitude;
+        check_unit("amplitude.baseline_adaptation_rate", a.baseline_adaptation_rate)?;
+        check_unit("amplitude.activity_smoothing_rate", a.activity_smoothing_rate)?;
+        check_unit("amplitude.peak_tracker_decay", a.peak_tracker_decay)?;
+        check_unit("amplitude.fade_speed", a.fade_speed)?;
+        if a.max_silence_frames < 1 {
+            return Err(ConfigError::Invalid("amplitude.max_silence_frames must be at least 1".to_string()));
+        }
+
+        check_positive("grid_activity_threshold", self.grid_activity_threshold)?;
+        let n = &self.noise;
+        check_positive("noise.over_subtraction", n.over_subtraction)?;
+        check_unit("noise.spectral_floor", n.spectral_floor)?;
+        check_positive("noise.threshold_margin", n.threshold_margin)?;
+
+        let p = &self.playback;
+        if p.dead_threshold < 0.0 {
+            return Err(ConfigError::Invalid("playback.dead_threshold must not be negative".to_string()));
+        }
+        check_positive("playback.play_hold", p.play_hold)?;
+        check_positive("playback.gap_hold", p.gap_hold)?;
+        check_positive("playback.silent_hold", p.silent_hold)?;
+        if p.idle_hold <= p.gap_hold {
+            return Err(ConfigError::Invalid("playback.idle_hold must be longer than playback.gap_hold".to_string()));
+        }
+
+        let st = &self.structure;
+        check_positive("structure.short_window", st.short_window)?;
+        check_positive("structure.tension_smoothing", st.tension_smoothing)?;
+        check_positive("structure.density_window", st.density_window)?;
+        check_positive("structure.roll_density", st.roll_density)?;
+        check_positive("structure.centroid_rise", st.centroid_rise)?;
+        check_unit("structure.snare_onset_threshold", st.snare_onset_threshold)?;
+        check_unit("structure.drop_tension", st.drop_tension)?;
+        check_positive("structure.drop_window", st.drop_window)?;
+        check_unit("structure.breakdown_energy_ratio", st.breakdown_energy_ratio)?;
+        check_unit("structure.breakdown_low_ratio", st.breakdown_low_ratio)?;
+        check_positive("structure.cooldown", st.cooldown)?;
+        if st.long_window <= st.short_window {
+            return Err(ConfigError::Invalid("structure.long_window must be longer than structure.short_window".to_string()));
+        }
+        if st.drop_low_ratio <= 1.0 {
+            return Err(ConfigError::Invalid("structure.drop_low_ratio must be greater than 1".to_string()));
+        }
+        for (name, weight) in [
+            ("structure.centroid_weight", st.centroid_weight),
+            ("structure.density_weight", st.density_weight),
+            ("structure.energy_weight", st.energy_weight),
+            ("structure.low_absence_weight", st.low_absence_weight),
+        ] {
+            check_unit(name, weight)?;
+        }
+
+        let sg = &self.segments;
+        check_positive("segments.block_secs", sg.block_secs)?;
+        if sg.kernel_blocks < 2 {
+            return Err(ConfigError::Invalid("segments.kernel_blocks must be at least 2".to_string()));
+        }
+        check_positive("segments.threshold_std", sg.threshold_std)?;
+        check_unit("segments.min_novelty", sg.min_novelty)?;
+        check_positive("segments.min_section_secs", sg.min_section_secs)?;
+        check_unit("segments.label_similarity", sg.label_similarity)?;
+
+        let l = &self.lfo;
+        if l.beats_per_bar < 1 {
+            return Err(ConfigError::Invalid("lfo.beats_per_bar must be at least 1".to_string()));
+        }
+        check_positive("lfo.fallback_bpm", l.fallback_bpm)?;
+        check_unit("lfo.phase_correction", l.phase_correction)?;
+        for (i, definition) in l.lfos.iter().enumerate() {
+            if definition.name.is_empty() {
+                return Err(ConfigError::Invalid(format!("lfo.lfos[{}] has an empty name", i)));
+            }
+            if l.lfos[..i].iter().any(|other| other.name == definition.name) {
+                return Err(ConfigError::Invalid(format!("duplicate LFO name '{}'", definition.name)));
+            }
+            check_positive(&format!("lfo '{}' period", definition.name), definition.period)?;
+            check_unit(&format!("lfo '{}' phase", definition.name), definition.phase)?;
+        }
+
+        let delay = self.latency.output_delay;
+        if !(0.0..=MAX_OUTPUT_DELAY).contains(&delay) {
+            return Err(ConfigError::Invalid(format!(
+                "latency.output_delay must be within 0..={} seconds (got {})",
+                MAX_OUTPUT_DELAY, delay
+            )));
+        }
+
+        for (i, follower) in self.field_envelopes.iter().enumerate() {
+            let known = match follower.field.split('.').collect::<Vec<_>>().as_slice() {
+                ["bands", band, attribute] => {
+                    self.bands.iter().any(|b| b.name == *band) && BAND_ATTRIBUTES.contains(attribute)
+                }
+                [name] => FLOAT_FIELDS.contains(name),
+                _ => false,
+            };
+            if !known {
+                return Err(ConfigError::Invalid(format!("field_envelopes: unknown field '{}'", follower.field)));
+            }
+            let unit = match follower.field.split('.').collect::<Vec<_>>().as_slice() {
+                ["bands", _, attribute] => UNIT_BAND_ATTRIBUTES.contains(attribute),
+                [name] => UNIT_FIELDS.contains(name),
+                _ => false,
+            };
+            if !unit {
+                // The follower's thresholds and output clamp assume 0-1 values
+                return Err(ConfigError::Invalid(format!(
+                    "field_envelopes: '{}' is not a 0-1 field, which envelope followers need",
+                    follower.field
+                )));
+            }
+            if self.field_envelopes[..i].iter().any(|other| other.field == follower.field) {
+                return Err(ConfigError::Invalid(format!("field_envelopes: '{}' has more than one envelope", follower.field)));
+            }
+            if let Some(envelope) = &follower.envelope {
+                check_envelope(&format!("field_envelopes '{}'", follower.field), envelope)?;
+            }
+        }
+
+        for (i, field) in self.custom_fields.iter().enumerate() {
+            if field.name.is_empty() {
+                return Err(ConfigError::Invalid(format!("custom_fields[{}] has an empty name", i)));
+            }
+            if self.custom_fields[..i].iter().any(|other| other.name == field.name) {
+                return Err(ConfigError::Invalid(format!("duplicate custom field name '{}'", field.name)));
+            }
+            if !(field.attack >= 0.0 && field.release >= 0.0) {
+                return Err(ConfigError::Invalid(format!("custom field '{}' attack and release must not be negative", field.name)));
+            }
+            if let Some(trigger) = &field.trigger {
+                if trigger.off.is_some_and(|off| off > trigger.on) {
+                    return Err(ConfigError::Invalid(format!("custom field '{}' trigger off must not exceed on", field.name)));
+                }
+            }
+        }
+        let band_names: Vec<&str> = self.bands.iter().map(|band| band.name.as_str()).collect();
+        let lfo_names: Vec<&str> = self.lfo.lfos.iter().map(|lfo| lfo.name.as_str()).collect();
+        ModulationBank::check(&self.custom_fields, &band_names, &lfo_names).map_err(ConfigError::Invalid)?;
+        Ok(())
+    }
+
+    /// `validate`, plus the limits set by an input running at `sample_rate`
+    pub fn validate_for_rate(&self, sample_rate: u32) -> Result<(), ConfigError> {
+        self.validate()?;
+        let nyquist = sample_rate as f32 / 2.0;
+        if self.vocal.freq_max > nyquist {
+            return Err(ConfigError::Invalid(format!(
+                "vocal.freq_max must not exceed the Nyquist frequency, {} Hz at {} Hz (got {})",
+                nyquist, sample_rate, self.vocal.freq_max
+            )));
+        }
+        Ok(())
+    }
+
+    /// Load and validate a JSON config; missing fields keep their defaults
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let text = fs::read_to_string(path).map_err(ConfigError::Io)?;
+        let config: ProcessorConfig = serde_json::from_str(&text).map_err(ConfigError::Parse)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ConfigError> {
+        let text = serde_json::to_string_pretty(self).map_err(ConfigError::Parse)?;
+        fs::write(path, text).map_err(ConfigError::Io)
+    }
+}
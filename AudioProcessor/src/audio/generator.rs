@@ -0,0 +1,328 @@
+// Built-in test-signal generator: tones, sweeps, noise, click tracks, drum patterns, chords and
+// silence, fed through the same analysis pipeline as a capture device. Develop visuals without
+// audio hardware, or check the beat and pitch detection against a known ground truth.
+
+use std::f64::consts::TAU;
+use std::fmt;
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::audio::input::{Analyzer, InputError, Pipeline};
+
+pub const GENERATOR_SAMPLE_RATE: u32 = 48_000;
+/// The same signal on both channels
+pub const GENERATOR_CHANNELS: u16 = 2;
+// Frames per block handed to the pipeline (10 ms)
+const BLOCK_FRAMES: usize = 480;
+// Sweep length when none is given
+const DEFAULT_SWEEP_SECS: f64 = 10.0;
+const NOTE_NAMES: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+
+/// One kind of test signal
+#[derive(Debug, Clone, PartialEq)]
+pub enum Signal {
+    /// Sine at a fixed frequency
+    Tone { hz: f64 },
+    /// Logarithmic sine sweep, restarting every `seconds`
+    Sweep { from_hz: f64, to_hz: f64, seconds: f64 },
+    WhiteNoise,
+    PinkNoise,
+    /// A short click on every beat
+    Clicks { bpm: f64 },
+    /// Kick on beats 1 and 3, snare on 2 and 4, closed hat on every eighth
+    Drums { bpm: f64 },
+    /// Sines at the given frequencies
+    Chord { notes: Vec<f64> },
+    Silence,
+}
+
+impl Signal {
+    /// `tone:100`, `sweep:20-20000[:10]`, `white`, `pink`, `clicks:120`, `drums:128`,
+    /// `chord:C4+E4+G4` (note names or Hz) or `silence`
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (kind, argument) = match text.split_once(':') {
+            Some((kind, argument)) => (kind, Some(argument)),
+            None => (text, None),
+        };
+        let number = |value: &str, what: &str| -> Result<f64, String> {
+            match value.trim().parse::<f64>() {
+                Ok(number) if number.is_finite() && number > 0.0 => Ok(number),
+                _ => Err(format!("{} must be a positive number (got '{}')", what, value)),
+            }
+        };
+        let required = |what: &str| argument.ok_or_else(|| format!("'{}' needs {}, e.g. '{}'", kind, what, example(kind)));
+        match kind {
+            "tone" => Ok(Signal::Tone { hz: number(required("a frequency")?, "frequency")? }),
+            "sweep" => {
+                let argument = required("a frequency range")?;
+                let (range, seconds) = match argument.split_once(':') {
+                    Some((range, seconds)) => (range, number(seconds, "sweep length")?),
+                    None => (argument, DEFAULT_SWEEP_SECS),
+                };
+                let (from, to) = range.split_once('-').ok_or_else(|| format!("sweep range must look like 20-20000 (got '{}')", range))?;
+                Ok(Signal::Sweep { from_hz: number(from, "frequency")?, to_hz: number(to, "frequency")?, seconds })
+            }
+            "white" => Ok(Signal::WhiteNoise),
+            "pink" => Ok(Signal::PinkNoise),
+            "clicks" => Ok(Signal::Clicks { bpm: number(required("a tempo")?, "BPM")? }),
+            "drums" => Ok(Signal::Drums { bpm: number(required("a tempo")?, "BPM")? }),
+            "chord" => {
+                let notes = required("notes")?
+                    .split('+')
+                    .map(|note| note_frequency(note).or_else(|_| number(note, "note")))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(Signal::Chord { notes })
+            }
+            "silence" => Ok(Signal::Silence),
+            _ => Err(format!(
+                "unknown signal '{}' (expected tone, sweep, white, pink, clicks, drums, chord or silence)",
+                kind
+            )),
+        }
+    }
+}
+
+fn example(kind: &str) -> &'static str {
+    match kind {
+        "tone" => "tone:100",
+        "sweep" => "sweep:20-20000:10",
+        "clicks" => "clicks:120",
+        "drums" => "drums:128",
+        "chord" => "chord:C4+E4+G4",
+        _ => "tone:100",
+    }
+}
+
+/// Frequency of a note name such as `A4`, `F#3` or `Bb2` (A4 = 440 Hz)
+pub fn note_frequency(note: &str) -> Result<f64, String> {
+    let invalid = || format!("'{}' is not a note (e.g. C4, F#3, Bb2)", note);
+    let split = note.find(|c: char| c.is_ascii_digit() || c == '-').ok_or_else(invalid)?;
+    let (name, octave) = note.split_at(split);
+    let octave: i32 = octave.parse().map_err(|_| invalid())?;
+    let mut chars = name.chars();
+    let letter = chars.next().ok_or_else(invalid)?.to_ascii_uppercase().to_string();
+    let accidental = chars.as_str();
+    let mut pitch_class = NOTE_NAMES.iter().position(|&n| n == letter).ok_or_else(invalid)? as i32;
+    pitch_class += match accidental {
+        "" => 0,
+        "#" => 1,
+        "b" => -1,
+        _ => return Err(invalid()),
+    };
+    let midi = 12 * (octave + 1) + pitch_class;
+    Ok(440.0 * 2f64.powf((midi - 69) as f64 / 12.0))
+}
+
+impl fmt::Display for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Signal::Tone { hz } => write!(f, "a {} Hz tone", hz),
+            Signal::Sweep { from_hz, to_hz, seconds } => write!(f, "a {}-{} Hz sweep every {}s", from_hz, to_hz, seconds),
+            Signal::WhiteNoise => write!(f, "white noise"),
+            Signal::PinkNoise => write!(f, "pink noise"),
+            Signal::Clicks { bpm } => write!(f, "clicks at {} BPM ({:.3} beats/s)", bpm, bpm / 60.0),
+            Signal::Drums { bpm } => write!(f, "drums at {} BPM ({:.3} beats/s)", bpm, bpm / 60.0),
+            Signal::Chord { ref notes } => {
+                let notes: Vec<String> = notes.iter().map(|hz| format!("{:.1}", hz)).collect();
+                write!(f, "a chord of {} Hz", notes.join(" + "))
+            }
+            Signal::Silence => write!(f, "silence"),
+        }
+    }
+}
+
+/// A signal played for `seconds`, or for ever
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    pub signal: Signal,
+    pub seconds: Option<f64>,
+}
+
+/// Segments played in turn and looped, e.g. `drums:128@8,silence@2,sweep:20-20000@5`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    pub segments: Vec<Segment>,
+}
+
+impl Program {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let segments = text
+            .split(',')
+            .map(|segment| {
+                let (signal, seconds) = match segment.trim().split_once('@') {
+                    Some((signal, seconds)) => match seconds.parse::<f64>() {
+                        Ok(seconds) if seconds.is_finite() && seconds > 0.0 => (signal, Some(seconds)),
+                        _ => return Err(format!("segment length must be positive seconds (got '{}')", seconds)),
+                    },
+                    None => (segment.trim(), None),
+                };
+                Ok(Segment { signal: Signal::parse(signal)?, seconds })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        if segments.len() > 1 && segments.iter().any(|segment| segment.seconds.is_none()) {
+            return Err("every segment of a sequence needs a length, e.g. 'clicks:120@4,silence@1'".to_string());
+        }
+        Ok(Program { segments })
+    }
+}
+
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", then ")?;
+            }
+            write!(f, "{}", segment.signal)?;
+            if let Some(seconds) = segment.seconds {
+                write!(f, " for {}s", seconds)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Renders a program sample by sample
+pub struct Generator {
+    program: Program,
+    sample_rate: f64,
+    segment: usize,
+    // Samples since the current segment started
+    position: u64,
+    noise: Noise,
+}
+
+impl Generator {
+    pub fn new(program: Program, sample_rate: u32) -> Self {
+        Generator {
+            program,
+            sample_rate: sample_rate as f64,
+            segment: 0,
+            position: 0,
+            noise: Noise::new(),
+        }
+    }
+
+    /// Fill an interleaved buffer, the same sample on every channel
+    pub fn fill(&mut self, out: &mut [f32], channels: usize) {
+        for frame in out.chunks_mut(channels.max(1)) {
+            let sample = self.next_sample() as f32;
+            frame.fill(sample);
+        }
+    }
+
+    fn next_sample(&mut self) -> f64 {
+        let segment = &self.program.segments[self.segment];
+        let t = self.position as f64 / self.sample_rate;
+        if segment.seconds.is_some_and(|seconds| t >= seconds) {
+            self.segment = (self.segment + 1) % self.program.segments.len();
+            self.position = 0;
+            return self.next_sample();
+        }
+        self.position += 1;
+        match segment.signal {
+            Signal::Tone { hz } => 0.5 * (TAU * hz * t).sin(),
+            Signal::Sweep { from_hz, to_hz, seconds } => {
+                // Phase of a sweep whose frequency grows exponentially from `from_hz` to `to_hz`
+                let t = t % seconds;
+                let ratio = (to_hz / from_hz).ln();
+                let phase = if ratio.abs() < 1e-9 {
+                    from_hz * t
+                } else {
+                    from_hz * seconds / ratio * ((ratio * t / seconds).exp() - 1.0)
+                };
+                0.5 * (TAU * phase).sin()
+            }
+            Signal::WhiteNoise => 0.3 * self.noise.white(),
+            Signal::PinkNoise => 0.5 * self.noise.pink(),
+            Signal::Clicks { bpm } => {
+                let since = t % (60.0 / bpm);
+                0.8 * (TAU * 1500.0 * since).sin() * (-since / 0.002).exp()
+            }
+            Signal::Drums { bpm } => {
+                let beat = 60.0 / bpm;
+                let beats = t / beat;
+                let since_kick = (beats % 2.0) * beat;
+                let since_snare = ((beats + 1.0) % 2.0) * beat;
+                let since_hat = (beats % 0.5) * beat;
+                let noise = self.noise.white();
+                let high_passed = noise - self.noise.last;
+                self.noise.last = noise;
+                kick(since_kick)
+                    + 0.35 * noise * (-since_snare / 0.06).exp()
+                    + 0.25 * (TAU * 185.0 * since_snare).sin() * (-since_snare / 0.04).exp()
+                    + 0.12 * high_passed * (-since_hat / 0.015).exp()
+            }
+            Signal::Chord { ref notes } => {
+                let scale = 0.6 / notes.len().max(1) as f64;
+                notes.iter().map(|hz| (TAU * hz * t).sin()).sum::<f64>() * scale
+            }
+            Signal::Silence => 0.0,
+        }
+    }
+}
+
+// Noise state, kept apart from the program so the current segment can be borrowed while rendering
+struct Noise {
+    // xorshift state
+    rng: u64,
+    // Paul Kellet's pink-noise filter
+    pink: [f64; 7],
+    // Previous white sample, for the hi-hat's high-pass
+    last: f64,
+}
+
+impl Noise {
+    fn new() -> Self {
+        Noise { rng: 0x2545_F491_4F6C_DD1D, pink: [0.0; 7], last: 0.0 }
+    }
+
+    // Uniform in -1..1
+    fn white(&mut self) -> f64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        (self.rng >> 11) as f64 / (1u64 << 52) as f64 - 1.0
+    }
+
+    fn pink(&mut self) -> f64 {
+        let white = self.white();
+        let b = &mut self.pink;
+        b[0] = 0.99886 * b[0] + white * 0.0555179;
+        b[1] = 0.99332 * b[1] + white * 0.0750759;
+        b[2] = 0.96900 * b[2] + white * 0.1538520;
+        b[3] = 0.86650 * b[3] + white * 0.3104856;
+        b[4] = 0.55000 * b[4] + white * 0.5329522;
+        b[5] = -0.7616 * b[5] - white * 0.0168980;
+        let pink = b[0] + b[1] + b[2] + b[3] + b[4] + b[5] + b[6] + white * 0.5362;
+        b[6] = white * 0.115926;
+        pink * 0.11
+    }
+}
+
+// Sine kick: pitch falls from about 150 Hz to 50 Hz while the level decays
+fn kick(since: f64) -> f64 {
+    const DROP: f64 = 0.03;
+    let phase = 50.0 * since + 100.0 * DROP * (1.0 - (-since / DROP).exp());
+    0.7 * (TAU * phase).sin() * (-since / 0.15).exp()
+}
+
+/// Play `generator` in real time on its own thread, feeding `pipeline` like a capture device
+pub fn spawn_generator(mut generator: Generator, pipeline: Pipeline) -> Result<(), InputError> {
+    let channels = GENERATOR_CHANNELS as usize;
+    let mut analyzer = Analyzer::new(&pipeline, GENERATOR_SAMPLE_RATE, channels)?;
+    thread::spawn(move || {
+        let block = Duration::from_secs_f64(BLOCK_FRAMES as f64 / GENERATOR_SAMPLE_RATE as f64);
+        let mut buffer = vec![0.0; BLOCK_FRAMES * channels];
+        let mut next = Instant::now();
+        loop {
+            generator.fill(&mut buffer, channels);
+            analyzer.process(&buffer, 0.0);
+            next += block;
+            match next.checked_duration_since(Instant::now()) {
+                Some(wait) => thread::sleep(wait),
+                // Fell behind (e.g. the machine was suspended): don't try to catch up
+                None => next = Instant::now(),
+            }
+        }
+    });
+    Ok(())
+}
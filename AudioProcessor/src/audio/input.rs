@@ -0,0 +1,634 @@
+// Audio input: builds the cpal capture stream that feeds the analysis pipeline. The stream is
+// owned by a dedicated thread (cpal streams cannot move between threads), which rebuilds it on
+// request so the input device can be switched while the server and its clients keep running.
+// The same thread watches the stream's health: when the device disappears or stops delivering
+// audio it sends silent frames and reopens the device (or a fallback) with backoff.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc as std_mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use prost::Message as ProstMessage;
+use rustfft::{num_complex::Complex, num_traits::Zero, Fft, FftPlanner};
+use tokio::sync::mpsc;
+use crate::audio::channels::ChannelMap;
+use crate::audio::clock;
+use crate::audio::noise::NoiseProfileStore;
+use crate::audio::passthrough::DelayLine;
+use crate::audio::processor::{AudioProcessor, ProtoState};
+use crate::state::AnalysisEvent;
+use crate::websocket::stats::{SharedLatency, Stage};
+
+const FFT_SIZE: usize = 1024;
+// How often the input thread checks the stream (and sends a silent frame while it is down)
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+// Seconds without a callback after which a stream counts as stalled
+const STALL_SECS: f64 = 1.0;
+// Reconnect backoff: first retry delay, doubled per failed attempt up to the maximum. The
+// maximum is also how often a fallback device checks whether the preferred one is back.
+const INITIAL_BACKOFF_SECS: f64 = 0.5;
+const MAX_BACKOFF_SECS: f64 = 8.0;
+
+#[derive(Debug)]
+pub enum InputError {
+    /// No input device with that name
+    NotFound(String),
+    /// The device refused the stream or its format
+    Device(String),
+    /// The stream's channel selection does not fit the device
+    Channels(String),
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputError::NotFound(name) => write!(f, "no input device named '{}'", name),
+            InputError::Device(msg) => write!(f, "could not open input: {}", msg),
+            InputError::Channels(msg) => write!(f, "channel selection does not fit the input: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for InputError {}
+
+/// Names of the host's input devices
+pub fn input_device_names() -> Vec<String> {
+    cpal::default_host()
+        .input_devices()
+        .map(|devices| devices.filter_map(|device| device.name().ok()).collect())
+        .unwrap_or_default()
+}
+
+/// Input device by exact name, else the first whose name contains `name` (case-insensitive)
+pub fn find_input_device(name: &str) -> Option<cpal::Device> {
+    let devices: Vec<cpal::Device> = cpal::default_host().input_devices().ok()?.collect();
+    let wanted = name.to_lowercase();
+    let position = devices
+        .iter()
+        .position(|device| device.name().is_ok_and(|n| n == name))
+        .or_else(|| devices.iter().position(|device| device.name().is_ok_and(|n| n.to_lowercase().contains(&wanted))))?;
+    devices.into_iter().nth(position)
+}
+
+/// Health of the capture device, reported in `device_status` and `device` events
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceStatus {
+    /// Capturing from the chosen device
+    Ok,
+    /// The stream stopped delivering audio; reconnecting
+    Stalled,
+    /// The device went away or failed to reopen; reconnecting
+    Disconnected,
+    /// Capturing from the fallback device until the chosen one comes back
+    Fallback,
+}
+
+impl DeviceStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeviceStatus::Ok => "ok",
+            DeviceStatus::Stalled => "stalled",
+            DeviceStatus::Disconnected => "disconnected",
+            DeviceStatus::Fallback => "fallback",
+        }
+    }
+}
+
+/// Liveness of the running stream, written from its callbacks
+#[derive(Default)]
+pub struct StreamHealth {
+    // Server time of the last data callback, in microseconds
+    last_callback_us: AtomicU64,
+    // Set by the error callback when the device is gone
+    lost: AtomicBool,
+}
+
+impl StreamHealth {
+    // Start watching a fresh stream
+    fn reset(&self) {
+        self.touch();
+        self.lost.store(false, Ordering::Relaxed);
+    }
+
+    fn touch(&self) {
+        self.last_callback_us.store((clock::server_time() * 1_000_000.0) as u64, Ordering::Relaxed);
+    }
+
+    fn last_callback(&self) -> f64 {
+        self.last_callback_us.load(Ordering::Relaxed) as f64 / 1_000_000.0
+    }
+}
+
+/// Everything a capture stream feeds
+#[derive(Clone)]
+pub struct Pipeline {
+    /// Name of the stream, stamped on every frame
+    pub name: String,
+    /// Device channels the stream analyzes
+    pub channels: ChannelMap,
+    pub processor: Arc<Mutex<AudioProcessor>>,
+    /// Encoded frames for the WebSocket clients, with the stream name and the server time each
+    /// was queued at
+    pub ws_tx: mpsc::Sender<(String, Vec<u8>, f64)>,
+    /// Decoded frames for the TUI, when it runs
+    pub tui_tx: Option<mpsc::Sender<ProtoState>>,
+    pub latency: SharedLatency,
+    pub passthrough: Option<Arc<DelayLine>>,
+    /// Update period in microseconds (0 = unthrottled)
+    pub update_period_us: Arc<AtomicU64>,
+    /// Noise profiles are per device, so they are swapped along with it
+    pub noise_store: Arc<NoiseProfileStore>,
+}
+
+/// Turns interleaved input buffers into analyzed frames for the clients and the TUI. Capture
+/// callbacks and the test-signal generator drive it the same way.
+pub struct Analyzer {
+    pipeline: Pipeline,
+    sample_rate: f64,
+    device_channels: usize,
+    // Channels left after the selection
+    channels: usize,
+    // The selected channels of the current buffer
+    selected: Vec<f32>,
+    fft: Arc<dyn Fft<f32>>,
+    fft_output: Vec<Complex<f32>>,
+    // Latest FFT_SIZE frames of the selected channels' mono mix
+    fft_window: VecDeque<f32>,
+    // The spectrum describes the middle of the FFT window
+    fft_window_delay: f64,
+    // Throttle: last update time (microseconds since the epoch)
+    last_update_us: u64,
+    // Server times of the previous analysis and the previous sent frame
+    last_analysis: Option<f64>,
+    last_sent: Option<f64>,
+}
+
+impl Analyzer {
+    /// Set `pipeline` up for input at `sample_rate` with `device_channels` interleaved channels
+    pub fn new(pipeline: &Pipeline, sample_rate: u32, device_channels: usize) -> Result<Self, InputError> {
+        let device_channels = device_channels.max(1);
+        pipeline.channels.validate(device_channels).map_err(InputError::Channels)?;
+        let channels = pipeline.channels.output_channels(device_channels);
+        if let Some(line) = &pipeline.passthrough {
+            // The pass-through output keeps running at the rate it was opened with
+            if line.sample_rate() != sample_rate {
+                return Err(InputError::Device(format!(
+                    "device runs at {} Hz but pass-through plays at {} Hz",
+                    sample_rate,
+                    line.sample_rate()
+                )));
+            }
+            line.set_input_channels(channels as u16);
+        }
+        {
+            let mut processor = pipeline.processor.lock().unwrap();
+            processor.config.validate_for_rate(sample_rate).map_err(|e| InputError::Device(e.to_string()))?;
+            processor.set_input_format(sample_rate, channels as u16);
+        }
+
+        // FFT setup
+        let mut planner = FftPlanner::<f32>::new();
+        Ok(Analyzer {
+            pipeline: pipeline.clone(),
+            sample_rate: sample_rate as f64,
+            device_channels,
+            channels,
+            selected: Vec::new(),
+            fft: planner.plan_fft_forward(FFT_SIZE),
+            fft_output: vec![Complex::zero(); FFT_SIZE],
+            fft_window: VecDeque::from(vec![0.0; FFT_SIZE]),
+            fft_window_delay: FFT_SIZE as f64 / sample_rate as f64 / 2.0,
+            last_update_us: 0,
+            last_analysis: None,
+            last_sent: None,
+        })
+    }
+
+    /// Analyze one buffer, captured `capture_delay` seconds before this call
+    pub fn process(&mut self, data: &[f32], capture_delay: f64) {
+        let Pipeline { name, processor, ws_tx, tui_tx, latency, passthrough, update_period_us, .. } = &self.pipeline;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs_f64();
+        let capture_time = clock::server_time() - capture_delay;
+        let delta_time = 1.0 / 60.0; // Approximate frame time
+        let channels = self.channels;
+
+        self.pipeline.channels.apply(data, self.device_channels, &mut self.selected);
+        let data = self.selected.as_slice();
+
+        // One lock per buffer, held through the analysis. Loudness metering needs every sample,
+        // so feed it before throttling
+        let mut processor = processor.lock().unwrap();
+        processor.process_samples(data);
+        if let Some(line) = passthrough {
+            line.push(data);
+        }
+        for frame in data.chunks_exact(channels) {
+            self.fft_window.pop_front();
+            self.fft_window.push_back(frame.iter().sum::<f32>() / channels as f32);
+        }
+
+        // Throttle logic
+        let period_us = update_period_us.load(Ordering::Relaxed);
+        if period_us > 0 {
+            let now_us = (now * 1_000_000.0) as u64;
+            if now_us < self.last_update_us + period_us {
+                return;
+            }
+            self.last_update_us = now_us;
+        }
+
+        // Run FFT over the mono window
+        for (bin, &sample) in self.fft_output.iter_mut().zip(self.fft_window.iter()) {
+            *bin = Complex::new(sample, 0.0);
+        }
+        self.fft.process(&mut self.fft_output);
+        // Compute magnitude spectrum (only first N/2 bins are real for real input)
+        let magnitudes: Vec<f32> = self.fft_output.iter().take(FFT_SIZE / 2).map(|c| c.norm()).collect();
+        let analysis_start = clock::server_time();
+        let frame_period = self.last_analysis.map_or(0.0, |last| analysis_start - last);
+        self.last_analysis = Some(analysis_start);
+        processor.capture_time = capture_time;
+        processor.pipeline_latency = latency.lock().unwrap().total();
+        let state = processor.update_base_state(delta_time, &magnitudes, now);
+        let analysis_end = clock::server_time();
+        {
+            let mut latency = latency.lock().unwrap();
+            latency.record(Stage::Capture, capture_delay);
+            latency.record(Stage::Buffer, (data.len() / channels) as f64 / self.sample_rate);
+            latency.record(Stage::FftWindow, self.fft_window_delay);
+            latency.record(Stage::Analysis, analysis_end - analysis_start);
+            latency.record(Stage::Smoothing, processor.smoothing_lag(frame_period));
+            latency.set_lookahead(processor.lookahead());
+            if let Some(line) = passthrough {
+                // The visuals are seen after the pipeline plus the display's own delay
+                line.set_visual_latency(latency.total() + processor.config.latency.output_delay as f64);
+            }
+        }
+        drop(processor);
+        let mut proto_state = ProtoState::from(&state);
+        proto_state.stream = name.clone();
+
+        // Send to WebSocket clients
+        let mut buf = Vec::new();
+        ProstMessage::encode(&proto_state, &mut buf).unwrap();
+        let sent_at = clock::server_time();
+        {
+            let mut latency = latency.lock().unwrap();
+            latency.record(Stage::Encode, sent_at - analysis_end);
+            // A change lands in the next sent frame, on average half a send interval later
+            if let Some(last) = self.last_sent {
+                latency.record(Stage::Throttle, (sent_at - last) / 2.0);
+            }
+        }
+        self.last_sent = Some(sent_at);
+        let _ = ws_tx.blocking_send((name.clone(), buf, sent_at));
+
+        // Send to TUI if enabled
+        if let Some(tui_tx) = tui_tx {
+            let _ = tui_tx.blocking_send(proto_state);
+        }
+    }
+}
+
+/// Build and start a capture stream on `device` feeding `pipeline`; its callbacks report to `health`
+pub fn build_input_stream(
+    device: &cpal::Device,
+    pipeline: &Pipeline,
+    health: &Arc<StreamHealth>,
+) -> Result<cpal::Stream, InputError> {
+    let device_error = |e: &dyn fmt::Display| InputError::Device(e.to_string());
+    let device_config = device.default_input_config().map_err(|e| device_error(&e))?;
+    let mut analyzer = Analyzer::new(pipeline, device_config.sample_rate().0, device_config.channels() as usize)?;
+    let data_health = Arc::clone(health);
+    let error_health = Arc::clone(health);
+
+    let stream = device
+        .build_input_stream(
+            &device_config.into(),
+            move |data: &[f32], info: &cpal::InputCallbackInfo| {
+                data_health.touch();
+                // The driver reports how long ago these samples were captured
+                let timestamp = info.timestamp();
+                let capture_delay = timestamp.callback.duration_since(&timestamp.capture).map_or(0.0, |d| d.as_secs_f64());
+                analyzer.process(data, capture_delay);
+            },
+            move |err| {
+                eprintln!("Audio stream error: {}", err);
+                // Other errors may be transient; if they stop the stream the stall check notices
+                if matches!(err, cpal::StreamError::DeviceNotAvailable) {
+                    error_health.lost.store(true, Ordering::Relaxed);
+                }
+            },
+            None,
+        )
+        .map_err(|e| device_error(&e))?;
+    stream.play().map_err(|e| device_error(&e))?;
+    Ok(stream)
+}
+
+enum InputCommand {
+    Switch {
+        name: String,
+        reply: std_mpsc::Sender<Result<String, InputError>>,
+    },
+}
+
+/// Handle to the thread owning the capture stream
+#[derive(Clone)]
+pub struct InputHandle {
+    commands: std_mpsc::Sender<InputCommand>,
+    device_name: Arc<Mutex<String>>,
+    channels: ChannelMap,
+}
+
+impl InputHandle {
+    /// Name of the device currently captured
+    pub fn device_name(&self) -> String {
+        self.device_name.lock().unwrap().clone()
+    }
+
+    /// Key of the current device and channel selection in the noise profile store
+    pub fn profile_key(&self) -> String {
+        self.channels.profile_key(&self.device_name())
+    }
+
+    /// Switch to another input device; returns its full name once the new stream runs.
+    /// On failure the previous device keeps running if it can be reopened. Blocks until the
+    /// input thread has tried the device, so call it off the async runtime.
+    pub fn switch(&self, name: &str) -> Result<String, InputError> {
+        let (reply, result) = std_mpsc::channel();
+        let command = InputCommand::Switch { name: name.to_string(), reply };
+        let stopped = || InputError::Device("input thread has stopped".to_string());
+        self.commands.send(command).map_err(|_| stopped())?;
+        result.recv().map_err(|_| stopped())?
+    }
+}
+
+/// Start capturing from `device` on a dedicated thread. When it fails, `fallback` (or the
+/// host's default input when `None`) is tried until `device` comes back.
+pub fn spawn_input(device: cpal::Device, fallback: Option<String>, pipeline: Pipeline) -> Result<InputHandle, InputError> {
+    let (commands, receiver) = std_mpsc::channel::<InputCommand>();
+    let (started_tx, started) = std_mpsc::channel();
+    let name = device.name().unwrap_or_else(|_| "unknown".to_string());
+    let device_name = Arc::new(Mutex::new(name.clone()));
+    let current_name = Arc::clone(&device_name);
+    let channels = pipeline.channels.clone();
+    thread::spawn(move || {
+        let health = Arc::new(StreamHealth::default());
+        health.reset();
+        let stream = match build_input_stream(&device, &pipeline, &health) {
+            Ok(stream) => {
+                let _ = started_tx.send(Ok(()));
+                stream
+            }
+            Err(e) => {
+                let _ = started_tx.send(Err(e));
+                return;
+            }
+        };
+        let mut capture = Capture {
+            pipeline,
+            health,
+            device_name: current_name,
+            current: device,
+            stream: Some(stream),
+            preferred: name,
+            fallback,
+            status: DeviceStatus::Ok,
+            attempts: 0,
+            next_retry: 0.0,
+        };
+        // Runs until every handle is dropped
+        loop {
+            match receiver.recv_timeout(HEALTH_CHECK_INTERVAL) {
+                Ok(InputCommand::Switch { name, reply }) => {
+                    let _ = reply.send(capture.switch(&name));
+                }
+                Err(std_mpsc::RecvTimeoutError::Timeout) => {}
+                Err(std_mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+            capture.check();
+        }
+    });
+    started.recv().map_err(|_| InputError::Device("input thread has stopped".to_string()))??;
+    Ok(InputHandle { commands, device_name, channels })
+}
+
+// The input thread's state
+struct Capture {
+    pipeline: Pipeline,
+    health: Arc<StreamHealth>,
+    // Shared with the handles
+    device_name: Arc<Mutex<String>>,
+    current: cpal::Device,
+    // None while the device is down
+    stream: Option<cpal::Stream>,
+    // Device last chosen by the user; recovery returns to it when it comes back
+    preferred: String,
+    fallback: Option<String>,
+    status: DeviceStatus,
+    // Failed reconnect attempts in a row
+    attempts: u32,
+    // Server time of the next reconnect attempt (or, on the fallback, the next preferred check)
+    next_retry: f64,
+}
+
+impl Capture {
+    /// Replace the stream with one on `device`. On failure no stream runs.
+    fn open(&mut self, device: cpal::Device) -> Result<String, InputError> {
+        // Release the old device first: some drivers refuse a second stream on it
+        drop(self.stream.take());
+        self.health.reset();
+        let stream = build_input_stream(&device, &self.pipeline, &self.health)?;
+        let name = device.name().unwrap_or_else(|_| "unknown".to_string());
+        self.stream = Some(stream);
+        self.current = device;
+        let old_name = std::mem::replace(&mut *self.device_name.lock().unwrap(), name.clone());
+        if old_name != name {
+            announce_switch(&self.pipeline, &old_name, &name);
+        }
+        Ok(name)
+    }
+
+    /// Go back to the current device after a failed `open`. If that fails too the stream
+    /// is reported disconnected and reconnecting starts
+    fn reopen_current(&mut self) -> Result<(), InputError> {
+        self.health.reset();
+        match build_input_stream(&self.current, &self.pipeline, &self.health) {
+            Ok(stream) => {
+                self.stream = Some(stream);
+                Ok(())
+            }
+            Err(e) => {
+                self.set_status(DeviceStatus::Disconnected);
+                self.next_retry = clock::server_time();
+                Err(e)
+            }
+        }
+    }
+
+    /// Explicit switch from a client or the TUI
+    fn switch(&mut self, name: &str) -> Result<String, InputError> {
+        let device = find_input_device(name).ok_or_else(|| InputError::NotFound(name.to_string()))?;
+        let was_running = self.stream.is_some();
+        match self.open(device) {
+            Ok(name) => {
+                self.preferred = name.clone();
+                self.attempts = 0;
+                self.set_status(DeviceStatus::Ok);
+                Ok(name)
+            }
+            Err(e) if was_running => match self.reopen_current() {
+                Ok(()) => Err(e),
+                Err(reopen) => Err(InputError::Device(format!(
+                    "{}; reopening '{}' failed as well, so no input is captured: {}",
+                    e,
+                    self.device_name.lock().unwrap(),
+                    reopen
+                ))),
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Detect a failed stream, and keep reconnecting while there is none
+    fn check(&mut self) {
+        let now = clock::server_time();
+        if self.stream.is_some() {
+            let failure = if self.health.lost.load(Ordering::Relaxed) {
+                Some(DeviceStatus::Disconnected)
+            } else if now - self.health.last_callback() > STALL_SECS {
+                Some(DeviceStatus::Stalled)
+            } else {
+                None
+            };
+            if let Some(status) = failure {
+                eprintln!("Audio input '{}' {}; reconnecting", self.device_name.lock().unwrap(), status.as_str());
+                drop(self.stream.take());
+                self.set_status(status);
+                self.attempts = 0;
+                self.next_retry = now;
+            }
+        }
+
+        if self.stream.is_none() {
+            // Keep clients updated (and the visuals decaying) while nothing is captured
+            send_silence(&self.pipeline);
+            if now >= self.next_retry {
+                self.reconnect(now);
+            }
+        } else if self.status == DeviceStatus::Fallback && now >= self.next_retry {
+            self.next_retry = now + MAX_BACKOFF_SECS;
+            if let Some(device) = find_exact_input_device(&self.preferred) {
+                match self.open(device) {
+                    Ok(name) => {
+                        println!("Audio input back on '{}'", name);
+                        self.set_status(DeviceStatus::Ok);
+                    }
+                    Err(_) => {
+                        // On failure reconnecting takes over
+                        let _ = self.reopen_current();
+                    }
+                }
+            }
+        }
+    }
+
+    // One reconnect attempt: the preferred device, then the fallback
+    fn reconnect(&mut self, now: f64) {
+        self.attempts += 1;
+        let fallback = match &self.fallback {
+            Some(name) => find_input_device(name),
+            None => cpal::default_host().default_input_device(),
+        }
+        .filter(|device| device.name().is_ok_and(|name| name != self.preferred));
+        for device in [find_exact_input_device(&self.preferred), fallback].into_iter().flatten() {
+            match self.open(device) {
+                Ok(name) => {
+                    let status = if name == self.preferred { DeviceStatus::Ok } else { DeviceStatus::Fallback };
+                    println!("Audio input recovered on '{}' after {} attempt(s)", name, self.attempts);
+                    self.set_status(status);
+                    self.attempts = 0;
+                    self.next_retry = now + MAX_BACKOFF_SECS;
+                    return;
+                }
+                Err(e) => eprintln!("Audio input retry {}: {}", self.attempts, e),
+            }
+        }
+        let backoff = INITIAL_BACKOFF_SECS * 2f64.powi(self.attempts.saturating_sub(1).min(16) as i32);
+        self.next_retry = now + backoff.min(MAX_BACKOFF_SECS);
+    }
+
+    // Publish a status change in the frames and as a `device` event
+    fn set_status(&mut self, status: DeviceStatus) {
+        if status == self.status {
+            return;
+        }
+        let mut processor = self.pipeline.processor.lock().unwrap();
+        processor.device_status = status.as_str().to_string();
+        processor.pending_events.push(AnalysisEvent {
+            kind: "device".to_string(),
+            time: clock::epoch_time(),
+            from: self.status.as_str().to_string(),
+            to: status.as_str().to_string(),
+            duration: 0.0,
+            confidence: 1.0,
+        });
+        self.status = status;
+    }
+}
+
+/// Input device by exact name only, for recovery
+fn find_exact_input_device(name: &str) -> Option<cpal::Device> {
+    cpal::default_host().input_devices().ok()?.find(|device| device.name().is_ok_and(|n| n == name))
+}
+
+/// Analyze one silent frame and send it, for when no stream is running
+fn send_silence(pipeline: &Pipeline) {
+    let magnitudes = vec![0.0; FFT_SIZE / 2];
+    let state = {
+        let mut processor = pipeline.processor.lock().unwrap();
+        processor.capture_time = clock::server_time();
+        processor.update_base_state(HEALTH_CHECK_INTERVAL.as_secs_f32(), &magnitudes, clock::epoch_time())
+    };
+    let mut proto_state = ProtoState::from(&state);
+    proto_state.stream = pipeline.name.clone();
+    let mut buf = Vec::new();
+    ProstMessage::encode(&proto_state, &mut buf).unwrap();
+    let _ = pipeline.ws_tx.blocking_send((pipeline.name.clone(), buf, clock::server_time()));
+    if let Some(tui_tx) = &pipeline.tui_tx {
+        let _ = tui_tx.blocking_send(proto_state);
+    }
+}
+
+/// Save the old device's pending noise profile, load the new one's and tell the clients
+fn announce_switch(pipeline: &Pipeline, old_name: &str, new_name: &str) {
+    let mut processor = pipeline.processor.lock().unwrap();
+    if processor.noise_profile_changed {
+        processor.noise_profile_changed = false;
+        let key = pipeline.channels.profile_key(old_name);
+        if let Err(e) = pipeline.noise_store.put(&key, processor.noise_profile.as_ref()) {
+            eprintln!("Could not save noise profile: {}", e);
+        }
+    }
+    processor.noise_profile = None;
+    match pipeline.noise_store.get(&pipeline.channels.profile_key(new_name)) {
+        Ok(Some(profile)) => processor.set_noise_profile(profile),
+        Ok(None) => {}
+        Err(e) => eprintln!("Ignoring noise profiles: {}", e),
+    }
+    processor.pending_events.push(AnalysisEvent {
+        kind: "input".to_string(),
+        time: clock::epoch_time(),
+        from: old_name.to_string(),
+        to: new_name.to_string(),
+        duration: 0.0,
+        confidence: 1.0,
+    });
+}
@@ -0,0 +1,143 @@
+// Beat-locked LFO bank. A beat clock counts beats at the detected tempo and is nudged toward
+// detected beats, and every LFO derives its phase from that clock, so all clients receive
+// identical, phase-coherent modulation.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LfoShape {
+    Sine,
+    Triangle,
+    /// Rising ramp
+    Saw,
+    Square,
+    /// New random value every cycle
+    RandomStep,
+    /// Random values with a smooth glide between cycles
+    SmoothRandom,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LfoUnit {
+    Beats,
+    Bars,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LfoDefinition {
+    pub name: String,
+    pub shape: LfoShape,
+    /// Length of one cycle in `unit`s (e.g. 0.5 beats, 4 bars)
+    pub period: f32,
+    pub unit: LfoUnit,
+    /// Phase offset in cycles (0-1)
+    #[serde(default)]
+    pub phase: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LfoConfig {
+    pub beats_per_bar: u32,
+    /// Tempo the clock runs at until beats have been detected
+    pub fallback_bpm: f32,
+    /// Fraction of the phase error to a detected beat corrected over the following beat
+    pub phase_correction: f32,
+    pub lfos: Vec<LfoDefinition>,
+}
+
+fn lfo(name: &str, shape: LfoShape, period: f32, unit: LfoUnit) -> LfoDefinition {
+    LfoDefinition { name: name.to_string(), shape, period, unit, phase: 0.0 }
+}
+
+impl Default for LfoConfig {
+    fn default() -> Self {
+        LfoConfig {
+            beats_per_bar: 4,
+            fallback_bpm: 120.0,
+            phase_correction: 0.5,
+            lfos: vec![
+                lfo("beat_sine", LfoShape::Sine, 1.0, LfoUnit::Beats),
+                lfo("beat_square", LfoShape::Square, 1.0, LfoUnit::Beats),
+                lfo("half_beat_saw", LfoShape::Saw, 0.5, LfoUnit::Beats),
+                lfo("bar_triangle", LfoShape::Triangle, 1.0, LfoUnit::Bars),
+                lfo("bar_random", LfoShape::RandomStep, 1.0, LfoUnit::Bars),
+                lfo("phrase_drift", LfoShape::SmoothRandom, 4.0, LfoUnit::Bars),
+            ],
+        }
+    }
+}
+
+/// Counts beats at the detected tempo, pulled toward detected beats
+pub struct BeatClock {
+    beats: f64,
+    // Phase correction (in beats) still to be applied
+    correction: f64,
+}
+
+impl BeatClock {
+    pub fn new() -> Self {
+        BeatClock { beats: 0.0, correction: 0.0 }
+    }
+
+    /// Beats since start
+    pub fn beats(&self) -> f64 {
+        self.beats
+    }
+
+    /// Advance by one frame. The phase error at a detected beat is worked off by running at
+    /// most 50% fast or slow instead of jumping, so the clock never runs backwards.
+    pub fn update(&mut self, delta_time: f32, bps: f32, beat_detected: bool, config: &LfoConfig) {
+        let bps = if bps > 0.1 { bps as f64 } else { config.fallback_bpm as f64 / 60.0 };
+        let advance = delta_time as f64 * bps;
+        let applied = self.correction.clamp(-0.5 * advance, 0.5 * advance);
+        self.correction -= applied;
+        self.beats += advance + applied;
+        if beat_detected {
+            let fraction = self.beats.fract();
+            let error = if fraction > 0.5 { fraction - 1.0 } else { fraction };
+            self.correction = -error * config.phase_correction as f64;
+        }
+    }
+}
+
+// Seed for the random shapes, from the LFO name so values survive reordering and restarts (FNV-1a)
+fn name_seed(name: &str) -> u64 {
+    name.bytes().fold(0xCBF2_9CE4_8422_2325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01B3))
+}
+
+// Deterministic 0-1 value for a cycle of one LFO (splitmix64)
+fn random_value(seed: u64, cycle: i64) -> f32 {
+    let mut z = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ cycle as u64;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    (z >> 40) as f32 / (1u64 << 24) as f32
+}
+
+/// Output (0-1) of one LFO at the clock position `beats`
+pub fn evaluate(definition: &LfoDefinition, beats: f64, beats_per_bar: u32) -> f32 {
+    let beats_per_cycle = match definition.unit {
+        LfoUnit::Beats => definition.period as f64,
+        LfoUnit::Bars => definition.period as f64 * beats_per_bar as f64,
+    };
+    let position = beats / beats_per_cycle.max(1e-6) + definition.phase as f64;
+    let cycle = position.floor() as i64;
+    let phase = position.fract() as f32;
+    let seed = name_seed(&definition.name);
+    match definition.shape {
+        LfoShape::Sine => 0.5 + 0.5 * (phase * std::f32::consts::TAU).sin(),
+        LfoShape::Triangle => 1.0 - (2.0 * phase - 1.0).abs(),
+        LfoShape::Saw => phase,
+        LfoShape::Square => if phase < 0.5 { 1.0 } else { 0.0 },
+        LfoShape::RandomStep => random_value(seed, cycle),
+        LfoShape::SmoothRandom => {
+            let from = random_value(seed, cycle);
+            let to = random_value(seed, cycle + 1);
+            let t = 0.5 - 0.5 * (phase * std::f32::consts::PI).cos();
+            from + (to - from) * t
+        }
+    }
+}
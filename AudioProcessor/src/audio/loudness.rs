@@ -0,0 +1,363 @@
+// ITU-R BS.1770-4 / EBU R128 loudness metering on the time-domain signal
+
+use std::collections::VecDeque;
+
+/// Loudness values below the absolute gate are reported as this floor instead of -inf
+pub const LOUDNESS_FLOOR_LUFS: f64 = -70.0;
+/// True-peak floor for digital silence
+pub const TRUE_PEAK_FLOOR_DBTP: f64 = -120.0;
+
+const BLOCK_SECONDS: f64 = 0.1; // 100 ms sub-blocks (75% overlap of 400 ms gating blocks)
+const MOMENTARY_BLOCKS: usize = 4; // 400 ms
+const SHORT_TERM_BLOCKS: usize = 30; // 3 s
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const INTEGRATED_RELATIVE_GATE_LU: f64 = -10.0;
+const LRA_RELATIVE_GATE_LU: f64 = -20.0;
+const HISTOGRAM_MAX_LUFS: f64 = 5.0;
+const HISTOGRAM_STEP_LU: f64 = 0.1;
+const HISTOGRAM_BINS: usize = ((HISTOGRAM_MAX_LUFS - ABSOLUTE_GATE_LUFS) / HISTOGRAM_STEP_LU) as usize;
+const TRUE_PEAK_OVERSAMPLING: usize = 4;
+const TRUE_PEAK_TAPS_PER_PHASE: usize = 12;
+
+/// Direct form I biquad
+#[derive(Clone)]
+struct Biquad {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+    x1: f64,
+    x2: f64,
+    y1: f64,
+    y2: f64,
+}
+
+impl Biquad {
+    fn new(b0: f64, b1: f64, b2: f64, a1: f64, a2: f64) -> Self {
+        Biquad { b0, b1, b2, a1, a2, x1: 0.0, x2: 0.0, y1: 0.0, y2: 0.0 }
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b0 * x + self.b1 * self.x1 + self.b2 * self.x2 - self.a1 * self.y1 - self.a2 * self.y2;
+        self.x2 = self.x1;
+        self.x1 = x;
+        self.y2 = self.y1;
+        self.y1 = y;
+        y
+    }
+}
+
+/// Two-stage K-weighting filter (high shelf + RLB high pass), derived for any sample rate
+#[derive(Clone)]
+struct KWeighting {
+    shelf: Biquad,
+    high_pass: Biquad,
+}
+
+impl KWeighting {
+    fn new(sample_rate: f64) -> Self {
+        // Stage 1: pre-filter modelling the acoustic effect of the head
+        let f0 = 1681.974450955533;
+        let gain_db = 3.999843853973347;
+        let q = 0.7071752369554196;
+        let k = (std::f64::consts::PI * f0 / sample_rate).tan();
+        let vh = 10f64.powf(gain_db / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        let shelf = Biquad::new(
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+            2.0 * (k * k - 1.0) / a0,
+            (1.0 - k / q + k * k) / a0,
+        );
+
+        // Stage 2: RLB weighting curve (high pass)
+        let f0 = 38.13547087602444;
+        let q = 0.5003270373238773;
+        let k = (std::f64::consts::PI * f0 / sample_rate).tan();
+        let a0 = 1.0 + k / q + k * k;
+        let high_pass = Biquad::new(
+            1.0,
+            -2.0,
+            1.0,
+            2.0 * (k * k - 1.0) / a0,
+            (1.0 - k / q + k * k) / a0,
+        );
+
+        KWeighting { shelf, high_pass }
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        self.high_pass.process(self.shelf.process(x))
+    }
+}
+
+/// Polyphase windowed-sinc interpolator used to estimate inter-sample peaks
+#[derive(Clone)]
+struct TruePeakDetector {
+    phases: Vec<Vec<f64>>,
+    history: VecDeque<f64>,
+}
+
+impl TruePeakDetector {
+    fn new() -> Self {
+        let total_taps = TRUE_PEAK_OVERSAMPLING * TRUE_PEAK_TAPS_PER_PHASE;
+        let center = (total_taps - 1) as f64 / 2.0;
+        let mut phases = vec![vec![0.0; TRUE_PEAK_TAPS_PER_PHASE]; TRUE_PEAK_OVERSAMPLING];
+        for n in 0..total_taps {
+            let t = (n as f64 - center) / TRUE_PEAK_OVERSAMPLING as f64;
+            let sinc = if t.abs() < 1e-12 { 1.0 } else { (std::f64::consts::PI * t).sin() / (std::f64::consts::PI * t) };
+            let window = 0.5 - 0.5 * (2.0 * std::f64::consts::PI * n as f64 / (total_taps - 1) as f64).cos();
+            phases[n % TRUE_PEAK_OVERSAMPLING][n / TRUE_PEAK_OVERSAMPLING] = sinc * window;
+        }
+        TruePeakDetector {
+            phases,
+            history: VecDeque::from(vec![0.0; TRUE_PEAK_TAPS_PER_PHASE]),
+        }
+    }
+
+    /// Push one sample and return the largest absolute value of its oversampled neighbourhood
+    fn process(&mut self, x: f64) -> f64 {
+        self.history.pop_front();
+        self.history.push_back(x);
+        let mut peak = x.abs();
+        for phase in &self.phases {
+            let value: f64 = phase.iter().zip(self.history.iter().rev()).map(|(c, s)| c * s).sum();
+            peak = peak.max(value.abs());
+        }
+        peak
+    }
+}
+
+/// Gated loudness histogram (0.1 LU resolution) so integrated measurements use bounded memory
+#[derive(Clone)]
+struct LoudnessHistogram {
+    counts: Vec<u64>,
+    energies: Vec<f64>,
+}
+
+impl LoudnessHistogram {
+    fn new() -> Self {
+        LoudnessHistogram {
+            counts: vec![0; HISTOGRAM_BINS],
+            energies: vec![0.0; HISTOGRAM_BINS],
+        }
+    }
+
+    fn bin_for(lufs: f64) -> Option<usize> {
+        if lufs < ABSOLUTE_GATE_LUFS {
+            return None;
+        }
+        let index = ((lufs - ABSOLUTE_GATE_LUFS) / HISTOGRAM_STEP_LU) as usize;
+        Some(index.min(HISTOGRAM_BINS - 1))
+    }
+
+    fn bin_lufs(index: usize) -> f64 {
+        ABSOLUTE_GATE_LUFS + (index as f64 + 0.5) * HISTOGRAM_STEP_LU
+    }
+
+    fn add(&mut self, energy: f64) {
+        if let Some(index) = Self::bin_for(energy_to_lufs(energy)) {
+            self.counts[index] += 1;
+            self.energies[index] += energy;
+        }
+    }
+
+    /// Mean energy of all blocks at or above `gate_lufs`
+    fn gated_mean_energy(&self, gate_lufs: f64) -> Option<f64> {
+        let start = Self::bin_for(gate_lufs).unwrap_or(0);
+        let count: u64 = self.counts[start..].iter().sum();
+        if count == 0 {
+            return None;
+        }
+        Some(self.energies[start..].iter().sum::<f64>() / count as f64)
+    }
+
+    /// Loudness value at the given percentile (0-1) of blocks at or above `gate_lufs`
+    fn percentile(&self, gate_lufs: f64, percentile: f64) -> Option<f64> {
+        let start = Self::bin_for(gate_lufs).unwrap_or(0);
+        let count: u64 = self.counts[start..].iter().sum();
+        if count == 0 {
+            return None;
+        }
+        let target = ((count as f64 - 1.0) * percentile).round() as u64;
+        let mut seen = 0;
+        for (offset, &bin_count) in self.counts[start..].iter().enumerate() {
+            seen += bin_count;
+            if seen > target {
+                return Some(Self::bin_lufs(start + offset));
+            }
+        }
+        None
+    }
+}
+
+fn energy_to_lufs(energy: f64) -> f64 {
+    if energy > 0.0 {
+        -0.691 + 10.0 * energy.log10()
+    } else {
+        f64::NEG_INFINITY
+    }
+}
+
+/// BS.1770 channel weighting: surrounds get +1.5 dB, LFE is excluded (5.1 layout)
+fn channel_weight(channel: usize, channels: usize) -> f64 {
+    if channels == 6 {
+        match channel {
+            3 => 0.0,
+            4 | 5 => 1.41,
+            _ => 1.0,
+        }
+    } else {
+        1.0
+    }
+}
+
+/// Snapshot of the current loudness measurements
+#[derive(Debug, Clone, Copy)]
+pub struct LoudnessReading {
+    pub momentary_lufs: f64,
+    pub short_term_lufs: f64,
+    pub integrated_lufs: f64,
+    pub loudness_range: f64,
+    pub true_peak_dbtp: f64,
+}
+
+/// K-weighted momentary (400 ms), short-term (3 s) and integrated loudness, loudness range and true peak
+pub struct LoudnessMeter {
+    sample_rate: f64,
+    channels: usize,
+    filters: Vec<KWeighting>,
+    peak_detectors: Vec<TruePeakDetector>,
+    block_size: usize,
+    block_position: usize,
+    block_energy: f64,
+    recent_blocks: VecDeque<f64>,
+    integrated_histogram: LoudnessHistogram,
+    short_term_histogram: LoudnessHistogram,
+    max_true_peak: f64,
+    reading: LoudnessReading,
+}
+
+impl LoudnessMeter {
+    pub fn new(sample_rate: u32, channels: u16) -> Self {
+        let sample_rate = sample_rate.max(1) as f64;
+        let channels = channels.max(1) as usize;
+        LoudnessMeter {
+            sample_rate,
+            channels,
+            filters: vec![KWeighting::new(sample_rate); channels],
+            peak_detectors: vec![TruePeakDetector::new(); channels],
+            block_size: ((sample_rate * BLOCK_SECONDS).round() as usize).max(1),
+            block_position: 0,
+            block_energy: 0.0,
+            recent_blocks: VecDeque::with_capacity(SHORT_TERM_BLOCKS),
+            integrated_histogram: LoudnessHistogram::new(),
+            short_term_histogram: LoudnessHistogram::new(),
+            max_true_peak: 0.0,
+            reading: LoudnessReading {
+                momentary_lufs: LOUDNESS_FLOOR_LUFS,
+                short_term_lufs: LOUDNESS_FLOOR_LUFS,
+                integrated_lufs: LOUDNESS_FLOOR_LUFS,
+                loudness_range: 0.0,
+                true_peak_dbtp: TRUE_PEAK_FLOOR_DBTP,
+            },
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate as u32
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.channels as u16
+    }
+
+    /// Feed interleaved samples in the meter's channel layout
+    pub fn process(&mut self, interleaved: &[f32]) {
+        for frame in interleaved.chunks_exact(self.channels) {
+            let mut weighted = 0.0;
+            for (channel, &sample) in frame.iter().enumerate() {
+                let sample = sample as f64;
+                let filtered = self.filters[channel].process(sample);
+                weighted += channel_weight(channel, self.channels) * filtered * filtered;
+                self.max_true_peak = self.max_true_peak.max(self.peak_detectors[channel].process(sample));
+            }
+            self.block_energy += weighted;
+            self.block_position += 1;
+            if self.block_position >= self.block_size {
+                self.finish_block();
+            }
+        }
+        self.reading.true_peak_dbtp = if self.max_true_peak > 0.0 {
+            (20.0 * self.max_true_peak.log10()).max(TRUE_PEAK_FLOOR_DBTP)
+        } else {
+            TRUE_PEAK_FLOOR_DBTP
+        };
+    }
+
+    pub fn reading(&self) -> LoudnessReading {
+        self.reading
+    }
+
+    fn finish_block(&mut self) {
+        let energy = self.block_energy / self.block_size as f64;
+        self.block_energy = 0.0;
+        self.block_position = 0;
+        if self.recent_blocks.len() == SHORT_TERM_BLOCKS {
+            self.recent_blocks.pop_front();
+        }
+        self.recent_blocks.push_back(energy);
+
+        if self.recent_blocks.len() >= MOMENTARY_BLOCKS {
+            let momentary_energy = self.recent_blocks.iter().rev().take(MOMENTARY_BLOCKS).sum::<f64>() / MOMENTARY_BLOCKS as f64;
+            self.reading.momentary_lufs = floor_lufs(energy_to_lufs(momentary_energy));
+            self.integrated_histogram.add(momentary_energy);
+        }
+
+        let short_term_energy = self.recent_blocks.iter().sum::<f64>() / self.recent_blocks.len() as f64;
+        self.reading.short_term_lufs = floor_lufs(energy_to_lufs(short_term_energy));
+        if self.recent_blocks.len() == SHORT_TERM_BLOCKS {
+            self.short_term_histogram.add(short_term_energy);
+        }
+
+        self.reading.integrated_lufs = self.integrated_loudness();
+        self.reading.loudness_range = self.loudness_range();
+    }
+
+    fn integrated_loudness(&self) -> f64 {
+        let Some(ungated) = self.integrated_histogram.gated_mean_energy(ABSOLUTE_GATE_LUFS) else {
+            return LOUDNESS_FLOOR_LUFS;
+        };
+        let relative_gate = energy_to_lufs(ungated) + INTEGRATED_RELATIVE_GATE_LU;
+        self.integrated_histogram
+            .gated_mean_energy(relative_gate.max(ABSOLUTE_GATE_LUFS))
+            .map(|energy| floor_lufs(energy_to_lufs(energy)))
+            .unwrap_or(LOUDNESS_FLOOR_LUFS)
+    }
+
+    /// EBU Tech 3342 loudness range: spread between the 10th and 95th percentile of gated short-term loudness
+    fn loudness_range(&self) -> f64 {
+        let Some(ungated) = self.short_term_histogram.gated_mean_energy(ABSOLUTE_GATE_LUFS) else {
+            return 0.0;
+        };
+        let gate = (energy_to_lufs(ungated) + LRA_RELATIVE_GATE_LU).max(ABSOLUTE_GATE_LUFS);
+        match (
+            self.short_term_histogram.percentile(gate, 0.10),
+            self.short_term_histogram.percentile(gate, 0.95),
+        ) {
+            (Some(low), Some(high)) => (high - low).max(0.0),
+            _ => 0.0,
+        }
+    }
+}
+
+fn floor_lufs(lufs: f64) -> f64 {
+    if lufs.is_finite() {
+        lufs.max(LOUDNESS_FLOOR_LUFS)
+    } else {
+        LOUDNESS_FLOOR_LUFS
+    }
+}
@@ -1,2 +0,0 @@
-pub mod processor;
-pub mod constants; 
\ No newline at end of file
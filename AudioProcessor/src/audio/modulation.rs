@@ -0,0 +1,468 @@
+// Modulation matrix: user-defined custom fields computed each frame from a small expression
+// language over the state fields, with per-field attack/release smoothing and optional
+// threshold triggers.
+//
+//   value     = number | name | func(args) | (expr) | -value
+//   operators = ^ (right-assoc), * /, + -, < <= > >= (give 1 or 0)
+//   names     = state fields (`kick_dynamic`), `bands.<band>.<value|dynamic|velocity|peak_hold|log>`,
+//               `lfo.<name>` and earlier custom fields (`custom.<name>`)
+
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use crate::state::{PrimaryFreq530State, SCALAR_FIELDS};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TriggerConfig {
+    /// Smoothed value at or above which the trigger turns on
+    pub on: f32,
+    /// Value below which it turns off again (defaults to `on`)
+    #[serde(default)]
+    pub off: Option<f32>,
+}
+
+/// One derived output, e.g. `{"name": "punch", "expression": "curve(clamp(0.7*kick_dynamic + 0.3*onset_strength, 0, 1), 2)", "release": 0.2}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomField {
+    pub name: String,
+    pub expression: String,
+    /// Seconds to follow a rising value (0 = instant)
+    #[serde(default)]
+    pub attack: f32,
+    /// Seconds to follow a falling value (0 = instant)
+    #[serde(default)]
+    pub release: f32,
+    /// Turn the output into a 0/1 trigger with hysteresis; each rising edge is also sent as an event
+    #[serde(default)]
+    pub trigger: Option<TriggerConfig>,
+}
+
+#[derive(Debug)]
+pub struct ExpressionError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ExpressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for ExpressionError {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Func {
+    Min,
+    Max,
+    Clamp,
+    Abs,
+    Sqrt,
+    Pow,
+    /// curve(x, k): x clamped to 0-1, raised to k (k > 1 eases in, k < 1 eases out)
+    Curve,
+    /// smoothstep(edge0, edge1, x)
+    Smoothstep,
+    /// step(edge, x): 1 when x >= edge
+    Step,
+    /// mix(a, b, t): linear interpolation
+    Mix,
+    Sin,
+    Cos,
+}
+
+impl Func {
+    fn parse(name: &str) -> Option<(Func, usize)> {
+        Some(match name {
+            "min" => (Func::Min, 2),
+            "max" => (Func::Max, 2),
+            "clamp" => (Func::Clamp, 3),
+            "abs" => (Func::Abs, 1),
+            "sqrt" => (Func::Sqrt, 1),
+            "pow" => (Func::Pow, 2),
+            "curve" => (Func::Curve, 2),
+            "smoothstep" => (Func::Smoothstep, 3),
+            "step" => (Func::Step, 2),
+            "mix" => (Func::Mix, 3),
+            "sin" => (Func::Sin, 1),
+            "cos" => (Func::Cos, 1),
+            _ => return None,
+        })
+    }
+
+    fn apply(self, args: &[f64]) -> f64 {
+        match self {
+            Func::Min => args[0].min(args[1]),
+            Func::Max => args[0].max(args[1]),
+            Func::Clamp => args[0].max(args[1]).min(args[2]),
+            Func::Abs => args[0].abs(),
+            Func::Sqrt => args[0].max(0.0).sqrt(),
+            Func::Pow => args[0].powf(args[1]),
+            Func::Curve => args[0].clamp(0.0, 1.0).powf(args[1]),
+            Func::Smoothstep => {
+                let t = ((args[2] - args[0]) / (args[1] - args[0])).clamp(0.0, 1.0);
+                t * t * (3.0 - 2.0 * t)
+            }
+            Func::Step => if args[1] >= args[0] { 1.0 } else { 0.0 },
+            Func::Mix => args[0] + (args[1] - args[0]) * args[2],
+            Func::Sin => args[0].sin(),
+            Func::Cos => args[0].cos(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Var {
+    Scalar(String),
+    Band(String, BandAttr),
+    Lfo(String),
+    /// Index of an earlier custom field
+    Custom(usize),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum BandAttr {
+    Value,
+    Dynamic,
+    Velocity,
+    PeakHold,
+    Log,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Number(f64),
+    Var(Var),
+    Neg(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+    Call(Func, Vec<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, state: &PrimaryFreq530State, custom: &[f32]) -> f64 {
+        match self {
+            Expr::Number(n) => *n,
+            Expr::Var(var) => match var {
+                Var::Scalar(name) => state.scalar(name).unwrap_or(0.0),
+                Var::Band(name, attr) => state.bands.iter().find(|band| &band.name == name).map_or(0.0, |band| match attr {
+                    BandAttr::Value => band.value,
+                    BandAttr::Dynamic => band.dynamic,
+                    BandAttr::Velocity => band.velocity,
+                    BandAttr::PeakHold => band.peak_hold,
+                    BandAttr::Log => band.log,
+                }),
+                Var::Lfo(name) => state.lfos.iter().find(|lfo| &lfo.name == name).map_or(0.0, |lfo| lfo.value),
+                Var::Custom(index) => custom.get(*index).copied().unwrap_or(0.0) as f64,
+            },
+            Expr::Neg(inner) => -inner.eval(state, custom),
+            Expr::Binary(op, left, right) => {
+                let (a, b) = (left.eval(state, custom), right.eval(state, custom));
+                let truth = |condition: bool| if condition { 1.0 } else { 0.0 };
+                match op {
+                    Op::Add => a + b,
+                    Op::Sub => a - b,
+                    Op::Mul => a * b,
+                    Op::Div => if b.abs() > 1e-12 { a / b } else { 0.0 },
+                    Op::Pow => a.powf(b),
+                    Op::Less => truth(a < b),
+                    Op::LessEqual => truth(a <= b),
+                    Op::Greater => truth(a > b),
+                    Op::GreaterEqual => truth(a >= b),
+                }
+            }
+            Expr::Call(func, args) => {
+                let values: Vec<f64> = args.iter().map(|arg| arg.eval(state, custom)).collect();
+                func.apply(&values)
+            }
+        }
+    }
+}
+
+/// Recursive-descent parser; names are resolved as they are read
+struct Parser<'a> {
+    text: &'a [u8],
+    position: usize,
+    // Names of the custom fields defined before this one
+    earlier: &'a [String],
+}
+
+impl<'a> Parser<'a> {
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, ExpressionError> {
+        Err(ExpressionError { position: self.position, message: message.into() })
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.text.get(self.position).is_some_and(|c| c.is_ascii_whitespace()) {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.text.get(self.position).copied()
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        if self.text[self.position..].starts_with(token.as_bytes()) {
+            self.position += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), ExpressionError> {
+        if self.eat(token) { Ok(()) } else { self.error(format!("expected '{}'", token)) }
+    }
+
+    fn comparison(&mut self) -> Result<Expr, ExpressionError> {
+        let left = self.sum()?;
+        // Two-character operators first
+        let op = [("<=", Op::LessEqual), (">=", Op::GreaterEqual), ("<", Op::Less), (">", Op::Greater)]
+            .into_iter()
+            .find(|(token, _)| self.eat(token))
+            .map(|(_, op)| op);
+        match op {
+            Some(op) => Ok(Expr::Binary(op, Box::new(left), Box::new(self.sum()?))),
+            None => Ok(left),
+        }
+    }
+
+    fn sum(&mut self) -> Result<Expr, ExpressionError> {
+        let mut left = self.product()?;
+        loop {
+            let op = if self.eat("+") { Op::Add } else if self.eat("-") { Op::Sub } else { return Ok(left) };
+            left = Expr::Binary(op, Box::new(left), Box::new(self.product()?));
+        }
+    }
+
+    fn product(&mut self) -> Result<Expr, ExpressionError> {
+        let mut left = self.unary()?;
+        loop {
+            let op = if self.eat("*") { Op::Mul } else if self.eat("/") { Op::Div } else { return Ok(left) };
+            left = Expr::Binary(op, Box::new(left), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, ExpressionError> {
+        if self.eat("-") {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        let base = self.primary()?;
+        if self.eat("^") {
+            return Ok(Expr::Binary(Op::Pow, Box::new(base), Box::new(self.unary()?)));
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<Expr, ExpressionError> {
+        match self.peek() {
+            Some(b'(') => {
+                self.position += 1;
+                let inner = self.comparison()?;
+                self.expect(")")?;
+                Ok(inner)
+            }
+            Some(c) if c.is_ascii_digit() || c == b'.' => {
+                let start = self.position;
+                while self.text.get(self.position).is_some_and(|c| c.is_ascii_digit() || *c == b'.') {
+                    self.position += 1;
+                }
+                let literal = std::str::from_utf8(&self.text[start..self.position]).unwrap_or("");
+                match literal.parse() {
+                    Ok(n) => Ok(Expr::Number(n)),
+                    Err(_) => Err(ExpressionError { position: start, message: format!("invalid number '{}'", literal) }),
+                }
+            }
+            Some(c) if c.is_ascii_alphabetic() || c == b'_' => {
+                let start = self.position;
+                while self.text.get(self.position).is_some_and(|c| c.is_ascii_alphanumeric() || *c == b'_' || *c == b'.') {
+                    self.position += 1;
+                }
+                let name = std::str::from_utf8(&self.text[start..self.position]).unwrap_or("").to_string();
+                if self.peek() == Some(b'(') {
+                    self.call(&name, start)
+                } else {
+                    self.variable(&name, start)
+                }
+            }
+            Some(c) => self.error(format!("unexpected '{}'", c as char)),
+            None => self.error("unexpected end of expression"),
+        }
+    }
+
+    fn call(&mut self, name: &str, start: usize) -> Result<Expr, ExpressionError> {
+        let Some((func, arity)) = Func::parse(name) else {
+            return Err(ExpressionError { position: start, message: format!("unknown function '{}'", name) });
+        };
+        self.expect("(")?;
+        let mut args = Vec::new();
+        if !self.eat(")") {
+            loop {
+                args.push(self.comparison()?);
+                if self.eat(")") {
+                    break;
+                }
+                self.expect(",")?;
+            }
+        }
+        if args.len() != arity {
+            return Err(ExpressionError {
+                position: start,
+                message: format!("{} takes {} argument(s), got {}", name, arity, args.len()),
+            });
+        }
+        Ok(Expr::Call(func, args))
+    }
+
+    fn variable(&self, name: &str, start: usize) -> Result<Expr, ExpressionError> {
+        let unknown = |message: String| Err(ExpressionError { position: start, message });
+        let parts: Vec<&str> = name.split('.').collect();
+        let var = match parts.as_slice() {
+            [field] if SCALAR_FIELDS.contains(field) => Var::Scalar(field.to_string()),
+            ["bands", band, attr] => {
+                let attr = match *attr {
+                    "value" => BandAttr::Value,
+                    "dynamic" => BandAttr::Dynamic,
+                    "velocity" => BandAttr::Velocity,
+                    "peak_hold" => BandAttr::PeakHold,
+                    "log" => BandAttr::Log,
+                    _ => return unknown(format!("unknown band attribute '{}'", attr)),
+                };
+                Var::Band(band.to_string(), attr)
+            }
+            ["lfo", lfo] => Var::Lfo(lfo.to_string()),
+            ["custom", custom] => match self.earlier.iter().position(|earlier| earlier == custom) {
+                Some(index) => Var::Custom(index),
+                None => return unknown(format!("custom field '{}' must be defined earlier", custom)),
+            },
+            _ => return unknown(format!("unknown field '{}'", name)),
+        };
+        Ok(Expr::Var(var))
+    }
+}
+
+fn parse(text: &str, earlier: &[String]) -> Result<Expr, ExpressionError> {
+    let mut parser = Parser { text: text.as_bytes(), position: 0, earlier };
+    let expr = parser.comparison()?;
+    match parser.peek() {
+        None => Ok(expr),
+        Some(c) => parser.error(format!("unexpected '{}'", c as char)),
+    }
+}
+
+/// Band and LFO names referenced by an expression, for checking against the config
+fn referenced_names(expr: &Expr, bands: &mut Vec<String>, lfos: &mut Vec<String>) {
+    match expr {
+        Expr::Var(Var::Band(name, _)) => bands.push(name.clone()),
+        Expr::Var(Var::Lfo(name)) => lfos.push(name.clone()),
+        Expr::Number(_) | Expr::Var(_) => {}
+        Expr::Neg(inner) => referenced_names(inner, bands, lfos),
+        Expr::Binary(_, left, right) => {
+            referenced_names(left, bands, lfos);
+            referenced_names(right, bands, lfos);
+        }
+        Expr::Call(_, args) => args.iter().for_each(|arg| referenced_names(arg, bands, lfos)),
+    }
+}
+
+struct CompiledField {
+    expr: Expr,
+    smoothed: f32,
+    trigger_on: bool,
+    last_fired: Option<f64>,
+}
+
+/// A rising edge of a trigger field
+pub struct TriggerEvent {
+    pub name: String,
+    pub time: f64,
+    /// Seconds since the previous rising edge (0 for the first)
+    pub interval: f64,
+}
+
+/// Compiled custom fields plus their smoothing/trigger state; `values` are the outputs
+pub struct ModulationBank {
+    fields: Vec<CompiledField>,
+    values: Vec<f32>,
+}
+
+impl ModulationBank {
+    pub fn new(definitions: &[CustomField]) -> Result<Self, ExpressionError> {
+        let mut names: Vec<String> = Vec::new();
+        let mut fields = Vec::new();
+        for definition in definitions {
+            fields.push(CompiledField { expr: parse(&definition.expression, &names)?, smoothed: 0.0, trigger_on: false, last_fired: None });
+            names.push(definition.name.clone());
+        }
+        Ok(ModulationBank { values: vec![0.0; fields.len()], fields })
+    }
+
+    /// Check every expression parses and only refers to existing bands and LFOs
+    pub fn check(definitions: &[CustomField], band_names: &[&str], lfo_names: &[&str]) -> Result<(), String> {
+        let mut earlier = Vec::new();
+        for definition in definitions {
+            let expr = parse(&definition.expression, &earlier)
+                .map_err(|e| format!("custom field '{}': {}", definition.name, e))?;
+            let (mut bands, mut lfos) = (Vec::new(), Vec::new());
+            referenced_names(&expr, &mut bands, &mut lfos);
+            if let Some(band) = bands.iter().find(|band| !band_names.contains(&band.as_str())) {
+                return Err(format!("custom field '{}' refers to unknown band '{}'", definition.name, band));
+            }
+            if let Some(lfo) = lfos.iter().find(|lfo| !lfo_names.contains(&lfo.as_str())) {
+                return Err(format!("custom field '{}' refers to unknown LFO '{}'", definition.name, lfo));
+            }
+            earlier.push(definition.name.clone());
+        }
+        Ok(())
+    }
+
+    /// Evaluate every field against this frame's state; returns the rising trigger edges
+    pub fn update(&mut self, state: &PrimaryFreq530State, definitions: &[CustomField], delta_time: f32, now: f64) -> Vec<TriggerEvent> {
+        let mut events = Vec::new();
+        for (index, (field, definition)) in self.fields.iter_mut().zip(definitions).enumerate() {
+            let target = field.expr.eval(state, &self.values) as f32;
+            let target = if target.is_finite() { target } else { 0.0 };
+            let tau = if target > field.smoothed { definition.attack } else { definition.release };
+            field.smoothed = if tau > 0.0 {
+                field.smoothed + (target - field.smoothed) * (1.0 - (-delta_time / tau).exp())
+            } else {
+                target
+            };
+
+            self.values[index] = match &definition.trigger {
+                Some(trigger) => {
+                    let off = trigger.off.unwrap_or(trigger.on);
+                    if !field.trigger_on && field.smoothed >= trigger.on {
+                        field.trigger_on = true;
+                        let interval = field.last_fired.map_or(0.0, |last| now - last);
+                        field.last_fired = Some(now);
+                        events.push(TriggerEvent { name: definition.name.clone(), time: now, interval });
+                    } else if field.trigger_on && field.smoothed < off {
+                        field.trigger_on = false;
+                    }
+                    if field.trigger_on { 1.0 } else { 0.0 }
+                }
+                None => field.smoothed,
+            };
+        }
+        events
+    }
+
+    pub fn values(&self) -> &[f32] {
+        &self.values
+    }
+}
@@ -0,0 +1,163 @@
+// Noise floor calibration: measure the per-bin spectrum of the input while it is "silent",
+// then subtract it from live spectra and derive activity thresholds for that device.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use crate::audio::calibration::CalibrationError;
+
+pub const DEFAULT_NOISE_PROFILE_FILE: &str = "noise_profiles.json";
+pub const DEFAULT_NOISE_CALIBRATION_SECS: f32 = 5.0;
+// Per-bin noise estimate is mean + this many standard deviations
+const NOISE_STD_MARGIN: f32 = 2.0;
+// Frames kept to measure what's left of the noise after subtraction (~20s at 100Hz)
+const MAX_CALIBRATION_FRAMES: usize = 2000;
+
+/// Measured noise of one input device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoiseProfile {
+    pub sample_rate: u32,
+    /// Noise magnitude per FFT bin
+    pub spectrum: Vec<f32>,
+    /// Largest RMS of the spectrum left over after subtraction during calibration
+    pub residual_amplitude: f32,
+    /// Largest mean bin energy left over after subtraction during calibration
+    pub residual_energy: f32,
+    pub frames: usize,
+}
+
+impl NoiseProfile {
+    /// Spectral subtraction with a floor so bins never go fully to zero (limits "musical noise")
+    pub fn subtract(&self, frequency_data: &[f32], over_subtraction: f32, spectral_floor: f32) -> Vec<f32> {
+        frequency_data
+            .iter()
+            .zip(self.spectrum.iter())
+            .map(|(&magnitude, &noise)| (magnitude - noise * over_subtraction).max(magnitude * spectral_floor))
+            .collect()
+    }
+
+    pub fn matches(&self, sample_rate: u32, bins: usize) -> bool {
+        self.sample_rate == sample_rate && self.spectrum.len() == bins
+    }
+}
+
+/// Accumulates spectra for a fixed time of input audio and produces a `NoiseProfile`
+pub struct NoiseCalibrator {
+    remaining_secs: f64,
+    sum: Vec<f64>,
+    sum_sq: Vec<f64>,
+    frames: Vec<Vec<f32>>,
+    frame_count: usize,
+}
+
+impl NoiseCalibrator {
+    pub fn new(seconds: f32) -> Self {
+        NoiseCalibrator {
+            remaining_secs: seconds.max(0.1) as f64,
+            sum: Vec::new(),
+            sum_sq: Vec::new(),
+            frames: Vec::new(),
+            frame_count: 0,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.remaining_secs <= 0.0
+    }
+
+    /// Count `frames` input frames at `sample_rate` toward the measured time
+    pub fn elapse(&mut self, frames: usize, sample_rate: u32) {
+        self.remaining_secs -= frames as f64 / sample_rate.max(1) as f64;
+    }
+
+    /// Add one spectrum; a change in bin count restarts the measurement
+    pub fn feed(&mut self, frequency_data: &[f32]) {
+        if self.sum.len() != frequency_data.len() {
+            self.sum = vec![0.0; frequency_data.len()];
+            self.sum_sq = vec![0.0; frequency_data.len()];
+            self.frames.clear();
+            self.frame_count = 0;
+        }
+        for (i, &magnitude) in frequency_data.iter().enumerate() {
+            self.sum[i] += magnitude as f64;
+            self.sum_sq[i] += (magnitude as f64).powi(2);
+        }
+        if self.frames.len() < MAX_CALIBRATION_FRAMES {
+            self.frames.push(frequency_data.to_vec());
+        }
+        self.frame_count += 1;
+    }
+
+    pub fn finish(&self, sample_rate: u32, over_subtraction: f32, spectral_floor: f32) -> Option<NoiseProfile> {
+        if self.frame_count == 0 {
+            return None;
+        }
+        let n = self.frame_count as f64;
+        let spectrum: Vec<f32> = self
+            .sum
+            .iter()
+            .zip(self.sum_sq.iter())
+            .map(|(&sum, &sum_sq)| {
+                let mean = sum / n;
+                let std = (sum_sq / n - mean * mean).max(0.0).sqrt();
+                (mean + NOISE_STD_MARGIN as f64 * std) as f32
+            })
+            .collect();
+        let mut profile = NoiseProfile {
+            sample_rate,
+            spectrum,
+            residual_amplitude: 0.0,
+            residual_energy: 0.0,
+            frames: self.frame_count,
+        };
+        for frame in &self.frames {
+            let cleaned = profile.subtract(frame, over_subtraction, spectral_floor);
+            let energy = cleaned.iter().map(|x| x * x).sum::<f32>() / cleaned.len().max(1) as f32;
+            profile.residual_energy = profile.residual_energy.max(energy);
+            profile.residual_amplitude = profile.residual_amplitude.max(energy.sqrt());
+        }
+        Some(profile)
+    }
+}
+
+/// Noise profiles keyed by input device name, in one JSON file
+pub struct NoiseProfileStore {
+    path: PathBuf,
+    // Held across each read-modify-write, since every stream saves to the same file
+    write_lock: Mutex<()>,
+}
+
+impl NoiseProfileStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        NoiseProfileStore { path: path.into(), write_lock: Mutex::new(()) }
+    }
+
+    fn load_all(&self) -> Result<BTreeMap<String, NoiseProfile>, CalibrationError> {
+        match fs::read_to_string(&self.path) {
+            Ok(text) => serde_json::from_str(&text).map_err(CalibrationError::Parse),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(e) => Err(CalibrationError::Io(e)),
+        }
+    }
+
+    pub fn get(&self, device: &str) -> Result<Option<NoiseProfile>, CalibrationError> {
+        Ok(self.load_all()?.remove(device))
+    }
+
+    /// Store (or with `None`, forget) the profile for a device
+    pub fn put(&self, device: &str, profile: Option<&NoiseProfile>) -> Result<(), CalibrationError> {
+        let _guard = self.write_lock.lock().unwrap();
+        let mut profiles = self.load_all()?;
+        match profile {
+            Some(profile) => profiles.insert(device.to_string(), profile.clone()),
+            None => profiles.remove(device),
+        };
+        let text = serde_json::to_string(&profiles).map_err(CalibrationError::Parse)?;
+        // Temp file + rename, so a crash mid-write can't truncate every device's profile
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, text).map_err(CalibrationError::Io)?;
+        fs::rename(&tmp, &self.path).map_err(CalibrationError::Io)
+    }
+}
@@ -0,0 +1,141 @@
+// Optional pass-through: the captured audio is played on an output device through a delay
+// line, so the audience hears it together with the (later) visuals instead of ahead of them.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// Longest delay the line holds, in seconds
+pub const MAX_DELAY: f32 = 2.0;
+/// Step for the TUI's delay keys, in seconds
+pub const DELAY_STEP: f32 = 0.005;
+// Drift from the target (seconds) tolerated before samples are dropped or silence inserted
+const DRIFT_TOLERANCE: f64 = 0.010;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DelaySetting {
+    /// Fixed delay in seconds
+    Fixed(f32),
+    /// Follow the measured visual latency, minus the output device's own latency
+    Auto,
+}
+
+impl DelaySetting {
+    /// `auto` or a number of seconds, as given on the command line
+    pub fn parse(text: &str) -> Result<Self, String> {
+        if text == "auto" {
+            return Ok(DelaySetting::Auto);
+        }
+        match text.parse::<f32>() {
+            Ok(seconds) if (0.0..=MAX_DELAY).contains(&seconds) => Ok(DelaySetting::Fixed(seconds)),
+            _ => Err(format!("delay must be 'auto' or seconds within 0..={} (got '{}')", MAX_DELAY, text)),
+        }
+    }
+}
+
+struct DelayState {
+    // Interleaved input samples waiting to be played
+    buffer: VecDeque<f32>,
+    input_channels: usize,
+    setting: DelaySetting,
+    // Capture-to-display latency of the visuals, for `Auto`
+    visual_latency: f64,
+    // Latency the output device reports between its callback and playback
+    output_latency: f64,
+}
+
+pub struct DelayLine {
+    sample_rate: u32,
+    state: Mutex<DelayState>,
+}
+
+impl DelayLine {
+    pub fn new(sample_rate: u32, input_channels: u16, setting: DelaySetting) -> Self {
+        DelayLine {
+            sample_rate,
+            state: Mutex::new(DelayState {
+                buffer: VecDeque::new(),
+                input_channels: input_channels.max(1) as usize,
+                setting,
+                visual_latency: 0.0,
+                output_latency: 0.0,
+            }),
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Follow a new input's channel count; what is buffered is dropped
+    pub fn set_input_channels(&self, channels: u16) {
+        let mut state = self.state.lock().unwrap();
+        state.input_channels = channels.max(1) as usize;
+        state.buffer.clear();
+    }
+
+    pub fn setting(&self) -> DelaySetting {
+        self.state.lock().unwrap().setting
+    }
+
+    pub fn set(&self, setting: DelaySetting) {
+        self.state.lock().unwrap().setting = setting;
+    }
+
+    /// Feed the measured capture-to-display latency of the visuals (used by `Auto`)
+    pub fn set_visual_latency(&self, seconds: f64) {
+        self.state.lock().unwrap().visual_latency = seconds;
+    }
+
+    /// Seconds the line currently aims to hold
+    pub fn delay(&self) -> f32 {
+        Self::target(&self.state.lock().unwrap()) as f32
+    }
+
+    fn target(state: &DelayState) -> f64 {
+        match state.setting {
+            DelaySetting::Fixed(seconds) => seconds as f64,
+            DelaySetting::Auto => (state.visual_latency - state.output_latency).clamp(0.0, MAX_DELAY as f64),
+        }
+    }
+
+    /// Queue one input callback's interleaved samples
+    pub fn push(&self, data: &[f32]) {
+        let mut state = self.state.lock().unwrap();
+        state.buffer.extend(data.iter().copied());
+        // Never hold more than the longest delay plus some headroom
+        let limit = ((MAX_DELAY as f64 + 1.0) * self.sample_rate as f64) as usize * state.input_channels;
+        let excess = state.buffer.len().saturating_sub(limit);
+        state.buffer.drain(..excess);
+    }
+
+    /// Fill an output callback's interleaved buffer. `output_latency` is the device-reported
+    /// time until these samples are heard. Output channels take input channels round-robin.
+    pub fn pull(&self, out: &mut [f32], output_channels: usize, output_latency: f64) {
+        let mut state = self.state.lock().unwrap();
+        state.output_latency = output_latency;
+        let output_channels = output_channels.max(1);
+        let frames = out.len() / output_channels;
+        let input_channels = state.input_channels;
+        let buffered = state.buffer.len() / input_channels;
+        let target = (Self::target(&state) * self.sample_rate as f64) as usize;
+        let tolerance = (DRIFT_TOLERANCE * self.sample_rate as f64) as usize;
+
+        if buffered > target + frames + tolerance {
+            // Too far behind: skip ahead to the target
+            let skip = buffered - target - frames;
+            state.buffer.drain(..skip * input_channels);
+        } else if buffered + tolerance < target + frames {
+            // Not enough delay yet (start-up or a longer setting): hold back with silence
+            out.fill(0.0);
+            return;
+        }
+
+        for frame in out.chunks_mut(output_channels) {
+            for (channel, sample) in frame.iter_mut().enumerate() {
+                *sample = state.buffer.get(channel % input_channels).copied().unwrap_or(0.0);
+            }
+            let consumed = input_channels.min(state.buffer.len());
+            state.buffer.drain(..consumed);
+        }
+    }
+}
@@ -0,0 +1,139 @@
+// Playback state machine: tells "quiet passage" apart from "gap between tracks",
+// "music stopped" and "input is dead", and records the transitions as events.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaybackState {
+    /// Input is digitally silent (disconnected, muted or dead driver)
+    Silent,
+    /// Only the noise floor is present; music has stopped
+    Idle,
+    Playing,
+    /// Short silence after playing, e.g. between two tracks
+    Gap,
+}
+
+impl PlaybackState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PlaybackState::Silent => "silent",
+            PlaybackState::Idle => "idle",
+            PlaybackState::Playing => "playing",
+            PlaybackState::Gap => "gap",
+        }
+    }
+}
+
+/// Hold times (seconds) and the level that counts as a dead input
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlaybackConfig {
+    /// Raw amplitude at or below which the input counts as digitally silent
+    pub dead_threshold: f32,
+    /// Sound needed before switching to playing
+    pub play_hold: f32,
+    /// Silence needed before playing becomes a gap
+    pub gap_hold: f32,
+    /// Silence needed before a gap becomes idle
+    pub idle_hold: f32,
+    /// Digital silence needed before the input counts as silent
+    pub silent_hold: f32,
+}
+
+impl Default for PlaybackConfig {
+    fn default() -> Self {
+        PlaybackConfig {
+            dead_threshold: 1e-7,
+            play_hold: 0.15,
+            gap_hold: 0.5,
+            idle_hold: 8.0,
+            silent_hold: 2.0,
+        }
+    }
+}
+
+/// A change of playback state
+#[derive(Debug, Clone)]
+pub struct PlaybackTransition {
+    pub from: PlaybackState,
+    pub to: PlaybackState,
+    pub time: f64,
+    /// Seconds spent in `from`
+    pub duration: f64,
+}
+
+pub struct PlaybackTracker {
+    state: PlaybackState,
+    entered_at: Option<f64>,
+    sound_time: f32,
+    quiet_time: f32,
+    dead_time: f32,
+}
+
+impl PlaybackTracker {
+    pub fn new() -> Self {
+        PlaybackTracker {
+            state: PlaybackState::Idle,
+            entered_at: None,
+            sound_time: 0.0,
+            quiet_time: 0.0,
+            dead_time: 0.0,
+        }
+    }
+
+    pub fn state(&self) -> PlaybackState {
+        self.state
+    }
+
+    /// Advance by one frame; returns the transition if the state changed
+    pub fn update(
+        &mut self,
+        raw_amplitude: f32,
+        silence_threshold: f32,
+        delta_time: f32,
+        now: f64,
+        config: &PlaybackConfig,
+    ) -> Option<PlaybackTransition> {
+        let entered_at = *self.entered_at.get_or_insert(now);
+        if raw_amplitude > silence_threshold {
+            self.sound_time += delta_time;
+            self.quiet_time = 0.0;
+            self.dead_time = 0.0;
+        } else {
+            self.sound_time = 0.0;
+            self.quiet_time += delta_time;
+            if raw_amplitude <= config.dead_threshold {
+                self.dead_time += delta_time;
+            } else {
+                self.dead_time = 0.0;
+            }
+        }
+
+        let sounding = self.sound_time >= config.play_hold;
+        let dead = self.dead_time >= config.silent_hold;
+        let next = match self.state {
+            PlaybackState::Playing if self.quiet_time >= config.gap_hold => PlaybackState::Gap,
+            PlaybackState::Playing => PlaybackState::Playing,
+            _ if sounding => PlaybackState::Playing,
+            PlaybackState::Gap if dead => PlaybackState::Silent,
+            PlaybackState::Gap if self.quiet_time >= config.idle_hold => PlaybackState::Idle,
+            PlaybackState::Idle if dead => PlaybackState::Silent,
+            // Noise is back without music: the input is alive again
+            PlaybackState::Silent if self.quiet_time > 0.0 && self.dead_time == 0.0 => PlaybackState::Idle,
+            state => state,
+        };
+        if next == self.state {
+            return None;
+        }
+        let transition = PlaybackTransition {
+            from: self.state,
+            to: next,
+            time: now,
+            duration: (now - entered_at).max(0.0),
+        };
+        self.state = next;
+        self.entered_at = Some(now);
+        Some(transition)
+    }
+}
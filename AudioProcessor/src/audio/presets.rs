@@ -0,0 +1,65 @@
+// Named analysis presets: one pretty-printed ProcessorConfig JSON file per preset in a directory,
+// so they can be edited, diffed and version-controlled like any other text file.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::audio::config::{ConfigError, ProcessorConfig};
+
+pub const DEFAULT_PRESET_DIR: &str = "presets";
+const PRESET_EXTENSION: &str = "json";
+
+#[derive(Clone)]
+pub struct PresetStore {
+    dir: PathBuf,
+}
+
+impl PresetStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        PresetStore { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Preset names (file stems), sorted
+    pub fn list(&self) -> Result<Vec<String>, ConfigError> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(ConfigError::Io(e)),
+        };
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == PRESET_EXTENSION))
+            .filter_map(|path| path.file_stem().and_then(|stem| stem.to_str()).map(str::to_string))
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    pub fn load(&self, name: &str) -> Result<ProcessorConfig, ConfigError> {
+        ProcessorConfig::load(&self.path_for(name)?)
+    }
+
+    pub fn save(&self, name: &str, config: &ProcessorConfig) -> Result<(), ConfigError> {
+        config.validate()?;
+        let path = self.path_for(name)?;
+        fs::create_dir_all(&self.dir).map_err(ConfigError::Io)?;
+        config.save(&path)
+    }
+
+    /// Names are plain identifiers so a control message can't point outside the preset directory
+    fn path_for(&self, name: &str) -> Result<PathBuf, ConfigError> {
+        let valid = !name.is_empty()
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(ConfigError::Invalid(format!(
+                "preset name '{}' may only contain letters, digits, '-' and '_'",
+                name
+            )));
+        }
+        Ok(self.dir.join(format!("{}.{}", name, PRESET_EXTENSION)))
+    }
+}
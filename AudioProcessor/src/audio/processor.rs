@@ -1,8 +1,24 @@
-use crate::state::PrimaryFreq530State;
+use crate::state::{AnalysisEvent, BandState, NamedValue, PrimaryFreq530State};
+use crate::audio::loudness::LoudnessMeter;
+use crate::audio::stereo::StereoAnalyzer;
+use crate::audio::spectral::{self, DescriptorTracker, CONTRAST_OCTAVES};
+use crate::audio::bands::{BandDefinition, BandOutput, BandResponse, BandTracker};
+use crate::audio::config::{ConfigError, EnvelopeConfig, HistoryConfig, ProcessorConfig};
+use crate::audio::calibration::{BandCalibration, CalibrationSnapshot};
+use crate::audio::noise::{NoiseCalibrator, NoiseProfile};
+use crate::audio::playback::{PlaybackState, PlaybackTracker, PlaybackTransition};
+use crate::audio::structure::{StructureDetector, StructureEvent, StructureFeatures};
+use crate::audio::segments::{SectionBoundary, SectionTracker};
+use crate::audio::lfo::{self, BeatClock};
+use crate::audio::modulation::{ModulationBank, TriggerEvent};
+use std::collections::VecDeque;
 
 // Define GRID_MAP_SIZE, e.g., for a 16x16 grid representation
 const GRID_MAP_SIZE: usize = 256;
 
+// Mono time-domain samples kept for zero-crossing rate (matches the FFT size)
+const TIME_DOMAIN_WINDOW: usize = 1024;
+
 // Define SPECTROGRAM_WIDTH and HEIGHT for the rolling buffer
 const SPECTROGRAM_WIDTH: usize = 256;
 const SPECTROGRAM_HEIGHT: usize = 64;
@@ -17,7 +33,6 @@ pub enum DetailLevel {
 
 pub struct GainState {
     pub value: f32,
-    pub gain: f32,
 }
 
 pub struct CircularBuffer {
@@ -43,56 +58,61 @@ impl CircularBuffer {
     pub fn values(&self) -> &[f32] {
         &self.buffer
     }
+
+    /// Values from oldest to newest
+    pub fn chronological(&self) -> Vec<f32> {
+        self.buffer[self.index..].iter().chain(self.buffer[..self.index].iter()).copied().collect()
+    }
+
+    /// Replace the contents with `values` (oldest to newest), keeping the capacity
+    pub fn fill_from(&mut self, values: &[f32]) {
+        self.buffer.fill(0.0);
+        self.index = 0;
+        for &value in &values[values.len().saturating_sub(self.capacity)..] {
+            self.push(value);
+        }
+    }
+
+    /// Change the capacity, keeping the most recent values in order
+    pub fn resize(&mut self, capacity: usize) {
+        let capacity = capacity.max(1);
+        if capacity == self.capacity {
+            return;
+        }
+        let recent = self.chronological();
+        let mut buffer = vec![0.0; capacity - recent.len().min(capacity)];
+        buffer.extend_from_slice(&recent[recent.len().saturating_sub(capacity)..]);
+        self.buffer = buffer;
+        self.index = 0;
+        self.capacity = capacity;
+    }
 }
 
 pub struct HistoryState {
     pub buffer: CircularBuffer,
-    pub min: f32,
-    pub max: f32,
 }
 
 impl HistoryState {
     pub fn new(size: usize) -> Self {
         HistoryState {
             buffer: CircularBuffer::new(size),
-            min: f32::INFINITY,
-            max: f32::NEG_INFINITY,
         }
     }
 
-    pub fn update_min_max(&mut self, new_value: f32) {
-        if new_value > self.max {
-            self.max = new_value;
-        }
-        if new_value < self.min {
-            self.min = new_value;
-        }
-        let old_value = self.buffer.values()[0];
-        if old_value == self.min || old_value == self.max {
-            let values = self.buffer.values();
-            self.min = values.iter().copied().fold(f32::INFINITY, f32::min).min(new_value);
-            self.max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max).max(new_value);
-        }
+    pub fn resize(&mut self, size: usize) {
+        self.buffer.resize(size);
     }
 }
 
 pub struct AudioProcessor {
     pub detail_level: DetailLevel,
-    pub low_history: HistoryState,
-    pub mid_history: HistoryState,
-    pub high_history: HistoryState,
-    pub kick_history: HistoryState,
-    pub snare_history: HistoryState,
-    pub hihat_history: HistoryState,
+    // Runtime tunables; change through `apply_config` so dependent state follows
+    pub config: ProcessorConfig,
+    // Configured frequency bands, each with its own value/dynamic/velocity/peak-hold state
+    pub band_trackers: Vec<BandTracker>,
     pub vocal_history: HistoryState,
     pub amplitude_history: HistoryState,
     pub raw_amplitude_history: HistoryState,
-    pub prev_low: f32,
-    pub prev_mid: f32,
-    pub prev_high: f32,
-    pub prev_kick: f32,
-    pub prev_snare: f32,
-    pub prev_hihat: f32,
     pub prev_amplitude: f32,
     pub prev_raw_amplitude: f32,
     pub snare_average: f32,
@@ -115,22 +135,8 @@ pub struct AudioProcessor {
     pub low_gain: f32,
     pub mid_gain: f32,
     pub high_gain: f32,
-    pub last_update: f64,
-    pub pending_state: Option<PrimaryFreq530State>,
-    pub max_low: f32,
-    pub max_mid: f32,
-    pub max_high: f32,
-    pub max_kick: f32,
-    pub max_snare: f32,
-    pub max_hihat: f32,
     pub max_amplitude: f32,
     pub max_raw_amplitude: f32,
-    pub low_dynamic_smoothed: f32,
-    pub mid_dynamic_smoothed: f32,
-    pub high_dynamic_smoothed: f32,
-    pub kick_dynamic_smoothed: f32,
-    pub snare_dynamic_smoothed: f32,
-    pub hihat_dynamic_smoothed: f32,
     pub amplitude_dynamic_smoothed: f32,
     pub raw_amplitude_dynamic_smoothed: f32,
     pub fade_in_out: f32,
@@ -143,28 +149,11 @@ pub struct AudioProcessor {
     pub silence_counter: i32,           // Counts frames of silence for fade-to-zero
     pub activity_smoothing: f32,        // Smoothed activity level
     // New enhanced envelope fields
-    pub amplitude_envelope: AmplitudeEnvelope,    // Enhanced envelope system
+    pub amplitude_envelope: EnvelopeFollower,     // Enhanced envelope system
     pub smoothed_amplitude: f32,                  // The final smoothed amplitude output
     pub amplitude_velocity: f32,                  // Rate of change for momentum-based smoothing
-    // Expanded histories for velocities (rate of change) per band
-    pub low_velocity_history: HistoryState,
-    pub mid_velocity_history: HistoryState,
-    pub high_velocity_history: HistoryState,
-    pub kick_velocity_history: HistoryState,
-    pub snare_velocity_history: HistoryState,
-    pub hihat_velocity_history: HistoryState,
-    // Peak-hold values with decay for each band (useful for "spiked" visuals)
-    pub low_peak_hold: f32,
-    pub mid_peak_hold: f32,
-    pub high_peak_hold: f32,
-    pub kick_peak_hold: f32,
-    pub snare_peak_hold: f32,
-    pub hihat_peak_hold: f32,
+    // Peak-hold value with decay for amplitude (bands keep their own in `band_trackers`)
     pub amplitude_peak_hold: f32,
-    // Log-scaled versions for perceptual uniformity in visuals
-    pub low_log: f32,
-    pub mid_log: f32,
-    pub high_log: f32,
     // Balance/difference metrics (e.g., low-mid balance for stereo-like effects)
     pub low_mid_balance: f32,
     pub mid_high_balance: f32,
@@ -173,53 +162,81 @@ pub struct AudioProcessor {
     pub prev_onset_strength: f32,
     // Rolling spectrogram buffer for data-based texture (no PNG)
     pub spectrogram_buffer: Vec<Vec<f32>>,
+    // Standard loudness metering on the time-domain input
+    pub loudness: LoudnessMeter,
+    // Stereo image analysis on the first two input channels
+    pub stereo: StereoAnalyzer,
+    // Recent mono-downmixed time-domain samples
+    pub recent_samples: VecDeque<f32>,
+    // Timbre descriptor histories for the dynamic variants
+    pub rolloff_85_tracker: DescriptorTracker,
+    pub rolloff_95_tracker: DescriptorTracker,
+    pub flatness_tracker: DescriptorTracker,
+    pub bandwidth_tracker: DescriptorTracker,
+    pub zcr_tracker: DescriptorTracker,
+    pub contrast_trackers: Vec<DescriptorTracker>,
+    // Measured noise of the current input, subtracted from every spectrum
+    pub noise_profile: Option<NoiseProfile>,
+    pub noise_calibrator: Option<NoiseCalibrator>,
+    // Set when the noise profile changes so the owner can persist it
+    pub noise_profile_changed: bool,
+    // Silent/idle/playing/gap detection
+    pub playback: PlaybackTracker,
+    // Build-up tension and drop/breakdown detection
+    pub structure: StructureDetector,
+    // Novelty-based section boundaries and repeat labels
+    pub sections: SectionTracker,
+    // Beat counter driving the LFO bank
+    pub beat_clock: BeatClock,
+    // User-defined custom fields evaluated over each frame's state
+    pub modulation: ModulationBank,
+    // One follower per entry in `config.field_envelopes`, with the parameters it resolves to
+    pub field_followers: Vec<(EnvelopeFollower, EnvelopeConfig)>,
+    // Server-clock time (see `clock::server_time`) the samples being analyzed were captured;
+    // set by the owner before each `update_base_state`
+    pub capture_time: f64,
+    // Number of frames emitted so far
+    pub frame_sequence: u64,
+    // Measured capture-to-send latency in seconds, set by the owner; used when
+    // `latency.compensate_pipeline` is on
+    pub pipeline_latency: f64,
+    // Events not yet delivered in a sent frame
+    pub pending_events: Vec<AnalysisEvent>,
+    // Health of the capture device ("ok", "stalled", ...), set by the input thread
+    pub device_status: String,
 }
 
-// New envelope system for sophisticated amplitude smoothing
-pub struct AmplitudeEnvelope {
+// Envelope follower with attack/decay, momentum, peak-hold and transient boost. Drives
+// `amplitude`, and one can be attached to any other field through `field_envelopes`.
+// Rates and adaptivity come from `EnvelopeConfig`; this only holds the running state
+pub struct EnvelopeFollower {
     pub current_value: f32,
     pub target_value: f32,
-    pub attack_rate: f32,          // How fast to increase (0.0 = instant, 1.0 = very slow)
-    pub decay_rate: f32,           // How fast to decrease (0.0 = instant, 1.0 = very slow)
-    pub adaptive_attack: bool,     // Whether to adapt attack rate based on audio content
-    pub adaptive_decay: bool,      // Whether to adapt decay rate based on audio content
-    pub momentum_factor: f32,      // Adds inertia to changes for smoother motion
-    pub peak_hold_time: f32,       // How long to hold peak values (in seconds)
     pub peak_hold_counter: f32,    // Current peak hold countdown
     pub last_peak_value: f32,      // Last significant peak value
     pub transient_boost: f32,      // Temporary boost for transients
-    pub transient_decay: f32,      // How fast transient boost decays
 }
 
-impl AmplitudeEnvelope {
+impl EnvelopeFollower {
     pub fn new() -> Self {
-        let params = &crate::audio::constants::CONSTANTS.amplitude_envelope_parameters;
-        AmplitudeEnvelope {
+        EnvelopeFollower {
             current_value: 0.0,
             target_value: 0.0,
-            attack_rate: params.default_attack_rate,
-            decay_rate: params.default_decay_rate,
-            adaptive_attack: true,
-            adaptive_decay: true,
-            momentum_factor: params.default_momentum_factor,
-            peak_hold_time: params.default_peak_hold_time,
             peak_hold_counter: 0.0,
             last_peak_value: 0.0,
             transient_boost: 0.0,
-            transient_decay: params.transient_decay_rate,
         }
     }
 
     /// Update the envelope with a new target value and delta time
-    /// Returns the smoothed amplitude value
-    pub fn update(&mut self, target: f32, delta_time: f32, spectral_flux: f32, beat_intensity: f32) -> f32 {
+    /// Returns the smoothed value (0-1)
+    pub fn update(&mut self, target: f32, delta_time: f32, spectral_flux: f32, beat_intensity: f32, params: &EnvelopeConfig) -> f32 {
         self.target_value = target;
         
         // Handle peak detection and holding
-        let params = &crate::audio::constants::CONSTANTS.amplitude_envelope_parameters;
         if target > self.last_peak_value * params.peak_detection_threshold {
             self.last_peak_value = target;
-            self.peak_hold_counter = self.peak_hold_time;
+            self.peak_hold_counter = params.peak_hold_time;
             
             // Add transient boost for sudden increases
             if target > self.current_value * 1.5 {
@@ -237,10 +254,10 @@ impl AmplitudeEnvelope {
         }
         
         // Adaptive rate calculation based on audio characteristics
-        let mut effective_attack_rate = self.attack_rate;
-        let mut effective_decay_rate = self.decay_rate;
+        let mut effective_attack_rate = params.attack_rate;
+        let mut effective_decay_rate = params.decay_rate;
         
-        if self.adaptive_attack {
+        if params.adaptive_attack {
             // Faster attack for high spectral flux (sudden changes)
             effective_attack_rate += spectral_flux * params.adaptive_attack_multiplier;
             // Faster attack for strong beats
@@ -248,7 +265,7 @@ impl AmplitudeEnvelope {
             effective_attack_rate = effective_attack_rate.min(0.8); // Cap at reasonable maximum
         }
         
-        if self.adaptive_decay {
+        if params.adaptive_decay {
             // Slower decay for sustained energy (low spectral flux but high amplitude)
             if spectral_flux < 0.1 && target > 0.3 {
                 effective_decay_rate *= 1.0 - params.adaptive_decay_multiplier; // Slower decay for sustained content
@@ -271,62 +288,21 @@ impl AmplitudeEnvelope {
         let change = difference * rate;
         
         // Add momentum-based smoothing
-        let momentum_change = change * self.momentum_factor;
+        let momentum_change = change * params.momentum_factor;
         self.current_value += momentum_change;
         
         // Apply transient boost and decay it
         self.current_value += self.transient_boost;
-        self.transient_boost *= self.transient_decay;
+        self.transient_boost *= params.transient_decay_rate;
         
         // Clamp to valid range
         self.current_value = self.current_value.clamp(0.0, 1.0);
         
         self.current_value
     }
-    
-    /// Configure the envelope for different responsiveness profiles
-    pub fn set_profile(&mut self, profile: EnvelopeProfile) {
-        match profile {
-            EnvelopeProfile::Smooth => {
-                self.attack_rate = 0.08;
-                self.decay_rate = 0.03;
-                self.momentum_factor = 0.9;
-                self.peak_hold_time = 0.15;
-            },
-            EnvelopeProfile::Responsive => {
-                self.attack_rate = 0.25;
-                self.decay_rate = 0.12;
-                self.momentum_factor = 0.6;
-                self.peak_hold_time = 0.05;
-            },
-            EnvelopeProfile::Punchy => {
-                self.attack_rate = 0.4;
-                self.decay_rate = 0.08;
-                self.momentum_factor = 0.4;
-                self.peak_hold_time = 0.08;
-            },
-            EnvelopeProfile::Sustained => {
-                self.attack_rate = 0.12;
-                self.decay_rate = 0.02;
-                self.momentum_factor = 0.95;
-                self.peak_hold_time = 0.25;
-            },
-        }
-    }
-}
-
-#[derive(Debug, Clone, Copy)]
-pub enum EnvelopeProfile {
-    Smooth,      // Very smooth, good for ambient/chill music
-    Responsive,  // Balanced, good for most music
-    Punchy,      // Fast attack, good for electronic/dance music
-    Sustained,   // Slow changes, good for classical/orchestral
 }
 
 pub struct BeatDetectionState {
-    pub kick_average: f32,
-    pub snare_average: f32,
-    pub hihat_average: f32,
     pub is_beat_candidate: bool,
     pub combined_ratio: f32,
     pub time_since_last_beat: f64,
@@ -337,24 +313,15 @@ pub struct BeatDetectionState {
 use crate::audio::constants::CONSTANTS;
 
 impl AudioProcessor {
-    pub fn new() -> Self {
+
+    pub fn with_config(config: ProcessorConfig) -> Self {
+        let windows = config.history.clone();
         AudioProcessor {
             detail_level: DetailLevel::Basic,
-            low_history: HistoryState::new(CONSTANTS.history_window_size.freq_history_window * 2),
-            mid_history: HistoryState::new(CONSTANTS.history_window_size.freq_history_window * 2),
-            high_history: HistoryState::new(CONSTANTS.history_window_size.freq_history_window * 2),
-            kick_history: HistoryState::new(CONSTANTS.history_window_size.beat_history_window),
-            snare_history: HistoryState::new(CONSTANTS.history_window_size.beat_history_window),
-            hihat_history: HistoryState::new(CONSTANTS.history_window_size.beat_history_window),
-            vocal_history: HistoryState::new(CONSTANTS.history_window_size.vocal_history_window),
-            amplitude_history: HistoryState::new(CONSTANTS.history_window_size.freq_history_window),
-            raw_amplitude_history: HistoryState::new(CONSTANTS.history_window_size.freq_history_window),
-            prev_low: 0.0,
-            prev_mid: 0.0,
-            prev_high: 0.0,
-            prev_kick: 0.0,
-            prev_snare: 0.0,
-            prev_hihat: 0.0,
+            band_trackers: Self::create_band_trackers(&config.bands, &windows),
+            vocal_history: HistoryState::new(windows.vocal_history_window),
+            amplitude_history: HistoryState::new(windows.freq_history_window),
+            raw_amplitude_history: HistoryState::new(windows.freq_history_window),
             prev_amplitude: 0.0,
             prev_raw_amplitude: 0.0,
             snare_average: 0.0,
@@ -377,22 +344,8 @@ impl AudioProcessor {
             low_gain: 1.0,
             mid_gain: 1.0,
             high_gain: 1.0,
-            last_update: 0.0,
-            pending_state: None,
-            max_low: 1.0,
-            max_mid: 1.0,
-            max_high: 1.0,
-            max_kick: 1.0,
-            max_snare: 1.0,
-            max_hihat: 1.0,
             max_amplitude: 1.0,
             max_raw_amplitude: 1.0,
-            low_dynamic_smoothed: 0.5,
-            mid_dynamic_smoothed: 0.5,
-            high_dynamic_smoothed: 0.5,
-            kick_dynamic_smoothed: 0.5,
-            snare_dynamic_smoothed: 0.5,
-            hihat_dynamic_smoothed: 0.5,
             amplitude_dynamic_smoothed: 0.5,
             raw_amplitude_dynamic_smoothed: 0.5,
             fade_in_out: 0.0,
@@ -405,28 +358,11 @@ impl AudioProcessor {
             silence_counter: 0,
             activity_smoothing: 0.0,
             // Initialize new envelope fields
-            amplitude_envelope: AmplitudeEnvelope::new(),
+            amplitude_envelope: EnvelopeFollower::new(),
             smoothed_amplitude: 0.0,
             amplitude_velocity: 0.0,
-            // Initialize velocity histories
-            low_velocity_history: HistoryState::new(CONSTANTS.history_window_size.freq_history_window),
-            mid_velocity_history: HistoryState::new(CONSTANTS.history_window_size.freq_history_window),
-            high_velocity_history: HistoryState::new(CONSTANTS.history_window_size.freq_history_window),
-            kick_velocity_history: HistoryState::new(CONSTANTS.history_window_size.beat_history_window),
-            snare_velocity_history: HistoryState::new(CONSTANTS.history_window_size.beat_history_window),
-            hihat_velocity_history: HistoryState::new(CONSTANTS.history_window_size.beat_history_window),
-            // Initialize peak holds
-            low_peak_hold: 0.0,
-            mid_peak_hold: 0.0,
-            high_peak_hold: 0.0,
-            kick_peak_hold: 0.0,
-            snare_peak_hold: 0.0,
-            hihat_peak_hold: 0.0,
             amplitude_peak_hold: 0.0,
-            // Initialize log-scaled and balance
-            low_log: 0.0,
-            mid_log: 0.0,
-            high_log: 0.0,
+            // Initialize balance
             low_mid_balance: 0.0,
             mid_high_balance: 0.0,
             // Initialize onset
@@ -434,16 +370,347 @@ impl AudioProcessor {
             prev_onset_strength: 0.0,
             // Initialize rolling spectrogram buffer
             spectrogram_buffer: (0..SPECTROGRAM_WIDTH).map(|_| vec![0.0; SPECTROGRAM_HEIGHT]).collect(),
+            loudness: LoudnessMeter::new(CONSTANTS.sample_rate as u32, 1),
+            stereo: StereoAnalyzer::new(CONSTANTS.sample_rate as u32, 1, &config.bands),
+            recent_samples: VecDeque::from(vec![0.0; TIME_DOMAIN_WINDOW]),
+            rolloff_85_tracker: DescriptorTracker::new(windows.freq_history_window * 2),
+            rolloff_95_tracker: DescriptorTracker::new(windows.freq_history_window * 2),
+            flatness_tracker: DescriptorTracker::new(windows.freq_history_window * 2),
+            bandwidth_tracker: DescriptorTracker::new(windows.freq_history_window * 2),
+            zcr_tracker: DescriptorTracker::new(windows.freq_history_window * 2),
+            contrast_trackers: (0..CONTRAST_OCTAVES)
+                .map(|_| DescriptorTracker::new(windows.freq_history_window * 2))
+                .collect(),
+            noise_profile: None,
+            noise_calibrator: None,
+            noise_profile_changed: false,
+            playback: PlaybackTracker::new(),
+            structure: StructureDetector::new(),
+            sections: SectionTracker::new(),
+            beat_clock: BeatClock::new(),
+            capture_time: 0.0,
+            frame_sequence: 0,
+            pipeline_latency: 0.0,
+            field_followers: config.field_envelopes.iter().map(|follower| (EnvelopeFollower::new(), follower.params())).collect(),
+            modulation: ModulationBank::new(&config.custom_fields).expect("custom fields are checked by ProcessorConfig::validate"),
+            pending_events: Vec::new(),
+            device_status: "ok".to_string(),
+            config,
         }
     }
 
-    pub fn new_with_detail_level(detail_level: DetailLevel) -> Self {
-        let mut processor = Self::new();
-        processor.detail_level = detail_level;
-        processor
+    fn band_history_sizes(response: BandResponse, windows: &HistoryConfig) -> (usize, usize) {
+        match response {
+            BandResponse::Smooth => (windows.freq_history_window * 2, windows.freq_history_window),
+            BandResponse::Transient => (windows.beat_history_window, windows.beat_history_window),
+        }
+    }
+
+    fn create_band_trackers(definitions: &[BandDefinition], windows: &HistoryConfig) -> Vec<BandTracker> {
+        definitions
+            .iter()
+            .map(|definition| {
+                let (history, velocity_history) = Self::band_history_sizes(definition.response, windows);
+                BandTracker::new(definition.clone(), history, velocity_history)
+            })
+            .collect()
+    }
+
+    /// Replace the band layout; trackers for bands whose definition is unchanged keep their state
+    pub fn set_bands(&mut self, definitions: &[BandDefinition]) {
+        let mut previous: Vec<BandTracker> = std::mem::take(&mut self.band_trackers);
+        self.band_trackers = Self::create_band_trackers(definitions, &self.config.history)
+            .into_iter()
+            .map(|fresh| {
+                match previous.iter().position(|old| old.definition == fresh.definition) {
+                    Some(index) => previous.swap_remove(index),
+                    None => fresh,
+                }
+            })
+            .collect();
+        self.config.bands = definitions.to_vec();
+        self.stereo.set_bands(definitions);
+    }
+
+    /// Swap in a new configuration while keeping history, adaptive maxima and beat state
+    pub fn apply_config(&mut self, config: ProcessorConfig) -> Result<(), ConfigError> {
+        config.validate_for_rate(self.loudness.sample_rate())?;
+        if config.bands != self.config.bands {
+            self.set_bands(&config.bands);
+        }
+        if config.history != self.config.history {
+            let windows = &config.history;
+            for tracker in self.band_trackers.iter_mut() {
+                let (history, velocity_history) = Self::band_history_sizes(tracker.definition.response, windows);
+                tracker.resize_history(history, velocity_history);
+            }
+            self.vocal_history.resize(windows.vocal_history_window);
+            self.amplitude_history.resize(windows.freq_history_window);
+            self.raw_amplitude_history.resize(windows.freq_history_window);
+            let descriptor_window = windows.freq_history_window * 2;
+            for tracker in self.descriptor_trackers_mut() {
+                tracker.resize(descriptor_window);
+            }
+        }
+        if config.field_envelopes != self.config.field_envelopes {
+            // Followers on fields that are still configured keep their running state
+            let mut previous: Vec<(String, EnvelopeFollower)> = self
+                .config
+                .field_envelopes
+                .iter()
+                .map(|follower| follower.field.clone())
+                .zip(self.field_followers.drain(..).map(|(follower, _)| follower))
+                .collect();
+            self.field_followers = config
+                .field_envelopes
+                .iter()
+                .map(|envelope| {
+                    let follower = match previous.iter().position(|(field, _)| *field == envelope.field) {
+                        Some(index) => previous.swap_remove(index).1,
+                        None => EnvelopeFollower::new(),
+                    };
+                    (follower, envelope.params())
+                })
+                .collect();
+        }
+        if config.custom_fields != self.config.custom_fields {
+            self.modulation = ModulationBank::new(&config.custom_fields).map_err(|e| ConfigError::Invalid(e.to_string()))?;
+        }
+        self.config = config;
+        Ok(())
+    }
+
+    /// Capture the adaptive state (maxima, baselines, gains, histories) for a warm start
+    pub fn calibration_snapshot(&self) -> CalibrationSnapshot {
+        let mut snapshot = CalibrationSnapshot::new();
+        snapshot.bands = self
+            .band_trackers
+            .iter()
+            .map(|tracker| BandCalibration {
+                name: tracker.definition.name.clone(),
+                min_hz: tracker.definition.min_hz,
+                max_hz: tracker.definition.max_hz,
+                value: tracker.value,
+                smoothed: tracker.smoothed,
+                max: tracker.max,
+                dynamic_smoothed: tracker.dynamic_smoothed,
+                peak_hold: tracker.peak_hold,
+                history: tracker.history.buffer.chronological(),
+                velocity_history: tracker.velocity_history.buffer.chronological(),
+            })
+            .collect();
+        snapshot.max_amplitude = self.max_amplitude;
+        snapshot.max_raw_amplitude = self.max_raw_amplitude;
+        snapshot.amplitude_baseline = self.amplitude_baseline;
+        snapshot.amplitude_peak_tracker = self.amplitude_peak_tracker;
+        snapshot.activity_smoothing = self.activity_smoothing;
+        snapshot.rolling_max_bands = self.rolling_max_bands;
+        snapshot.amplitude_dynamic_smoothed = self.amplitude_dynamic_smoothed;
+        snapshot.raw_amplitude_dynamic_smoothed = self.raw_amplitude_dynamic_smoothed;
+        snapshot.gains = vec![
+            ("low".to_string(), self.low_gain),
+            ("mid".to_string(), self.mid_gain),
+            ("high".to_string(), self.high_gain),
+            ("kick".to_string(), self.kick_gain),
+            ("snare".to_string(), self.snare_gain),
+            ("hihat".to_string(), self.hihat_gain),
+            ("vocal".to_string(), self.vocal_gain),
+            ("amplitude".to_string(), self.amplitude_gain),
+            ("raw_amplitude".to_string(), self.raw_amplitude_gain),
+        ];
+        snapshot.kick_average = self.kick_average;
+        snapshot.snare_average = self.snare_average;
+        snapshot.hihat_average = self.hihat_average;
+        snapshot.amplitude_history = self.amplitude_history.buffer.chronological();
+        snapshot.raw_amplitude_history = self.raw_amplitude_history.buffer.chronological();
+        snapshot.vocal_history = self.vocal_history.buffer.chronological();
+        snapshot.descriptor_histories = self
+            .descriptor_trackers()
+            .map(|tracker| tracker.history.buffer.chronological())
+            .collect();
+        snapshot
+    }
+
+    /// Warm-start from a snapshot; bands are matched by name and range, anything else is left as is
+    pub fn restore_calibration(&mut self, snapshot: &CalibrationSnapshot) {
+        for tracker in self.band_trackers.iter_mut() {
+            let saved = snapshot.bands.iter().find(|band| {
+                band.name == tracker.definition.name
+                    && band.min_hz == tracker.definition.min_hz
+                    && band.max_hz == tracker.definition.max_hz
+            });
+            if let Some(saved) = saved {
+                tracker.value = saved.value;
+                tracker.max = saved.max;
+                tracker.smoothed = saved.smoothed;
+                tracker.dynamic_smoothed = saved.dynamic_smoothed;
+                tracker.peak_hold = saved.peak_hold;
+                tracker.history.buffer.fill_from(&saved.history);
+                tracker.velocity_history.buffer.fill_from(&saved.velocity_history);
+            }
+        }
+        self.max_amplitude = snapshot.max_amplitude;
+        self.max_raw_amplitude = snapshot.max_raw_amplitude;
+        self.amplitude_baseline = snapshot.amplitude_baseline;
+        self.amplitude_peak_tracker = snapshot.amplitude_peak_tracker;
+        self.activity_smoothing = snapshot.activity_smoothing;
+        self.rolling_max_bands = snapshot.rolling_max_bands;
+        self.amplitude_dynamic_smoothed = snapshot.amplitude_dynamic_smoothed;
+        self.raw_amplitude_dynamic_smoothed = snapshot.raw_amplitude_dynamic_smoothed;
+        for (name, gain) in &snapshot.gains {
+            match name.as_str() {
+                "low" => self.low_gain = *gain,
+                "mid" => self.mid_gain = *gain,
+                "high" => self.high_gain = *gain,
+                "kick" => self.kick_gain = *gain,
+                "snare" => self.snare_gain = *gain,
+                "hihat" => self.hihat_gain = *gain,
+                "vocal" => self.vocal_gain = *gain,
+                "amplitude" => self.amplitude_gain = *gain,
+                "raw_amplitude" => self.raw_amplitude_gain = *gain,
+                _ => {}
+            }
+        }
+        self.kick_average = snapshot.kick_average;
+        self.snare_average = snapshot.snare_average;
+        self.hihat_average = snapshot.hihat_average;
+        self.amplitude_history.buffer.fill_from(&snapshot.amplitude_history);
+        self.raw_amplitude_history.buffer.fill_from(&snapshot.raw_amplitude_history);
+        self.vocal_history.buffer.fill_from(&snapshot.vocal_history);
+        let histories = snapshot.descriptor_histories.clone();
+        for (tracker, history) in self.descriptor_trackers_mut().zip(histories.iter()) {
+            tracker.history.buffer.fill_from(history);
+        }
+    }
+
+    /// Forget everything learned about the input level and start cold
+    pub fn reset_calibration(&mut self) {
+        let fresh = AudioProcessor::with_config(self.config.clone());
+        self.restore_calibration(&fresh.calibration_snapshot());
+    }
+
+    fn descriptor_trackers(&self) -> impl Iterator<Item = &DescriptorTracker> {
+        [
+            &self.rolloff_85_tracker,
+            &self.rolloff_95_tracker,
+            &self.flatness_tracker,
+            &self.bandwidth_tracker,
+            &self.zcr_tracker,
+        ]
+        .into_iter()
+        .chain(self.contrast_trackers.iter())
+    }
+
+    fn descriptor_trackers_mut(&mut self) -> impl Iterator<Item = &mut DescriptorTracker> {
+        [
+            &mut self.rolloff_85_tracker,
+            &mut self.rolloff_95_tracker,
+            &mut self.flatness_tracker,
+            &mut self.bandwidth_tracker,
+            &mut self.zcr_tracker,
+        ]
+        .into_iter()
+        .chain(self.contrast_trackers.iter_mut())
+    }
+
+    /// Listen for `seconds` and measure the noise spectrum; keep the input silent meanwhile
+    pub fn start_noise_calibration(&mut self, seconds: f32) {
+        self.noise_calibrator = Some(NoiseCalibrator::new(seconds));
+    }
+
+    pub fn is_calibrating_noise(&self) -> bool {
+        self.noise_calibrator.is_some()
+    }
+
+    /// Stop a running noise measurement and keep the current profile
+    pub fn cancel_noise_calibration(&mut self) {
+        self.noise_calibrator = None;
+    }
+
+    /// Install a stored profile; it is ignored if it was measured at another sample rate
+    pub fn set_noise_profile(&mut self, profile: NoiseProfile) {
+        if profile.sample_rate == self.loudness.sample_rate() {
+            self.noise_profile = Some(profile);
+        }
+    }
+
+    pub fn clear_noise_profile(&mut self) {
+        self.noise_calibrator = None;
+        self.noise_profile = None;
+        self.noise_profile_changed = true;
+    }
+
+    /// Feed an active calibration and return the spectrum with the noise profile subtracted
+    fn remove_noise(&mut self, frequency_data: &[f32]) -> Option<Vec<f32>> {
+        if let Some(calibrator) = self.noise_calibrator.as_mut() {
+            calibrator.feed(frequency_data);
+            if calibrator.is_done() {
+                let noise = &self.config.noise;
+                let profile = calibrator.finish(self.loudness.sample_rate(), noise.over_subtraction, noise.spectral_floor);
+                self.noise_calibrator = None;
+                if profile.is_some() {
+                    self.noise_profile = profile;
+                    self.noise_profile_changed = true;
+                }
+            }
+            return None;
+        }
+        let profile = self.noise_profile.as_ref()?;
+        if !profile.matches(self.loudness.sample_rate(), frequency_data.len()) {
+            return None;
+        }
+        Some(profile.subtract(frequency_data, self.config.noise.over_subtraction, self.config.noise.spectral_floor))
+    }
+
+    /// Raw amplitude below which the input counts as silent (measured per device when calibrated)
+    fn silence_threshold(&self) -> f32 {
+        match &self.noise_profile {
+            Some(profile) if self.noise_calibrator.is_none() => profile.residual_amplitude * self.config.noise.threshold_margin,
+            _ => self.config.amplitude.silence_threshold,
+        }
+    }
+
+    /// Mean bin energy below which the grid map counts as inactive (measured per device when calibrated)
+    fn grid_activity_threshold(&self) -> f32 {
+        match &self.noise_profile {
+            Some(profile) if self.noise_calibrator.is_none() => profile.residual_energy * self.config.noise.threshold_margin,
+            _ => self.config.grid_activity_threshold,
+        }
+    }
+
+    /// Latest output of the named band (zeroes if the band isn't configured)
+    pub fn band(&self, name: &str) -> BandOutput {
+        self.band_trackers
+            .iter()
+            .find(|tracker| tracker.name() == name)
+            .map(|tracker| tracker.output)
+            .unwrap_or_default()
+    }
+
+    /// Set the sample rate and channel count of the time-domain input fed to `process_samples`
+    pub fn set_input_format(&mut self, sample_rate: u32, channels: u16) {
+        if self.loudness.sample_rate() != sample_rate || self.loudness.channels() != channels {
+            self.loudness = LoudnessMeter::new(sample_rate, channels);
+            self.stereo = StereoAnalyzer::new(sample_rate, channels, &self.config.bands);
+        }
+    }
+
+    /// Feed every interleaved input buffer (unthrottled) for the time-domain measurements
+    pub fn process_samples(&mut self, interleaved: &[f32]) {
+        self.loudness.process(interleaved);
+        self.stereo.push(interleaved);
+        let channels = self.loudness.channels().max(1) as usize;
+        if let Some(calibrator) = self.noise_calibrator.as_mut() {
+            // The measurement lasts `seconds` of input, however often the spectrum is analyzed
+            calibrator.elapse(interleaved.len() / channels, self.loudness.sample_rate());
+        }
+        for frame in interleaved.chunks_exact(channels) {
+            self.recent_samples.pop_front();
+            self.recent_samples.push_back(frame.iter().sum::<f32>() / channels as f32);
+        }
     }
 
-    pub fn update_base_state(&mut self, delta_time: f32, frequency_data: &[f32], now: f64) -> Option<PrimaryFreq530State> {
+    pub fn update_base_state(&mut self, delta_time: f32, frequency_data: &[f32], now: f64) -> PrimaryFreq530State {
         // Update time-related states
         self.time += delta_time as f64;
         self.adjusted_time += delta_time as f64 * self.prev_amplitude as f64;
@@ -456,6 +723,13 @@ impl AudioProcessor {
         let new_adjusted_sin_normal = (new_adjusted_sin + 1.0) / 2.0;
         let new_adjusted_cos_normal = (new_adjusted_cos + 1.0) / 2.0;
 
+        // Noise calibration sees the raw spectrum; everything below sees it with the noise removed
+        let cleaned_spectrum = self.remove_noise(frequency_data);
+        let frequency_data = cleaned_spectrum.as_deref().unwrap_or(frequency_data);
+
+        let loudness = self.loudness.reading();
+        let stereo = self.stereo.analyze();
+
         let sanitized_last_beat_time = if self.last_beat_time.is_finite() && self.last_beat_time > 0.0 {
             self.last_beat_time
         } else {
@@ -485,7 +759,7 @@ impl AudioProcessor {
             _ => vec![0.0; SPECTROGRAM_WIDTH * SPECTROGRAM_HEIGHT], // Default for lower details
         };
         
-        let state = if frequency_data.is_empty() {
+        let mut state = if frequency_data.is_empty() {
             PrimaryFreq530State {
                 time: self.time,
                 adjusted_time: self.adjusted_time,
@@ -544,23 +818,48 @@ impl AudioProcessor {
                 mid_high_balance: 0.5,
                 onset_strength: 0.0,
                 spectrogram_data: vec![0.0; SPECTROGRAM_WIDTH * SPECTROGRAM_HEIGHT],
+                momentary_lufs: loudness.momentary_lufs,
+                short_term_lufs: loudness.short_term_lufs,
+                integrated_lufs: loudness.integrated_lufs,
+                loudness_range: loudness.loudness_range,
+                true_peak_dbtp: loudness.true_peak_dbtp,
+                stereo_correlation: stereo.correlation as f64,
+                stereo_width: stereo.width as f64,
+                mid_side_ratio: stereo.mid_side_ratio as f64,
+                stereo_pan: stereo.pan as f64,
+                left_band_energies: stereo.left_bands.iter().map(|&x| x as f64).collect(),
+                right_band_energies: stereo.right_bands.iter().map(|&x| x as f64).collect(),
+                band_pan: stereo.band_pan.iter().map(|&x| x as f64).collect(),
+                spectral_rolloff_85: 0.0,
+                spectral_rolloff_95: 0.0,
+                spectral_flatness: 0.0,
+                spectral_bandwidth: 0.0,
+                spectral_contrast: vec![0.0; CONTRAST_OCTAVES],
+                zero_crossing_rate: 0.0,
+                spectral_rolloff_85_dynamic: 0.0,
+                spectral_rolloff_95_dynamic: 0.0,
+                spectral_flatness_dynamic: 0.0,
+                spectral_bandwidth_dynamic: 0.0,
+                spectral_contrast_dynamic: vec![0.0; CONTRAST_OCTAVES],
+                zero_crossing_rate_dynamic: 0.0,
+                bands: self.band_trackers.iter().map(|tracker| band_state(tracker.name(), &BandOutput::default())).collect(),
+                playback_state: self.playback.state().as_str().to_string(),
+                events: Vec::new(),
+                buildup_tension: self.structure.tension() as f64,
+                section_index: self.sections.section_index(),
+                section_label: self.sections.label(),
+                beat_clock: self.beat_clock.beats(),
+                lfos: self.lfo_values(),
+                custom: Vec::new(),
+                sequence: 0,
+                capture_time: self.capture_time,
+                next_beat_time: self.next_beat_time(now),
+                device_status: self.device_status.clone(),
             }
         } else {
-            let sample_rate = 44100.0;
+            let sample_rate = self.loudness.sample_rate() as f32;
             let fft_size = frequency_data.len() * 2;
             let bin_width = sample_rate / fft_size as f32;
-            let low_range = (20.0, 250.0);
-            let mid_range = (250.0, 4000.0);
-            let high_range = (4000.0, 20000.0);
-            let kick_range = (40.0, 100.0);
-            let snare_range = (120.0, 500.0);
-            let hihat_range = (2000.0, 10000.0);
-            let band_mean = |min_hz: f32, max_hz: f32| {
-                let min_bin = (min_hz / bin_width).floor() as usize;
-                let max_bin = (max_hz / bin_width).ceil() as usize;
-                let bins = &frequency_data[min_bin.min(frequency_data.len())..max_bin.min(frequency_data.len())];
-                if bins.is_empty() { 0.0 } else { bins.iter().copied().sum::<f32>() / bins.len() as f32 }
-            };
 
             // --- BEGIN SPECTRAL CENTROID CALCULATION ---
             let mut weighted_sum_freq = 0.0;
@@ -575,59 +874,42 @@ impl AudioProcessor {
             spectral_centroid_value = if nyquist_freq > 1e-6 { spectral_centroid_value / nyquist_freq } else { 0.0 };
             // --- END SPECTRAL CENTROID CALCULATION ---
 
-            // --- BEGIN CHROMAGRAM CALCULATION ---
-            let mut chromagram_values = vec![0.0f32; 12];
-            if !frequency_data.is_empty() {
-                let reference_freq_a4 = 440.0f32;
-                for (i, mag) in frequency_data.iter().enumerate() {
-                    if *mag <= 1e-6 { continue; }
-                    let freq = (i as f32 + 0.5) * bin_width;
-                    if freq <= 0.0 { continue; }
-
-                    let midi_note = 69.0 + 12.0 * (freq / reference_freq_a4).log2();
-                    if midi_note < 0.0 { continue; }
-
-                    let pitch_class = (midi_note.round() as i32) % 12;
-                    chromagram_values[pitch_class as usize] += mag;
-                }
+            let chromagram_values = spectral::chromagram(frequency_data, bin_width);
 
-                let max_chroma_val = chromagram_values.iter().cloned().fold(0.0f32, f32::max);
-                if max_chroma_val > 1e-6 {
-                    for val in chromagram_values.iter_mut() { *val /= max_chroma_val; }
-                }
+            // Configured frequency bands
+            let band_smoothing = self.config.band_smoothing;
+            for tracker in self.band_trackers.iter_mut() {
+                tracker.update(frequency_data, bin_width, delta_time, &band_smoothing);
             }
-            // --- END CHROMAGRAM CALCULATION ---
-
-            let raw_low = band_mean(low_range.0, low_range.1);
-            let raw_mid = band_mean(mid_range.0, mid_range.1);
-            let raw_high = band_mean(high_range.0, high_range.1);
-            let kick = band_mean(kick_range.0, kick_range.1);
-            let snare = band_mean(snare_range.0, snare_range.1);
-            let hihat = band_mean(hihat_range.0, hihat_range.1);
-            let alpha = 0.1;
-            let mut low = self.prev_low * (1.0 - alpha) + raw_low * alpha;
-            let mut mid = self.prev_mid * (1.0 - alpha) + raw_mid * alpha;
-            let mut high = self.prev_high * (1.0 - alpha) + raw_high * alpha;
-            let max_alpha = 0.05;
-            self.max_low = self.max_low * (1.0 - max_alpha) + raw_low.max(self.max_low) * max_alpha;
-            self.max_mid = self.max_mid * (1.0 - max_alpha) + raw_mid.max(self.max_mid) * max_alpha;
-            self.max_high = self.max_high * (1.0 - max_alpha) + raw_high.max(self.max_high) * max_alpha;
-            self.max_kick = self.max_kick.max(kick);
-            self.max_snare = self.max_snare.max(snare);
-            self.max_hihat = self.max_hihat.max(hihat);
-            low = (low / self.max_low).clamp(0.0, 1.0);
-            mid = (mid / self.max_mid).clamp(0.0, 1.0);
-            high = (high / self.max_high).clamp(0.0, 1.0);
-            let kick = (kick / self.max_kick).clamp(0.0, 1.0);
-            let snare = (snare / self.max_snare).clamp(0.0, 1.0);
-            let hihat = (hihat / self.max_hihat).clamp(0.0, 1.0);
-            let post_alpha = 0.15;
-            low = self.prev_low * (1.0 - post_alpha) + low * post_alpha;
-            mid = self.prev_mid * (1.0 - post_alpha) + mid * post_alpha;
-            high = self.prev_high * (1.0 - post_alpha) + high * post_alpha;
+            let low_band = self.band("low");
+            let mid_band = self.band("mid");
+            let high_band = self.band("high");
+            let kick_band = self.band("kick");
+            let snare_band = self.band("snare");
+            let hihat_band = self.band("hihat");
+            let (low, mid, high) = (low_band.value, mid_band.value, high_band.value);
+            let (kick, snare, hihat) = (kick_band.value, snare_band.value, hihat_band.value);
             // Calculate raw amplitude using RMS (Root Mean Square) - this is the basic math
             let raw_amplitude = (frequency_data.iter().map(|x| x * x).sum::<f32>() / frequency_data.len() as f32).sqrt();
             
+            // Playback state (silent/idle/playing/gap) from the input level
+            let silence_threshold = self.silence_threshold();
+            if let Some(transition) = self.playback.update(raw_amplitude, silence_threshold, delta_time, now, &self.config.playback) {
+                self.pending_events.push(playback_event(&transition));
+            }
+
+            // Build-up tension and drops/breakdowns from the long-window features
+            let structure_features = StructureFeatures {
+                energy: raw_amplitude,
+                low,
+                centroid: spectral_centroid_value,
+                snare_dynamic: snare_band.dynamic,
+                playing: self.playback.state() == PlaybackState::Playing,
+            };
+            if let Some(event) = self.structure.update(&structure_features, delta_time, now, &self.config.structure) {
+                self.pending_events.push(structure_event(&event));
+            }
+            
             // Update raw amplitude tracking for normalization reference
             self.max_raw_amplitude = self.max_raw_amplitude.max(raw_amplitude);
             
@@ -640,7 +922,8 @@ impl AudioProcessor {
                 raw_normalized_amplitude,
                 delta_time,
                 self.spectral_flux,
-                self.beat_intensity
+                self.beat_intensity,
+                &self.config.envelope,
             );
             
             // Store the smoothed result
@@ -649,54 +932,54 @@ impl AudioProcessor {
             // Update velocity for additional smoothing if needed
             self.amplitude_velocity = (smoothed_amplitude - self.prev_amplitude) / delta_time.max(0.001);
             
-            self.prev_low = low;
-            self.prev_mid = mid;
-            self.prev_high = high;
-            self.prev_kick = kick;
-            self.prev_snare = snare;
-            self.prev_hihat = hihat;
             self.prev_amplitude = smoothed_amplitude; // Use smoothed amplitude
             self.prev_raw_amplitude = raw_amplitude;
-            self.low_history.buffer.push(low);
-            self.mid_history.buffer.push(mid);
-            self.high_history.buffer.push(high);
-            self.kick_history.buffer.push(kick);
-            self.snare_history.buffer.push(snare);
-            self.hihat_history.buffer.push(hihat);
             self.amplitude_history.buffer.push(smoothed_amplitude);
             self.raw_amplitude_history.buffer.push(raw_amplitude);
             // Calculate dynamic values with smoothing
-            let sharpness = 0.5;
-            let smoothing = 0.8;
-            let low_dynamic = dynamic_normalize_with_sharpness(low as f32, &self.low_history, sharpness);
-            self.low_dynamic_smoothed = self.low_dynamic_smoothed * smoothing + low_dynamic * (1.0 - smoothing);
-            let mid_dynamic = dynamic_normalize_with_sharpness(mid as f32, &self.mid_history, sharpness);
-            self.mid_dynamic_smoothed = self.mid_dynamic_smoothed * smoothing + mid_dynamic * (1.0 - smoothing);
-            let high_dynamic = dynamic_normalize_with_sharpness(high as f32, &self.high_history, sharpness);
-            self.high_dynamic_smoothed = self.high_dynamic_smoothed * smoothing + high_dynamic * (1.0 - smoothing);
-            let kick_dynamic = dynamic_normalize_with_sharpness(kick as f32, &self.kick_history, sharpness);
-            self.kick_dynamic_smoothed = self.kick_dynamic_smoothed * smoothing + kick_dynamic * (1.0 - smoothing);
-            let snare_dynamic = dynamic_normalize_with_sharpness(snare as f32, &self.snare_history, sharpness);
-            self.snare_dynamic_smoothed = self.snare_dynamic_smoothed * smoothing + snare_dynamic * (1.0 - smoothing);
-            let hihat_dynamic = dynamic_normalize_with_sharpness(hihat as f32, &self.hihat_history, sharpness);
-            self.hihat_dynamic_smoothed = self.hihat_dynamic_smoothed * smoothing + hihat_dynamic * (1.0 - smoothing);
-            let amplitude_dynamic = dynamic_normalize_with_sharpness(smoothed_amplitude as f32, &self.amplitude_history, sharpness);
+            let sharpness = band_smoothing.sharpness;
+            let smoothing = band_smoothing.dynamic_smoothing;
+            let amplitude_dynamic = dynamic_normalize_with_sharpness(smoothed_amplitude, &self.amplitude_history, sharpness);
             self.amplitude_dynamic_smoothed = self.amplitude_dynamic_smoothed * smoothing + amplitude_dynamic * (1.0 - smoothing);
-            let raw_amplitude_dynamic = dynamic_normalize_with_sharpness(raw_amplitude as f32, &self.raw_amplitude_history, sharpness);
+            let raw_amplitude_dynamic = dynamic_normalize_with_sharpness(raw_amplitude, &self.raw_amplitude_history, sharpness);
             self.raw_amplitude_dynamic_smoothed = self.raw_amplitude_dynamic_smoothed * smoothing + raw_amplitude_dynamic * (1.0 - smoothing);
 
+            // Timbre descriptors, each with the same history-based dynamic variant as the bands
+            let rolloff_85 = spectral::spectral_rolloff(frequency_data, 0.85);
+            let rolloff_95 = spectral::spectral_rolloff(frequency_data, 0.95);
+            let flatness = spectral::spectral_flatness(frequency_data);
+            let bandwidth = spectral::spectral_bandwidth(frequency_data, spectral_centroid_value);
+            let contrast = spectral::spectral_contrast(frequency_data, bin_width);
+            let zcr = spectral::zero_crossing_rate(self.recent_samples.make_contiguous());
+            let rolloff_85_dynamic = self.rolloff_85_tracker.update(rolloff_85, sharpness, smoothing);
+            let rolloff_95_dynamic = self.rolloff_95_tracker.update(rolloff_95, sharpness, smoothing);
+            let flatness_dynamic = self.flatness_tracker.update(flatness, sharpness, smoothing);
+            let bandwidth_dynamic = self.bandwidth_tracker.update(bandwidth, sharpness, smoothing);
+            let zcr_dynamic = self.zcr_tracker.update(zcr, sharpness, smoothing);
+            let contrast_dynamic: Vec<f32> = self
+                .contrast_trackers
+                .iter_mut()
+                .zip(contrast.iter())
+                .map(|(tracker, &value)| tracker.update(value, sharpness, smoothing))
+                .collect();
+
+            // Section boundaries from the chroma/contrast self-similarity
+            if let Some(boundary) = self.sections.update(&chromagram_values, &contrast, delta_time, now, &self.config.segments) {
+                self.pending_events.push(section_event(&boundary));
+            }
+
             // --- BEGIN BEAT DETECTION PIPELINE ---
             // Calculate spectral flux
             self.spectral_flux = self.calculate_spectral_flux(frequency_data, self.prev_fft_bins.as_deref());
             self.prev_fft_bins = Some(frequency_data.to_vec());
 
             // Determine if audio is active
-            let is_audio_active = smoothed_amplitude > crate::audio::constants::CONSTANTS.audio_activity_threshold;
+            let is_audio_active = smoothed_amplitude > self.config.audio_activity_threshold;
 
             // Create GainState for kick, snare, hihat
-            let kick_state = GainState { value: kick, gain: self.kick_gain };
-            let snare_state = GainState { value: snare, gain: self.snare_gain };
-            let hihat_state = GainState { value: hihat, gain: self.hihat_gain };
+            let kick_state = GainState { value: kick };
+            let snare_state = GainState { value: snare };
+            let hihat_state = GainState { value: hihat };
 
             // Run beat detection
             let beat_detection = self.update_beat_detection(
@@ -720,6 +1003,9 @@ impl AudioProcessor {
             // Update bps
             self.update_bps();
 
+            // Beat clock for the LFO bank, locked to the detected beats
+            self.beat_clock.update(delta_time, self.bps, beat_detection.is_beat_candidate, &self.config.lfo);
+
             // --- BEGIN FREQUENCY GRID MAP CALCULATION (conditional based on detail level) ---
             let frequency_grid_map_f64 = match self.detail_level {
                 DetailLevel::Basic => {
@@ -730,14 +1016,12 @@ impl AudioProcessor {
                     let mut grid_map_values_f32 = vec![0.0f32; GRID_MAP_SIZE];
             
                     if !frequency_data.is_empty() {
-                        let sample_rate = 44100.0;
                         let nyquist = sample_rate / 2.0;
                         let bin_width = nyquist / frequency_data.len() as f32;
                         
                         let total_energy: f32 = frequency_data.iter().map(|&x| x * x).sum();
                         let avg_energy = total_energy / frequency_data.len() as f32;
-                        let audio_activity_threshold = 0.00001;
-                        let is_audio_active = avg_energy > audio_activity_threshold;
+                        let audio_activity_threshold = self.grid_activity_threshold();
                         
                         let activity_level = if avg_energy > audio_activity_threshold {
                             (avg_energy / (audio_activity_threshold * 10.0)).min(1.0)
@@ -766,13 +1050,13 @@ impl AudioProcessor {
                         
                         for grid_y in 0..grid_size {
                             for grid_x in 0..grid_size {
-                                let (start_bin, end_bin, start_freq, end_freq) = frequency_bands[grid_x];
+                                let (start_bin, end_bin, start_freq, _) = frequency_bands[grid_x];
                                 
                                 let mut band_magnitude = 0.0f32;
                                 let mut bin_count = 0;
                                 
-                                for bin in start_bin..=end_bin.min(frequency_data.len() - 1) {
-                                    band_magnitude += frequency_data[bin];
+                                for magnitude in &frequency_data[start_bin..=end_bin.min(frequency_data.len() - 1)] {
+                                    band_magnitude += magnitude;
                                     bin_count += 1;
                                 }
                                 
@@ -780,12 +1064,10 @@ impl AudioProcessor {
                                     band_magnitude /= bin_count as f32;
                                 }
                                 
-                                let mut final_magnitude = band_magnitude;
-                                
-                                match grid_y {
+                                let final_magnitude = match grid_y {
                                     0..=3 => {
-                                        let bass_boost = if grid_x < 4 { 1.0 + self.prev_low * 0.3 } else { 1.0 };
-                                        final_magnitude = band_magnitude * bass_boost;
+                                        let bass_boost = if grid_x < 4 { 1.0 + low * 0.3 } else { 1.0 };
+                                        band_magnitude * bass_boost
                                     },
                                     4..=7 => {
                                         let harmonic_enhancement = if activity_level > 0.05 {
@@ -803,7 +1085,7 @@ impl AudioProcessor {
                                             harmonic_energy * 0.2 * activity_level
                                         } else { 0.0 };
                                         
-                                        final_magnitude = band_magnitude + harmonic_enhancement;
+                                        band_magnitude + harmonic_enhancement
                                     },
                                     8..=11 => {
                                         let beat_enhancement = if self.bps > 0.1 && self.beat_intensity > 0.05 {
@@ -819,7 +1101,7 @@ impl AudioProcessor {
                                             0.2 * beat_strength * (beat_phase * 2.0 * std::f32::consts::PI).sin()
                                         } else { 0.0 };
                                         
-                                        final_magnitude = band_magnitude + band_magnitude * beat_enhancement;
+                                        band_magnitude + band_magnitude * beat_enhancement
                                     },
                                     12..=15 => {
                                         let flux_enhancement = if let Some(ref prev_bins) = self.prev_fft_bins {
@@ -833,15 +1115,15 @@ impl AudioProcessor {
                                         } else { 0.0 };
                                         
                                         let freq_emphasis = if start_freq > 1000.0 { 
-                                            self.prev_high * 0.3 
+                                            high * 0.3 
                                         } else if start_freq > 250.0 { 
-                                            self.prev_mid * 0.2 
+                                            mid * 0.2 
                                         } else { 0.0 };
                                         
-                                        final_magnitude = band_magnitude + flux_enhancement * activity_level + freq_emphasis;
+                                        band_magnitude + flux_enhancement * activity_level + freq_emphasis
                                     },
-                                    _ => final_magnitude = band_magnitude,
-                                }
+                                    _ => band_magnitude,
+                                };
                                 
                                 grid_map_values_f32[grid_y * grid_size + grid_x] = final_magnitude;
                             }
@@ -889,7 +1171,8 @@ impl AudioProcessor {
             let mut beat_phase_value = 0.0;
             if self.bps > 0.1 && self.last_beat_time.is_finite() && self.last_beat_time > 0.0 {
                 let beat_duration = 1.0 / self.bps as f64;
-                let time_since_last_beat = now - self.last_beat_time;
+                // Phase at the moment the frame is expected to be seen
+                let time_since_last_beat = now + self.lookahead() - self.last_beat_time;
                 if time_since_last_beat >= 0.0 {
                     beat_phase_value = (time_since_last_beat / beat_duration) % 1.0;
                 }
@@ -899,35 +1182,8 @@ impl AudioProcessor {
             // Compute and store quantized bands (32 log bands, quantized to u8, rolling max)
             self.quantized_bands = self.compute_quantized_bands_log_rolling(frequency_data, 32, sample_rate);
 
-            // Calculate velocities (rate of change) for each band
-            let low_velocity = (low - self.prev_low) / delta_time.max(0.001);
-            let mid_velocity = (mid - self.prev_mid) / delta_time.max(0.001);
-            let high_velocity = (high - self.prev_high) / delta_time.max(0.001);
-            let kick_velocity = (kick - self.prev_kick) / delta_time.max(0.001);
-            let snare_velocity = (snare - self.prev_snare) / delta_time.max(0.001);
-            let hihat_velocity = (hihat - self.prev_hihat) / delta_time.max(0.001);
-            self.low_velocity_history.buffer.push(low_velocity);
-            self.mid_velocity_history.buffer.push(mid_velocity);
-            self.high_velocity_history.buffer.push(high_velocity);
-            self.kick_velocity_history.buffer.push(kick_velocity);
-            self.snare_velocity_history.buffer.push(snare_velocity);
-            self.hihat_velocity_history.buffer.push(hihat_velocity);
-
-            // Update peak holds with slow decay (for spiked visuals)
-            let peak_decay = 0.95; // Slow decay
-            self.low_peak_hold = self.low_peak_hold.max(low) * peak_decay;
-            self.mid_peak_hold = self.mid_peak_hold.max(mid) * peak_decay;
-            self.high_peak_hold = self.high_peak_hold.max(high) * peak_decay;
-            self.kick_peak_hold = self.kick_peak_hold.max(kick) * peak_decay;
-            self.snare_peak_hold = self.snare_peak_hold.max(snare) * peak_decay;
-            self.hihat_peak_hold = self.hihat_peak_hold.max(hihat) * peak_decay;
-            self.amplitude_peak_hold = self.amplitude_peak_hold.max(smoothed_amplitude) * peak_decay;
-
-            // Log-scaled values (perceptual, useful for brightness/scale in shaders)
-            let log_scale = |v: f32| if v > 0.0 { (1.0 + v.ln() / 10.0).clamp(0.0, 1.0) } else { 0.0 };
-            self.low_log = log_scale(low);
-            self.mid_log = log_scale(mid);
-            self.high_log = log_scale(high);
+            // Update amplitude peak hold with slow decay (for spiked visuals)
+            self.amplitude_peak_hold = self.amplitude_peak_hold.max(smoothed_amplitude) * band_smoothing.peak_decay;
 
             // Balance metrics (0-1, where 0.5 is balanced)
             self.low_mid_balance = (low / (low + mid).max(1e-6)).clamp(0.0, 1.0);
@@ -935,15 +1191,15 @@ impl AudioProcessor {
 
             // Onset strength (enhanced transient detection)
             self.onset_strength = self.calculate_onset_strength(frequency_data);
-            let onset_velocity = (self.onset_strength - self.prev_onset_strength) / delta_time.max(0.001);
             self.prev_onset_strength = self.onset_strength;
 
             // Vocal likelihood calculation
             let harmonic_score = self.get_harmonic_score(frequency_data);
             let mid_variance = self.calculate_mid_variance();
-            let vocal_likelihood = (harmonic_score * CONSTANTS.vocal_harmonic_weight +
-                                    mid_variance * CONSTANTS.vocal_variance_weight +
-                                    mid * CONSTANTS.vocal_mid_weight).clamp(0.0, 1.0);
+            let vocal = &self.config.vocal;
+            let vocal_likelihood = (harmonic_score * vocal.harmonic_weight +
+                                    mid_variance * vocal.variance_weight +
+                                    mid * vocal.mid_weight).clamp(0.0, 1.0);
 
             PrimaryFreq530State {
                 time: self.time,
@@ -967,12 +1223,12 @@ impl AudioProcessor {
                 raw_amplitude: raw_amplitude as f64,
                 beat_intensity: self.beat_intensity as f64,
                 bps: self.bps as f64,
-                low_dynamic: self.low_dynamic_smoothed as f64,
-                mid_dynamic: self.mid_dynamic_smoothed as f64,
-                high_dynamic: self.high_dynamic_smoothed as f64,
-                kick_dynamic: self.kick_dynamic_smoothed as f64,
-                snare_dynamic: self.snare_dynamic_smoothed as f64,
-                hihat_dynamic: self.hihat_dynamic_smoothed as f64,
+                low_dynamic: low_band.dynamic as f64,
+                mid_dynamic: mid_band.dynamic as f64,
+                high_dynamic: high_band.dynamic as f64,
+                kick_dynamic: kick_band.dynamic as f64,
+                snare_dynamic: snare_band.dynamic as f64,
+                hihat_dynamic: hihat_band.dynamic as f64,
                 amplitude_dynamic: self.amplitude_dynamic_smoothed as f64,
                 raw_amplitude_dynamic: self.raw_amplitude_dynamic_smoothed as f64,
                 spectral_flux: self.spectral_flux as f64,
@@ -983,219 +1239,152 @@ impl AudioProcessor {
                 chromagram: chromagram_values.iter().map(|&x| x as f64).collect(),
                 beat_phase: beat_phase_value,
                 frequency_grid_map: frequency_grid_map_f64,
-                low_velocity: low_velocity as f64,
-                mid_velocity: mid_velocity as f64,
-                high_velocity: high_velocity as f64,
-                kick_velocity: kick_velocity as f64,
-                snare_velocity: snare_velocity as f64,
-                hihat_velocity: hihat_velocity as f64,
-                low_peak_hold: self.low_peak_hold as f64,
-                mid_peak_hold: self.mid_peak_hold as f64,
-                high_peak_hold: self.high_peak_hold as f64,
-                kick_peak_hold: self.kick_peak_hold as f64,
-                snare_peak_hold: self.snare_peak_hold as f64,
-                hihat_peak_hold: self.hihat_peak_hold as f64,
+                low_velocity: low_band.velocity as f64,
+                mid_velocity: mid_band.velocity as f64,
+                high_velocity: high_band.velocity as f64,
+                kick_velocity: kick_band.velocity as f64,
+                snare_velocity: snare_band.velocity as f64,
+                hihat_velocity: hihat_band.velocity as f64,
+                low_peak_hold: low_band.peak_hold as f64,
+                mid_peak_hold: mid_band.peak_hold as f64,
+                high_peak_hold: high_band.peak_hold as f64,
+                kick_peak_hold: kick_band.peak_hold as f64,
+                snare_peak_hold: snare_band.peak_hold as f64,
+                hihat_peak_hold: hihat_band.peak_hold as f64,
                 amplitude_peak_hold: self.amplitude_peak_hold as f64,
-                low_log: self.low_log as f64,
-                mid_log: self.mid_log as f64,
-                high_log: self.high_log as f64,
+                low_log: low_band.log as f64,
+                mid_log: mid_band.log as f64,
+                high_log: high_band.log as f64,
                 low_mid_balance: self.low_mid_balance as f64,
                 mid_high_balance: self.mid_high_balance as f64,
                 onset_strength: self.onset_strength as f64,
                 spectrogram_data: spectrogram_data_f64,
+                momentary_lufs: loudness.momentary_lufs,
+                short_term_lufs: loudness.short_term_lufs,
+                integrated_lufs: loudness.integrated_lufs,
+                loudness_range: loudness.loudness_range,
+                true_peak_dbtp: loudness.true_peak_dbtp,
+                stereo_correlation: stereo.correlation as f64,
+                stereo_width: stereo.width as f64,
+                mid_side_ratio: stereo.mid_side_ratio as f64,
+                stereo_pan: stereo.pan as f64,
+                left_band_energies: stereo.left_bands.iter().map(|&x| x as f64).collect(),
+                right_band_energies: stereo.right_bands.iter().map(|&x| x as f64).collect(),
+                band_pan: stereo.band_pan.iter().map(|&x| x as f64).collect(),
+                spectral_rolloff_85: rolloff_85 as f64,
+                spectral_rolloff_95: rolloff_95 as f64,
+                spectral_flatness: flatness as f64,
+                spectral_bandwidth: bandwidth as f64,
+                spectral_contrast: contrast.iter().map(|&x| x as f64).collect(),
+                zero_crossing_rate: zcr as f64,
+                spectral_rolloff_85_dynamic: rolloff_85_dynamic as f64,
+                spectral_rolloff_95_dynamic: rolloff_95_dynamic as f64,
+                spectral_flatness_dynamic: flatness_dynamic as f64,
+                spectral_bandwidth_dynamic: bandwidth_dynamic as f64,
+                spectral_contrast_dynamic: contrast_dynamic.iter().map(|&x| x as f64).collect(),
+                zero_crossing_rate_dynamic: zcr_dynamic as f64,
+                bands: self.band_trackers.iter().map(|tracker| band_state(tracker.name(), &tracker.output)).collect(),
+                playback_state: self.playback.state().as_str().to_string(),
+                events: Vec::new(),
+                buildup_tension: self.structure.tension() as f64,
+                section_index: self.sections.section_index(),
+                section_label: self.sections.label(),
+                beat_clock: self.beat_clock.beats(),
+                lfos: self.lfo_values(),
+                custom: Vec::new(),
+                sequence: 0,
+                capture_time: self.capture_time,
+                next_beat_time: self.next_beat_time(now),
+                device_status: self.device_status.clone(),
             }
         };
 
-        let should_update = now - self.last_update >= crate::audio::constants::CONSTANTS.websocket_update_interval_ms as f64 / 1000.0;
-        if should_update {
-            self.last_update = now;
-            let fade_speed = 0.02;
-            let target_value = self.amplitude_dynamic_smoothed.clamp(0.0, 1.0);
-            self.fade_in_out = self.fade_in_out * (1.0 - fade_speed) + target_value * fade_speed;
-            self.fade_in_out = self.fade_in_out.clamp(0.0, 1.0);
-            Some(state)
-        } else {
-            self.pending_state = Some(state);
-            None
+        // Per-field envelope followers
+        for ((follower, params), envelope) in self.field_followers.iter_mut().zip(&self.config.field_envelopes) {
+            if let Some(value) = state.field_mut(&envelope.field) {
+                *value = follower.update(*value as f32, delta_time, self.spectral_flux, self.beat_intensity, params) as f64;
+            }
         }
-    }
-
-    pub fn get_pending_state(&mut self) -> Option<PrimaryFreq530State> {
-        self.pending_state.take()
-    }
 
-    /// Configure the amplitude envelope for different musical styles and responsiveness
-    pub fn set_amplitude_envelope_profile(&mut self, profile: EnvelopeProfile) {
-        self.amplitude_envelope.set_profile(profile);
-    }
+        // Custom fields see the finished frame, so they can combine any of the fields above
+        let triggers = self.modulation.update(&state, &self.config.custom_fields, delta_time, now);
+        self.pending_events.extend(triggers.iter().map(trigger_event));
+        state.custom = self
+            .config
+            .custom_fields
+            .iter()
+            .zip(self.modulation.values())
+            .map(|(field, &value)| NamedValue { name: field.name.clone(), value: value as f64 })
+            .collect();
 
-    /// Manually configure the amplitude envelope parameters
-    pub fn configure_amplitude_envelope(&mut self, 
-        attack_rate: f32, 
-        decay_rate: f32, 
-        momentum_factor: f32, 
-        peak_hold_time: f32
-    ) {
-        self.amplitude_envelope.attack_rate = attack_rate.clamp(0.001, 1.0);
-        self.amplitude_envelope.decay_rate = decay_rate.clamp(0.001, 1.0);
-        self.amplitude_envelope.momentum_factor = momentum_factor.clamp(0.0, 1.0);
-        self.amplitude_envelope.peak_hold_time = peak_hold_time.clamp(0.0, 1.0);
+        self.frame_sequence += 1;
+        state.sequence = self.frame_sequence;
+        state.events = std::mem::take(&mut self.pending_events);
+        let fade_speed = self.config.amplitude.fade_speed;
+        let target_value = self.amplitude_dynamic_smoothed.clamp(0.0, 1.0);
+        self.fade_in_out = self.fade_in_out * (1.0 - fade_speed) + target_value * fade_speed;
+        self.fade_in_out = self.fade_in_out.clamp(0.0, 1.0);
+        state
     }
 
-    /// Get the current smoothed amplitude value
-    pub fn get_smoothed_amplitude(&self) -> f32 {
-        self.smoothed_amplitude
+    /// Predicted time of the next beat on the server clock (0 without a tempo)
+    fn next_beat_time(&self, now: f64) -> f64 {
+        if self.bps <= 0.1 || !self.last_beat_time.is_finite() || self.last_beat_time <= 0.0 {
+            return 0.0;
+        }
+        // Clients get the beat `output_delay` early, so the next one is the first after that
+        let output_delay = self.config.latency.output_delay as f64;
+        let beat_duration = 1.0 / self.bps as f64;
+        let beats_since = ((now + output_delay - self.last_beat_time) / beat_duration).floor().max(0.0) + 1.0;
+        let next_beat = self.last_beat_time + beats_since * beat_duration;
+        // `now` and `capture_time` describe the same moment on the two clocks
+        self.capture_time + (next_beat - now) - output_delay
     }
 
-    /// Get the current amplitude velocity (rate of change)
-    pub fn get_amplitude_velocity(&self) -> f32 {
-        self.amplitude_velocity
+    /// Seed the tempo from tapped beats (UNIX-epoch seconds, oldest first); detected beats
+    /// take over from there
+    pub fn apply_tap_tempo(&mut self, taps: &[f64]) {
+        if let Some(&last) = taps.last() {
+            if taps.len() >= 2 {
+                self.beat_times = taps.to_vec();
+                self.bps = self.calculate_instant_bps();
+                self.last_beat_time = last;
+            }
+        }
     }
 
-    /// Enable or disable adaptive attack/decay based on audio content
-    pub fn set_adaptive_envelope(&mut self, adaptive_attack: bool, adaptive_decay: bool) {
-        self.amplitude_envelope.adaptive_attack = adaptive_attack;
-        self.amplitude_envelope.adaptive_decay = adaptive_decay;
+    /// Seconds `beat_phase` is advanced by to land on the beat when displayed
+    pub fn lookahead(&self) -> f64 {
+        let pipeline = if self.config.latency.compensate_pipeline { self.pipeline_latency } else { 0.0 };
+        self.config.latency.output_delay as f64 + pipeline
     }
 
-    fn normalize_decibel_value(&self, db_value: f32, min_db: f32, max_db: f32) -> f32 {
-        let clamped_db = db_value.clamp(min_db, max_db);
-        (clamped_db - min_db) / (max_db - min_db)
-    }
-    fn db_to_magnitude(&self, db: f32) -> f32 {
-        if db <= crate::audio::constants::CONSTANTS.beat_detection_parameters.db_min {
+    /// Estimated time for `amplitude` to cover 63% of a step, when updated every `frame_period` seconds
+    pub fn smoothing_lag(&self, frame_period: f64) -> f64 {
+        let envelope = &self.config.envelope;
+        let gain = (envelope.attack_rate * envelope.momentum_factor) as f64;
+        // Instant at full gain; with zero gain it never moves, which is not a lag either
+        if gain <= 0.0 || gain >= 1.0 {
             0.0
         } else {
-            10.0f32.powf((db + 60.0) / 20.0).min(1.0)
-        }
-    }
-    fn db_array_to_magnitudes(&self, db_array: &[f32]) -> Vec<f32> {
-        db_array.iter().map(|&db| self.db_to_magnitude(db)).collect()
-    }
-    fn normalize(value: f32, history: &HistoryState) -> f32 {
-        if history.max == history.min {
-            value
-        } else {
-            (value - history.min) / (history.max - history.min)
-        }
-    }
-    fn calculate_amplitude_rms(&self, data: &[f32]) -> f32 {
-        let linear_values = self.db_array_to_magnitudes(data);
-        let sum: f32 = linear_values.iter().map(|&x| x * x).sum();
-        let rms = (sum / linear_values.len() as f32).sqrt();
-        (rms * 4.0).min(1.0)
-    }
-    fn apply_envelope(&self, current: f32, previous: f32) -> f32 {
-        if current >= previous {
-            current
-        } else {
-            (previous * crate::audio::constants::CONSTANTS.decay_rate + current * (1.0 - crate::audio::constants::CONSTANTS.decay_rate)).max(0.0)
-        }
-    }
-    fn apply_gain_and_history(value: f32, gain: f32, history: &mut HistoryState, target_min: f32, target_max: f32, gain_adjust_rate: f32) -> GainState {
-        let gained_value = value * gain;
-        history.buffer.push(gained_value);
-        history.update_min_max(gained_value);
-        let normalized_value = Self::normalize(gained_value, history);
-        let new_gain = if normalized_value < target_min {
-            gain + gain_adjust_rate
-        } else if normalized_value > target_max {
-            gain - gain_adjust_rate
-        } else {
-            gain
-        };
-        GainState {
-            value: normalized_value,
-            gain: new_gain.clamp(0.1, 10.0),
-        }
-    }
-    fn update_history(&mut self, state: GainState, field: &str) {
-        match field {
-            "raw_amplitude" => self.raw_amplitude_gain = state.gain,
-            "amplitude" => {
-                self.amplitude_gain = state.gain;
-                self.prev_amplitude = state.value;
-            }
-            "kick" => {
-                self.kick_gain = state.gain;
-                self.prev_kick = state.value;
-            }
-            "snare" => {
-                self.snare_gain = state.gain;
-                self.prev_snare = state.value;
-            }
-            "hihat" => {
-                self.hihat_gain = state.gain;
-                self.prev_hihat = state.value;
-            }
-            "low" => {
-                self.low_gain = state.gain;
-                self.prev_low = state.value;
-            }
-            "mid" => {
-                self.mid_gain = state.gain;
-                self.prev_mid = state.value;
-            }
-            "high" => {
-                self.high_gain = state.gain;
-                self.prev_high = state.value;
-            }
-            "vocal" => self.vocal_gain = state.gain,
-            _ => {}
+            -frame_period / (1.0 - gain).ln()
         }
     }
-    fn calculate_band_magnitude(&self, fft_bins: &[f32], start_index: usize, end_index: usize) -> f32 {
-        let count = (end_index - start_index).min(fft_bins.len().saturating_sub(start_index));
-        if count == 0 {
-            return 0.0;
-        }
-        let sum: f32 = fft_bins[start_index..start_index + count]
+
+    /// Current output of every configured LFO
+    fn lfo_values(&self) -> Vec<NamedValue> {
+        let beats = self.beat_clock.beats();
+        self.config
+            .lfo
+            .lfos
             .iter()
-            .map(|&x| self.db_to_magnitude(x))
-            .sum();
-        sum / count as f32
-    }
-    fn use_frequency_ranges(&self, fft_bins: &[f32], fft_size: usize) -> (f32, f32, f32) {
-        let low_end_index = (fft_size as f32 * 250.0 / crate::audio::constants::CONSTANTS.sample_rate).floor() as usize;
-        let mid_end_index = (fft_size as f32 * 4000.0 / crate::audio::constants::CONSTANTS.sample_rate).floor() as usize;
-        let low = self.calculate_band_magnitude(fft_bins, 0, low_end_index);
-        let mid = self.calculate_band_magnitude(fft_bins, low_end_index, mid_end_index);
-        let high = self.calculate_band_magnitude(fft_bins, mid_end_index, fft_bins.len());
-        (low, mid, high)
-    }
-    fn calculate_beat_band_energy(&self, data: &[f32], min_freq: f32, max_freq: f32, sample_rate: f32) -> f32 {
-        let bin_width = sample_rate / (2.0 * data.len() as f32);
-        let min_bin = (min_freq / bin_width).floor() as usize;
-        let max_bin = (max_freq / bin_width).ceil() as usize;
-        let mut energy = 0.0;
-        let mut peak_energy = f32::NEG_INFINITY;
-        let mut bin_count = 0;
-        for i in min_bin..max_bin.min(data.len()) {
-            let db = data[i].clamp(
-                crate::audio::constants::CONSTANTS.beat_detection_parameters.db_min,
-                crate::audio::constants::CONSTANTS.beat_detection_parameters.db_max,
-            );
-            let magnitude = 10.0f32.powf(db / 10.0);
-            energy += magnitude;
-            peak_energy = peak_energy.max(magnitude);
-            bin_count += 1;
-        }
-        if bin_count == 0 {
-            return 0.0;
-        }
-        let avg_energy = energy / bin_count as f32;
-        let combined_energy = peak_energy * 0.7 + avg_energy * 0.3;
-        let scale = if max_freq >= crate::audio::constants::CONSTANTS.beat_detection_parameters.hihat_freq_range.min {
-            crate::audio::constants::CONSTANTS.beat_detection_parameters.hihat_energy_scale
-        } else {
-            crate::audio::constants::CONSTANTS.beat_detection_parameters.energy_scale
-        };
-        (combined_energy * scale).powf(0.8).min(1.0)
-    }
-    fn calculate_beat_rms(&self, data: &[f32]) -> f32 {
-        let sum: f32 = data.iter().map(|&x| x * x).sum();
-        (sum / data.len() as f32).sqrt()
+            .map(|definition| NamedValue {
+                name: definition.name.clone(),
+                value: lfo::evaluate(definition, beats, self.config.lfo.beats_per_bar) as f64,
+            })
+            .collect()
     }
+
     fn calculate_spectral_flux(&self, current_bins: &[f32], prev_bins: Option<&[f32]>) -> f32 {
         if let Some(prev) = prev_bins {
             let min_length = current_bins.len().min(prev.len());
@@ -1211,43 +1400,37 @@ impl AudioProcessor {
         }
     }
     fn update_beat_detection(&mut self, kick_state: GainState, snare_state: GainState, hihat_state: GainState, is_audio_active: bool, now: f64) -> BeatDetectionState {
-        let kick_average = self.kick_average * crate::audio::constants::CONSTANTS.beat_detection_parameters.beat_alpha
-            + kick_state.value * (1.0 - crate::audio::constants::CONSTANTS.beat_detection_parameters.beat_alpha);
-        let snare_average = self.snare_average * crate::audio::constants::CONSTANTS.beat_detection_parameters.beat_alpha
-            + snare_state.value * (1.0 - crate::audio::constants::CONSTANTS.beat_detection_parameters.beat_alpha);
-        let hihat_average = self.hihat_average * crate::audio::constants::CONSTANTS.beat_detection_parameters.beat_alpha
-            + hihat_state.value * (1.0 - crate::audio::constants::CONSTANTS.beat_detection_parameters.beat_alpha);
-        let kick_ratio = kick_state.value / kick_average.max(crate::audio::constants::CONSTANTS.noise_floor);
-        let snare_ratio = snare_state.value / snare_average.max(crate::audio::constants::CONSTANTS.noise_floor);
-        let hihat_ratio = hihat_state.value / hihat_average.max(crate::audio::constants::CONSTANTS.noise_floor);
-        let combined_ratio = kick_ratio * 0.6 + snare_ratio * 0.3 + hihat_ratio * 0.1;
+        let beat = &self.config.beat;
+        let noise_floor = self.config.noise_floor;
+        let kick_average = self.kick_average * beat.beat_alpha + kick_state.value * (1.0 - beat.beat_alpha);
+        let snare_average = self.snare_average * beat.beat_alpha + snare_state.value * (1.0 - beat.beat_alpha);
+        let hihat_average = self.hihat_average * beat.beat_alpha + hihat_state.value * (1.0 - beat.beat_alpha);
+        let kick_ratio = kick_state.value / kick_average.max(noise_floor);
+        let snare_ratio = snare_state.value / snare_average.max(noise_floor);
+        let hihat_ratio = hihat_state.value / hihat_average.max(noise_floor);
+        let combined_ratio = kick_ratio * beat.kick_weight + snare_ratio * beat.snare_weight + hihat_ratio * beat.hihat_weight;
         let time_since_last_beat = now - self.last_beat_time;
         let is_beat_candidate = is_audio_active
-            && combined_ratio > crate::audio::constants::CONSTANTS.beat_detection_parameters.beat_threshold
-            && self.spectral_flux > crate::audio::constants::CONSTANTS.beat_detection_parameters.spectral_flux_threshold
-            && time_since_last_beat > crate::audio::constants::CONSTANTS.beat_detection_parameters.min_beat_interval as f64;
+            && combined_ratio > beat.beat_threshold
+            && self.spectral_flux > beat.spectral_flux_threshold
+            && time_since_last_beat > beat.min_beat_interval as f64;
         self.kick_average = kick_average;
         self.snare_average = snare_average;
         self.hihat_average = hihat_average;
         BeatDetectionState {
-            kick_average,
-            snare_average,
-            hihat_average,
             is_beat_candidate,
             combined_ratio,
             time_since_last_beat,
         }
     }
     fn update_beat_intensity(&mut self, is_beat_candidate: bool, combined_ratio: f32, time_since_last_beat: f64) -> f32 {
+        let beat = &self.config.beat;
         let intensity = if is_beat_candidate {
-            (self.beat_intensity
-                * (1.0 - crate::audio::constants::CONSTANTS.beat_detection_parameters.beat_decay_rate * time_since_last_beat as f32)
-                + combined_ratio * 0.2)
-                .clamp(crate::audio::constants::CONSTANTS.beat_detection_parameters.min_beat_intensity, 1.0)
+            (self.beat_intensity * (1.0 - beat.beat_decay_rate * time_since_last_beat as f32) + combined_ratio * 0.2)
+                .clamp(beat.min_beat_intensity, 1.0)
         } else {
-            (self.beat_intensity
-                * (1.0 - crate::audio::constants::CONSTANTS.beat_detection_parameters.beat_decay_rate * 0.5 * time_since_last_beat as f32))
-                .max(crate::audio::constants::CONSTANTS.beat_detection_parameters.min_beat_intensity)
+            (self.beat_intensity * (1.0 - beat.beat_decay_rate * 0.5 * time_since_last_beat as f32))
+                .max(beat.min_beat_intensity)
         };
         self.beat_intensity = intensity;
         intensity
@@ -1255,8 +1438,8 @@ impl AudioProcessor {
     fn update_beat_times(&mut self, now: f64, is_beat_candidate: bool) -> Vec<f64> {
         if is_beat_candidate {
             self.beat_times.push(now);
-            self.beat_times
-                .retain(|&time| now - time < crate::audio::constants::CONSTANTS.history_window_size.beat_time_window as f64);
+            let window = self.config.history.beat_time_window as f64;
+            self.beat_times.retain(|&time| now - time < window);
             self.last_beat_time = now;
         }
         self.beat_times.clone()
@@ -1283,15 +1466,16 @@ impl AudioProcessor {
     }
     fn update_bps(&mut self) -> f32 {
         let instant_bps = self.calculate_instant_bps();
-        self.bps = self.bps * (1.0 - crate::audio::constants::CONSTANTS.beat_detection_parameters.bps_smoothing_factor)
-            + instant_bps * crate::audio::constants::CONSTANTS.beat_detection_parameters.bps_smoothing_factor;
+        let smoothing = self.config.beat.bps_smoothing_factor;
+        self.bps = self.bps * (1.0 - smoothing) + instant_bps * smoothing;
         self.bps
     }
     fn get_harmonic_score(&self, fft_bins: &[f32]) -> f32 {
-        let bin_width = crate::audio::constants::CONSTANTS.sample_rate / (2.0 * crate::audio::constants::CONSTANTS.frequency_data_buffer_size as f32);
-        let min_bin = (crate::audio::constants::CONSTANTS.vocal_freq_min / bin_width).floor() as usize;
-        let max_bin = (crate::audio::constants::CONSTANTS.vocal_freq_max / bin_width).ceil() as usize;
-        let slice = &fft_bins[min_bin..max_bin.min(fft_bins.len())];
+        let vocal = &self.config.vocal;
+        let bin_width = self.loudness.sample_rate() as f32 / (2.0 * fft_bins.len().max(1) as f32);
+        let min_bin = ((vocal.freq_min / bin_width).floor() as usize).min(fft_bins.len());
+        let max_bin = ((vocal.freq_max / bin_width).ceil() as usize).clamp(min_bin, fft_bins.len());
+        let slice = &fft_bins[min_bin..max_bin];
         let max_amplitude = slice.iter().copied().fold(f32::NEG_INFINITY, f32::max);
         let normalized_fft: Vec<f32> = slice
             .iter()
@@ -1300,17 +1484,17 @@ impl AudioProcessor {
         let mut harmonic_count = 0;
         for fundamental_bin in 0..(max_bin - min_bin) / 2 {
             let fundamental_freq = (fundamental_bin + min_bin) as f32 * bin_width;
-            if normalized_fft.get(fundamental_bin).copied().unwrap_or(0.0) > crate::audio::constants::CONSTANTS.harmonic_threshold {
+            if normalized_fft.get(fundamental_bin).copied().unwrap_or(0.0) > vocal.harmonic_threshold {
                 let mut harmonics_found = 0;
-                for harmonic in (2..=crate::audio::constants::CONSTANTS.harmonic_count + 1).step_by(2) {
+                for harmonic in (2..=vocal.harmonic_count + 1).step_by(2) {
                     let harmonic_bin = ((fundamental_freq * harmonic as f32) / bin_width).round() as usize;
                     if harmonic_bin < normalized_fft.len()
-                        && normalized_fft[harmonic_bin] > crate::audio::constants::CONSTANTS.harmonic_threshold
+                        && normalized_fft[harmonic_bin] > vocal.harmonic_threshold
                     {
                         harmonics_found += 1;
                     }
                 }
-                if harmonics_found >= crate::audio::constants::CONSTANTS.harmonic_count / 2 {
+                if harmonics_found >= vocal.harmonic_count / 2 {
                     harmonic_count += 1;
                 }
             }
@@ -1322,13 +1506,16 @@ impl AudioProcessor {
         }
     }
     fn calculate_mid_variance(&self) -> f32 {
-        let values = self
-            .mid_history
+        let Some(mid) = self.band_trackers.iter().find(|tracker| tracker.name() == "mid") else {
+            return 0.0;
+        };
+        let values = mid
+            .history
             .buffer
             .values()
             .iter()
             .copied()
-            .take(crate::audio::constants::CONSTANTS.history_window_size.vocal_variance_window)
+            .take(self.config.history.vocal_variance_window)
             .collect::<Vec<f32>>();
         if values.len() < 2 {
             return 0.0;
@@ -1339,7 +1526,7 @@ impl AudioProcessor {
             .map(|&x| (x - mean).powi(2))
             .sum::<f32>()
             / values.len() as f32;
-        (variance / crate::audio::constants::CONSTANTS.vocal_max_variance).min(1.0)
+        (variance / self.config.vocal.max_variance).min(1.0)
     }
     /// Compute N logarithmic frequency bands from FFT data, quantize to u8 (0-255), with rolling max normalization
     fn compute_quantized_bands_log_rolling(
@@ -1352,17 +1539,17 @@ impl AudioProcessor {
         let max_freq = sample_rate / 2.0;
         let n_bins = frequency_data.len();
         let mut bands = vec![0.0; num_bands];
-        for band in 0..num_bands {
+        for (band, band_value) in bands.iter_mut().enumerate() {
             let start_freq = min_freq * (max_freq / min_freq).powf(band as f32 / num_bands as f32);
             let end_freq = min_freq * (max_freq / min_freq).powf((band + 1) as f32 / num_bands as f32);
             let start_bin = ((start_freq / max_freq) * n_bins as f32).floor() as usize;
             let end_bin = ((end_freq / max_freq) * n_bins as f32).ceil() as usize;
             let slice = &frequency_data[start_bin.min(n_bins)..end_bin.min(n_bins)];
-            bands[band] = if slice.is_empty() { 0.0 } else { slice.iter().copied().sum::<f32>() / slice.len() as f32 };
+            *band_value = if slice.is_empty() { 0.0 } else { slice.iter().copied().sum::<f32>() / slice.len() as f32 };
         }
         // Rolling max normalization
         let max_val = bands.iter().cloned().fold(0.0, f32::max);
-        let alpha = 0.05; // Smoothing factor for rolling max
+        let alpha = self.config.quantized_band_alpha;
         self.rolling_max_bands = self.rolling_max_bands * (1.0 - alpha) + max_val * alpha;
         let norm = self.rolling_max_bands.max(1e-6); // Avoid divide by zero
         let bands_normalized: Vec<f32> = bands.iter().map(|&b| (b / norm).clamp(0.0, 1.0)).collect();
@@ -1373,15 +1560,12 @@ impl AudioProcessor {
     /// Calculate amplitude for video speed control (0-1 range)
     /// Goal: 0 = quiet/silent, 0.5 = typical audio level, 1 = very busy/loud
     fn calculate_video_amplitude(&mut self, raw_amplitude: f32) -> f32 {
-        const SILENCE_THRESHOLD: f32 = 0.0001;
-        const BASELINE_ADAPTATION_RATE: f32 = 0.001;
-        const PEAK_ADAPTATION_RATE: f32 = 0.01;
-        const ACTIVITY_SMOOTHING_RATE: f32 = 0.1;
-        const MAX_SILENCE_FRAMES: i32 = 100;
+        let params = self.config.amplitude.clone();
+        let silence_threshold = self.silence_threshold();
         
-        if raw_amplitude > SILENCE_THRESHOLD {
-            self.amplitude_baseline = self.amplitude_baseline * (1.0 - BASELINE_ADAPTATION_RATE) 
-                + raw_amplitude * BASELINE_ADAPTATION_RATE;
+        if raw_amplitude > silence_threshold {
+            self.amplitude_baseline = self.amplitude_baseline * (1.0 - params.baseline_adaptation_rate) 
+                + raw_amplitude * params.baseline_adaptation_rate;
             self.silence_counter = 0;
         } else {
             self.silence_counter += 1;
@@ -1390,23 +1574,23 @@ impl AudioProcessor {
         if raw_amplitude > self.amplitude_peak_tracker {
             self.amplitude_peak_tracker = raw_amplitude;
         } else {
-            self.amplitude_peak_tracker = self.amplitude_peak_tracker * 0.995;
+            self.amplitude_peak_tracker *= params.peak_tracker_decay;
         }
         
-        let activity_ratio = if self.amplitude_baseline > SILENCE_THRESHOLD {
+        let activity_ratio = if self.amplitude_baseline > silence_threshold {
             raw_amplitude / self.amplitude_baseline
         } else {
             0.0
         };
         
-        self.activity_smoothing = self.activity_smoothing * (1.0 - ACTIVITY_SMOOTHING_RATE) 
-            + activity_ratio * ACTIVITY_SMOOTHING_RATE;
+        self.activity_smoothing = self.activity_smoothing * (1.0 - params.activity_smoothing_rate) 
+            + activity_ratio * params.activity_smoothing_rate;
         
-        if self.silence_counter >= MAX_SILENCE_FRAMES {
+        if self.silence_counter >= params.max_silence_frames {
             return 0.0;
         }
         
-        let normalized_amplitude = if raw_amplitude <= SILENCE_THRESHOLD {
+        let normalized_amplitude = if raw_amplitude <= silence_threshold {
             0.0
         } else {
             let log_activity = (activity_ratio + 0.1).ln();
@@ -1423,7 +1607,7 @@ impl AudioProcessor {
         };
         
         let silence_fade = if self.silence_counter > 0 {
-            (1.0 - (self.silence_counter as f32 / MAX_SILENCE_FRAMES as f32)).max(0.0)
+            (1.0 - (self.silence_counter as f32 / params.max_silence_frames as f32)).max(0.0)
         } else {
             1.0
         };
@@ -1515,6 +1699,75 @@ impl From<&crate::state::PrimaryFreq530State> for ProtoState {
             mid_high_balance: s.mid_high_balance,
             onset_strength: s.onset_strength,
             spectrogram_data: s.spectrogram_data.clone(),
+            momentary_lufs: s.momentary_lufs,
+            short_term_lufs: s.short_term_lufs,
+            integrated_lufs: s.integrated_lufs,
+            loudness_range: s.loudness_range,
+            true_peak_dbtp: s.true_peak_dbtp,
+            stereo_correlation: s.stereo_correlation,
+            stereo_width: s.stereo_width,
+            mid_side_ratio: s.mid_side_ratio,
+            stereo_pan: s.stereo_pan,
+            left_band_energies: s.left_band_energies.clone(),
+            right_band_energies: s.right_band_energies.clone(),
+            band_pan: s.band_pan.clone(),
+            spectral_rolloff_85: s.spectral_rolloff_85,
+            spectral_rolloff_95: s.spectral_rolloff_95,
+            spectral_flatness: s.spectral_flatness,
+            spectral_bandwidth: s.spectral_bandwidth,
+            spectral_contrast: s.spectral_contrast.clone(),
+            zero_crossing_rate: s.zero_crossing_rate,
+            spectral_rolloff_85_dynamic: s.spectral_rolloff_85_dynamic,
+            spectral_rolloff_95_dynamic: s.spectral_rolloff_95_dynamic,
+            spectral_flatness_dynamic: s.spectral_flatness_dynamic,
+            spectral_bandwidth_dynamic: s.spectral_bandwidth_dynamic,
+            spectral_contrast_dynamic: s.spectral_contrast_dynamic.clone(),
+            zero_crossing_rate_dynamic: s.zero_crossing_rate_dynamic,
+            bands: s
+                .bands
+                .iter()
+                .map(|band| proto_mod::BandState {
+                    name: band.name.clone(),
+                    value: band.value,
+                    dynamic: band.dynamic,
+                    velocity: band.velocity,
+                    peak_hold: band.peak_hold,
+                    log: band.log,
+                })
+                .collect(),
+            playback_state: s.playback_state.clone(),
+            events: s
+                .events
+                .iter()
+                .map(|event| proto_mod::AnalysisEvent {
+                    kind: event.kind.clone(),
+                    time: event.time,
+                    from: event.from.clone(),
+                    to: event.to.clone(),
+                    duration: event.duration,
+                    confidence: event.confidence,
+                })
+                .collect(),
+            buildup_tension: s.buildup_tension,
+            section_index: s.section_index,
+            section_label: s.section_label,
+            beat_clock: s.beat_clock,
+            lfos: s
+                .lfos
+                .iter()
+                .map(|lfo| proto_mod::NamedValue { name: lfo.name.clone(), value: lfo.value })
+                .collect(),
+            custom: s
+                .custom
+                .iter()
+                .map(|field| proto_mod::NamedValue { name: field.name.clone(), value: field.value })
+                .collect(),
+            sequence: s.sequence,
+            capture_time: s.capture_time,
+            next_beat_time: s.next_beat_time,
+            device_status: s.device_status.clone(),
+            // Stamped by the stream's pipeline
+            stream: String::new(),
         }
     }
 }
@@ -1526,19 +1779,62 @@ pub mod proto_mod {
 }
 pub use proto_mod::PrimaryFreq530State as ProtoState;
 
-fn dynamic_normalize(value: f32, history: &HistoryState) -> f32 {
-    let values = history.buffer.values();
-    let n = values.len() as f32;
-    if n < 2.0 {
-        return 0.5;
+fn playback_event(transition: &PlaybackTransition) -> AnalysisEvent {
+    AnalysisEvent {
+        kind: "playback".to_string(),
+        time: transition.time,
+        from: transition.from.as_str().to_string(),
+        to: transition.to.as_str().to_string(),
+        duration: transition.duration,
+        confidence: 1.0,
+    }
+}
+
+fn structure_event(event: &StructureEvent) -> AnalysisEvent {
+    AnalysisEvent {
+        kind: event.kind.as_str().to_string(),
+        time: event.time,
+        from: String::new(),
+        to: String::new(),
+        duration: event.duration,
+        confidence: event.confidence as f64,
     }
-    let mean = values.iter().sum::<f32>() / n;
-    let std = (values.iter().map(|&x| (x - mean).powi(2)).sum::<f32>() / n).sqrt().max(1e-6);
-    let z = (value - mean) / std;
-    1.0 / (1.0 + (-z).exp())
 }
 
-fn dynamic_normalize_with_sharpness(value: f32, history: &HistoryState, sharpness: f32) -> f32 {
+fn section_event(boundary: &SectionBoundary) -> AnalysisEvent {
+    AnalysisEvent {
+        kind: "section".to_string(),
+        time: boundary.time,
+        from: boundary.previous_label.to_string(),
+        to: boundary.label.to_string(),
+        duration: boundary.duration,
+        confidence: boundary.novelty.clamp(0.0, 1.0) as f64,
+    }
+}
+
+fn trigger_event(trigger: &TriggerEvent) -> AnalysisEvent {
+    AnalysisEvent {
+        kind: "trigger".to_string(),
+        time: trigger.time,
+        from: String::new(),
+        to: trigger.name.clone(),
+        duration: trigger.interval,
+        confidence: 1.0,
+    }
+}
+
+fn band_state(name: &str, output: &BandOutput) -> BandState {
+    BandState {
+        name: name.to_string(),
+        value: output.value as f64,
+        dynamic: output.dynamic as f64,
+        velocity: output.velocity as f64,
+        peak_hold: output.peak_hold as f64,
+        log: output.log as f64,
+    }
+}
+
+pub fn dynamic_normalize_with_sharpness(value: f32, history: &HistoryState, sharpness: f32) -> f32 {
     let values = history.buffer.values();
     let n = values.len() as f32;
     if n < 2.0 {
@@ -1548,4 +1844,7 @@ fn dynamic_normalize_with_sharpness(value: f32, history: &HistoryState, sharpnes
     let std = (values.iter().map(|&x| (x - mean).powi(2)).sum::<f32>() / n).sqrt().max(1e-6);
     let z = sharpness * (value - mean) / std;
     1.0 / (1.0 + (-z).exp())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,187 @@
+// Regression tests: the processor driven with generated test signals, as the capture thread
+// would drive it, with assertions on the fields the visuals depend on.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+use crate::audio::generator::{note_frequency, Generator, Program, GENERATOR_SAMPLE_RATE};
+use crate::state::{PrimaryFreq530State, SCALAR_FIELDS};
+use super::*;
+
+// The rate the generator plays at, as in production
+const SAMPLE_RATE: u32 = GENERATOR_SAMPLE_RATE;
+const FFT_SIZE: usize = 1024;
+// 60 analysis frames per second, matching the `delta_time` the capture thread passes
+const HOP: usize = SAMPLE_RATE as usize / 60;
+const DELTA_TIME: f32 = 1.0 / 60.0;
+
+/// A processor fed by a generator, one analysis frame at a time on a simulated clock
+struct Harness {
+    processor: AudioProcessor,
+    generator: Generator,
+    fft: Arc<dyn Fft<f32>>,
+    window: VecDeque<f32>,
+    now: f64,
+}
+
+impl Harness {
+    fn new(program: &str) -> Self {
+        let mut processor = AudioProcessor::with_config(ProcessorConfig::default());
+        processor.set_input_format(SAMPLE_RATE, 2);
+        Harness {
+            processor,
+            generator: generator(program),
+            fft: FftPlanner::new().plan_fft_forward(FFT_SIZE),
+            window: VecDeque::from(vec![0.0; FFT_SIZE]),
+            now: 1_000.0,
+        }
+    }
+
+    /// Play a new program from the current state
+    fn play(&mut self, program: &str) {
+        self.generator = generator(program);
+    }
+
+    /// Analyze one hop of audio and return the emitted frame
+    fn step(&mut self) -> PrimaryFreq530State {
+        let mut block = vec![0.0; HOP * 2];
+        self.generator.fill(&mut block, 2);
+        self.processor.process_samples(&block);
+        for frame in block.chunks_exact(2) {
+            self.window.pop_front();
+            self.window.push_back((frame[0] + frame[1]) / 2.0);
+        }
+        let mut bins: Vec<Complex<f32>> = self.window.iter().map(|&sample| Complex::new(sample, 0.0)).collect();
+        self.fft.process(&mut bins);
+        let magnitudes: Vec<f32> = bins.iter().take(FFT_SIZE / 2).map(|c| c.norm()).collect();
+        self.now += HOP as f64 / SAMPLE_RATE as f64;
+        self.processor.update_base_state(DELTA_TIME, &magnitudes, self.now)
+    }
+
+    /// Frames emitted over `seconds`
+    fn run(&mut self, seconds: f64) -> Vec<PrimaryFreq530State> {
+        let steps = (seconds * 60.0).round() as usize;
+        (0..steps).map(|_| self.step()).collect()
+    }
+}
+
+fn generator(program: &str) -> Generator {
+    Generator::new(Program::parse(program).unwrap(), SAMPLE_RATE)
+}
+
+fn argmax(values: &[f64]) -> usize {
+    values
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .map_or(0, |(i, _)| i)
+}
+
+#[test]
+fn low_tone_lands_in_low_and_kick() {
+    let mut harness = Harness::new("tone:100");
+    let frames = harness.run(3.0);
+    let state = frames.last().unwrap();
+    assert!(state.low > state.mid && state.low > state.high, "low {} mid {} high {}", state.low, state.mid, state.high);
+    // Kick and snare are neighbours: each transient band follows its own maximum, and the
+    // unwindowed FFT leaks a steady tone into both, so only the far hihat band is compared
+    assert!(state.kick > state.hihat, "kick {} hihat {}", state.kick, state.hihat);
+
+    // A high tone leaves the kick dark
+    let mut harness = Harness::new("tone:6000");
+    let frames = harness.run(3.0);
+    let state = frames.last().unwrap();
+    assert!(state.kick < 0.01 && state.hihat > 0.1, "kick {} hihat {}", state.kick, state.hihat);
+}
+
+#[test]
+fn silence_drives_amplitude_to_zero() {
+    let mut harness = Harness::new("pink");
+    let frames = harness.run(5.0);
+    assert!(frames.last().unwrap().amplitude > 0.1, "pink noise should register");
+
+    // The level is cut after `max_silence_frames` silent frames; allow the envelope a second to settle
+    harness.play("silence");
+    let limit = harness.processor.config.amplitude.max_silence_frames as usize + 60;
+    let silent_after = (1..=limit * 2).find(|_| harness.step().amplitude < 1e-3);
+    let silent_after = silent_after.expect("amplitude never reached 0 in silence");
+    assert!(silent_after <= limit, "amplitude reached 0 after {} frames, expected at most {}", silent_after, limit);
+
+    // And stays there
+    let frames = harness.run(2.0);
+    assert!(frames.iter().all(|state| state.amplitude < 1e-3));
+}
+
+#[test]
+fn clicks_at_120_bpm_give_two_beats_per_second() {
+    let mut harness = Harness::new("clicks:120");
+    let frames = harness.run(20.0);
+    let bps = frames.last().unwrap().bps;
+    assert!((bps - 2.0).abs() < 0.1, "bps {}", bps);
+}
+
+#[test]
+fn drums_at_128_bpm_follow_the_tempo() {
+    let mut harness = Harness::new("drums:128");
+    let frames = harness.run(20.0);
+    let bps = frames.last().unwrap().bps;
+    assert!((bps - 128.0 / 60.0).abs() < 0.15, "bps {}", bps);
+}
+
+#[test]
+fn chromagram_peaks_on_the_played_pitch_class() {
+    for (note, pitch_class) in [("A5", 9), ("C6", 0), ("E6", 4), ("G6", 7)] {
+        let mut harness = Harness::new(&format!("tone:{}", note_frequency(note).unwrap()));
+        let frames = harness.run(1.0);
+        let chromagram = &frames.last().unwrap().chromagram;
+        assert_eq!(chromagram.len(), 12);
+        assert_eq!(argmax(chromagram), pitch_class, "{}: {:?}", note, chromagram);
+    }
+}
+
+#[test]
+fn chord_lights_up_its_pitch_classes() {
+    let mut harness = Harness::new("chord:C6+E6+G6");
+    let frames = harness.run(1.0);
+    let chromagram = &frames.last().unwrap().chromagram;
+    let mut ranked: Vec<usize> = (0..12).collect();
+    ranked.sort_by(|&a, &b| chromagram[b].total_cmp(&chromagram[a]));
+    let mut top = ranked[..3].to_vec();
+    top.sort();
+    assert_eq!(top, vec![0, 4, 7], "{:?}", chromagram);
+}
+
+#[test]
+fn outputs_stay_finite() {
+    let mut harness = Harness::new("silence@1,tone:100@1,sweep:20-20000:3@3,white@1,pink@1,clicks:120@2,drums:128@3,chord:C4+E4+G4@1,silence@3");
+    let frames = harness.run(16.0);
+    assert!(!frames.is_empty());
+    for state in &frames {
+        for &field in SCALAR_FIELDS {
+            let value = state.scalar(field).unwrap();
+            assert!(value.is_finite(), "{} is {} at t={}", field, value, state.time);
+        }
+        let vectors = [
+            ("chromagram", &state.chromagram),
+            ("frequency_grid_map", &state.frequency_grid_map),
+            ("spectrogram_data", &state.spectrogram_data),
+            ("spectral_contrast", &state.spectral_contrast),
+            ("spectral_contrast_dynamic", &state.spectral_contrast_dynamic),
+            ("left_band_energies", &state.left_band_energies),
+            ("right_band_energies", &state.right_band_energies),
+            ("band_pan", &state.band_pan),
+            ("beat_times", &state.beat_times),
+        ];
+        for (name, values) in vectors {
+            assert!(values.iter().all(|value| value.is_finite()), "{} has {:?} at t={}", name, values, state.time);
+        }
+        for band in &state.bands {
+            let values = [band.value, band.dynamic, band.velocity, band.peak_hold, band.log];
+            assert!(values.iter().all(|value| value.is_finite()), "band {} is {:?}", band.name, values);
+        }
+        for value in state.lfos.iter().chain(&state.custom) {
+            assert!(value.value.is_finite(), "{} is {}", value.name, value.value);
+        }
+    }
+}
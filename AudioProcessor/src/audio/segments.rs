@@ -0,0 +1,380 @@
+// Section segmentation (verse/chorus/bridge) from a self-similarity novelty curve over
+// chroma and spectral contrast. `SectionTracker` is the causal live variant: it sees a
+// boundary `kernel_blocks` blocks late. `segment_offline` runs over a whole file with a
+// centred kernel. Both label sections by similarity so a repeated chorus gets the same ID.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+use serde::{Deserialize, Serialize};
+use crate::audio::spectral::{self, CONTRAST_OCTAVES};
+
+const FEATURE_SIZE: usize = 12 + CONTRAST_OCTAVES;
+// Novelty values kept for the adaptive boundary threshold
+const NOVELTY_HISTORY: usize = 64;
+// FFT size used for offline analysis (same as the live input path)
+const OFFLINE_FFT_SIZE: usize = 1024;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SegmentConfig {
+    /// Seconds of frames averaged into one feature block
+    pub block_secs: f32,
+    /// Half-width of the checkerboard kernel in blocks; also the live detection delay
+    pub kernel_blocks: usize,
+    /// Novelty must exceed mean + this many standard deviations of recent novelty
+    pub threshold_std: f32,
+    /// Absolute novelty floor for a boundary
+    pub min_novelty: f32,
+    /// Shortest section in seconds
+    pub min_section_secs: f32,
+    /// Cosine similarity at which a section counts as a repeat of an earlier one
+    pub label_similarity: f32,
+}
+
+impl Default for SegmentConfig {
+    fn default() -> Self {
+        SegmentConfig {
+            block_secs: 0.5,
+            kernel_blocks: 8,
+            threshold_std: 1.5,
+            min_novelty: 0.1,
+            min_section_secs: 8.0,
+            label_similarity: 0.92,
+        }
+    }
+}
+
+/// A section found by offline analysis
+#[derive(Debug, Clone, Serialize)]
+pub struct Segment {
+    pub start: f64,
+    pub end: f64,
+    pub label: u32,
+    /// Novelty at the boundary that opened the section (0 for the first one)
+    pub novelty: f32,
+}
+
+/// A section boundary found by the live tracker
+#[derive(Debug, Clone)]
+pub struct SectionBoundary {
+    pub time: f64,
+    pub previous_label: u32,
+    pub label: u32,
+    /// Length of the section that just ended
+    pub duration: f64,
+    pub novelty: f32,
+}
+
+/// Averages per-frame features into fixed-length blocks
+struct BlockBuilder {
+    sum: Vec<f32>,
+    frames: usize,
+    elapsed: f32,
+    started: f64,
+}
+
+impl BlockBuilder {
+    fn new() -> Self {
+        BlockBuilder { sum: vec![0.0; FEATURE_SIZE], frames: 0, elapsed: 0.0, started: 0.0 }
+    }
+
+    /// Add a frame; returns the finished block and its start time once `block_secs` have passed
+    fn push(&mut self, chroma: &[f32], contrast: &[f32], delta_time: f32, now: f64, block_secs: f32) -> Option<(f64, Vec<f32>)> {
+        if self.frames == 0 {
+            self.started = now;
+        }
+        for (sum, &value) in self.sum.iter_mut().zip(chroma.iter().chain(contrast.iter())) {
+            *sum += value;
+        }
+        self.frames += 1;
+        self.elapsed += delta_time;
+        if self.elapsed < block_secs {
+            return None;
+        }
+        let block = self.sum.iter().map(|&sum| sum / self.frames as f32).collect();
+        let started = self.started;
+        *self = BlockBuilder::new();
+        Some((started, block))
+    }
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    match (norm_a > 1e-9, norm_b > 1e-9) {
+        (true, true) => dot / (norm_a * norm_b),
+        // Two silent blocks are alike; silence and sound are not
+        (false, false) => 1.0,
+        _ => 0.0,
+    }
+}
+
+/// Gaussian-tapered checkerboard kernel over the self-similarity of `blocks` around the
+/// boundary before `center`, scaled to 0-1; zero where the kernel doesn't fit
+fn checkerboard_novelty(blocks: &[Vec<f32>], center: usize, half: usize) -> f32 {
+    if center < half || center + half > blocks.len() {
+        return 0.0;
+    }
+    let (start, end) = (center - half, center + half);
+    let sigma = half as f32 * 0.5;
+    let taper = |i: usize| {
+        let distance = i as f32 + 0.5 - center as f32;
+        (-(distance * distance) / (2.0 * sigma * sigma)).exp()
+    };
+    let mut score = 0.0;
+    let mut total = 0.0;
+    for i in start..end {
+        for j in start..end {
+            let weight = taper(i) * taper(j);
+            let same_side = (i < center) == (j < center);
+            let similarity = cosine_similarity(&blocks[i], &blocks[j]);
+            score += if same_side { weight } else { -weight } * similarity;
+            total += weight;
+        }
+    }
+    // Within-side similarity minus cross similarity; fully dissimilar halves give 0.5
+    if total > 0.0 { (2.0 * score / total).clamp(0.0, 1.0) } else { 0.0 }
+}
+
+fn mean_block<'a>(blocks: impl Iterator<Item = &'a Vec<f32>>) -> Vec<f32> {
+    let mut mean = vec![0.0; FEATURE_SIZE];
+    let mut count = 0;
+    for block in blocks {
+        for (m, &value) in mean.iter_mut().zip(block.iter()) {
+            *m += value;
+        }
+        count += 1;
+    }
+    for m in mean.iter_mut() {
+        *m /= count.max(1) as f32;
+    }
+    mean
+}
+
+/// Section signatures seen so far; a new section reuses the ID of the most similar one
+struct SectionLabels {
+    prototypes: Vec<(Vec<f32>, usize)>,
+}
+
+impl SectionLabels {
+    fn new() -> Self {
+        SectionLabels { prototypes: Vec::new() }
+    }
+
+    /// Existing ID whose signature is similar enough, or the next unused ID
+    fn classify(&self, signature: &[f32], threshold: f32) -> u32 {
+        self.prototypes
+            .iter()
+            .enumerate()
+            .map(|(id, (prototype, _))| (id, cosine_similarity(prototype, signature)))
+            .filter(|&(_, similarity)| similarity >= threshold)
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+            .map_or(self.prototypes.len() as u32, |(id, _)| id as u32)
+    }
+
+    /// Fold a finished section into its label's signature
+    fn learn(&mut self, label: u32, signature: &[f32], blocks: usize) {
+        match self.prototypes.get_mut(label as usize) {
+            Some((prototype, count)) => {
+                let total = (*count + blocks).max(1) as f32;
+                for (p, &value) in prototype.iter_mut().zip(signature.iter()) {
+                    *p = (*p * *count as f32 + value * blocks as f32) / total;
+                }
+                *count += blocks;
+            }
+            None => self.prototypes.push((signature.to_vec(), blocks)),
+        }
+    }
+}
+
+fn mean_and_std(values: &VecDeque<f32>) -> (f32, f32) {
+    let n = values.len().max(1) as f32;
+    let mean = values.iter().sum::<f32>() / n;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / n;
+    (mean, variance.sqrt())
+}
+
+/// Causal segmentation of the live stream
+pub struct SectionTracker {
+    builder: BlockBuilder,
+    // (start time, features) of the last 2 * kernel_blocks blocks
+    window: VecDeque<(f64, Vec<f32>)>,
+    novelty_history: VecDeque<f32>,
+    // Novelty of the previous two steps, for peak picking one step late
+    previous_novelty: [f32; 2],
+    labels: SectionLabels,
+    section_index: u32,
+    label: u32,
+    section_started: Option<f64>,
+    section_sum: Vec<f32>,
+    section_blocks: usize,
+}
+
+impl SectionTracker {
+    pub fn new() -> Self {
+        SectionTracker {
+            builder: BlockBuilder::new(),
+            window: VecDeque::new(),
+            novelty_history: VecDeque::new(),
+            previous_novelty: [0.0; 2],
+            labels: SectionLabels::new(),
+            section_index: 0,
+            label: 0,
+            section_started: None,
+            section_sum: vec![0.0; FEATURE_SIZE],
+            section_blocks: 0,
+        }
+    }
+
+    /// Number of boundaries seen so far
+    pub fn section_index(&self) -> u32 {
+        self.section_index
+    }
+
+    /// ID of the current section; repeats of the same material share an ID
+    pub fn label(&self) -> u32 {
+        self.label
+    }
+
+    /// Advance by one frame; returns a boundary when one is confirmed
+    pub fn update(&mut self, chroma: &[f32], contrast: &[f32], delta_time: f32, now: f64, config: &SegmentConfig) -> Option<SectionBoundary> {
+        let (block_start, block) = self.builder.push(chroma, contrast, delta_time, now, config.block_secs)?;
+        self.section_started.get_or_insert(block_start);
+        let half = config.kernel_blocks.max(2);
+        self.window.push_back((block_start, block));
+        while self.window.len() > 2 * half {
+            self.window.pop_front();
+        }
+        if self.window.len() < 2 * half {
+            return None;
+        }
+
+        let blocks: Vec<Vec<f32>> = self.window.iter().map(|(_, block)| block.clone()).collect();
+        let novelty = checkerboard_novelty(&blocks, half, half);
+        let [before, peak] = self.previous_novelty;
+        self.previous_novelty = [peak, novelty];
+        let (mean, std) = mean_and_std(&self.novelty_history);
+        let first_window = self.novelty_history.is_empty();
+        let history_full = self.novelty_history.len() >= half;
+        self.novelty_history.push_back(novelty);
+        if self.novelty_history.len() > NOVELTY_HISTORY {
+            self.novelty_history.pop_front();
+        }
+
+        // The previous step's centre sat between what are now blocks half-2 and half-1,
+        // so everything up to half-2 belongs to the current section
+        let committed = if first_window { 0 } else { half - 2 };
+        for block in &blocks[committed..=half - 2] {
+            for (sum, &value) in self.section_sum.iter_mut().zip(block.iter()) {
+                *sum += value;
+            }
+            self.section_blocks += 1;
+        }
+
+        let boundary_time = self.window[half - 1].0;
+        let section_started = self.section_started.unwrap_or(boundary_time);
+        let is_peak = peak > before && peak >= novelty;
+        let significant = history_full && peak >= config.min_novelty && peak > mean + config.threshold_std * std;
+        let long_enough = boundary_time - section_started >= config.min_section_secs as f64;
+        if !(is_peak && significant && long_enough) {
+            return None;
+        }
+
+        let finished: Vec<f32> = self.section_sum.iter().map(|&sum| sum / self.section_blocks.max(1) as f32).collect();
+        self.labels.learn(self.label, &finished, self.section_blocks);
+        let signature = mean_block(blocks[half - 1..].iter());
+        let previous_label = self.label;
+        self.label = self.labels.classify(&signature, config.label_similarity);
+        self.section_index += 1;
+        self.section_sum = vec![0.0; FEATURE_SIZE];
+        self.section_blocks = 0;
+        self.section_started = Some(boundary_time);
+        Some(SectionBoundary {
+            time: boundary_time,
+            previous_label,
+            label: self.label,
+            duration: boundary_time - section_started,
+            novelty: peak,
+        })
+    }
+}
+
+/// Segment a whole recording (mono samples) with a centred kernel and label repeats
+pub fn segment_offline(samples: &[f32], sample_rate: u32, config: &SegmentConfig) -> Vec<Segment> {
+    let mut planner = FftPlanner::<f32>::new();
+    let fft: Arc<dyn rustfft::Fft<f32>> = planner.plan_fft_forward(OFFLINE_FFT_SIZE);
+    let bin_width = sample_rate as f32 / OFFLINE_FFT_SIZE as f32;
+    let delta_time = OFFLINE_FFT_SIZE as f32 / sample_rate as f32;
+
+    let mut builder = BlockBuilder::new();
+    let mut starts = Vec::new();
+    let mut blocks = Vec::new();
+    let mut buffer = vec![Complex::new(0.0, 0.0); OFFLINE_FFT_SIZE];
+    for (frame_index, frame) in samples.chunks(OFFLINE_FFT_SIZE).enumerate() {
+        for (i, slot) in buffer.iter_mut().enumerate() {
+            *slot = Complex::new(frame.get(i).copied().unwrap_or(0.0), 0.0);
+        }
+        fft.process(&mut buffer);
+        let magnitudes: Vec<f32> = buffer.iter().take(OFFLINE_FFT_SIZE / 2).map(|c| c.norm()).collect();
+        let chroma = spectral::chromagram(&magnitudes, bin_width);
+        let contrast = spectral::spectral_contrast(&magnitudes, bin_width);
+        let now = frame_index as f64 * delta_time as f64;
+        if let Some((start, block)) = builder.push(&chroma, &contrast, delta_time, now, config.block_secs) {
+            starts.push(start);
+            blocks.push(block);
+        }
+    }
+    let total = samples.len() as f64 / sample_rate as f64;
+    if blocks.is_empty() {
+        return vec![Segment { start: 0.0, end: total, label: 0, novelty: 0.0 }];
+    }
+
+    let half = config.kernel_blocks.max(2);
+    let novelty: Vec<f32> = (0..blocks.len()).map(|center| checkerboard_novelty(&blocks, center, half)).collect();
+    let n = novelty.len() as f32;
+    let mean = novelty.iter().sum::<f32>() / n;
+    let std = (novelty.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / n).sqrt();
+    let threshold = (mean + config.threshold_std * std).max(config.min_novelty);
+
+    // Local maxima within the kernel, strongest first, kept if far enough from those already chosen
+    let mut candidates: Vec<usize> = (1..blocks.len())
+        .filter(|&i| {
+            let lo = i.saturating_sub(half);
+            let hi = (i + half + 1).min(novelty.len());
+            novelty[i] >= threshold && novelty[lo..hi].iter().all(|&v| v <= novelty[i])
+        })
+        .collect();
+    candidates.sort_by(|&a, &b| novelty[b].partial_cmp(&novelty[a]).unwrap_or(std::cmp::Ordering::Equal));
+    let mut boundaries: Vec<usize> = Vec::new();
+    for candidate in candidates {
+        let time = starts[candidate];
+        let clear_of_others = boundaries.iter().all(|&b| (starts[b] - time).abs() >= config.min_section_secs as f64);
+        let clear_of_ends = time >= config.min_section_secs as f64 && total - time >= config.min_section_secs as f64;
+        if clear_of_others && clear_of_ends {
+            boundaries.push(candidate);
+        }
+    }
+    boundaries.sort_unstable();
+
+    let mut edges = vec![0];
+    edges.extend(boundaries);
+    edges.push(blocks.len());
+    let mut labels = SectionLabels::new();
+    let mut segments = Vec::new();
+    for pair in edges.windows(2) {
+        let (from, to) = (pair[0], pair[1]);
+        let signature = mean_block(blocks[from..to].iter());
+        let label = labels.classify(&signature, config.label_similarity);
+        labels.learn(label, &signature, to - from);
+        segments.push(Segment {
+            start: if from == 0 { 0.0 } else { starts[from] },
+            end: if to == blocks.len() { total } else { starts[to] },
+            label,
+            novelty: if from == 0 { 0.0 } else { novelty[from] },
+        });
+    }
+    segments
+}
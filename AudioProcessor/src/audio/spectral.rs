@@ -0,0 +1,151 @@
+// Timbre descriptors computed from the magnitude spectrum and the time-domain buffer
+
+use crate::audio::processor::{dynamic_normalize_with_sharpness, HistoryState};
+
+/// Number of octave sub-bands used for spectral contrast (starting at CONTRAST_MIN_FREQ)
+pub const CONTRAST_OCTAVES: usize = 6;
+const CONTRAST_MIN_FREQ: f32 = 200.0;
+// Fraction of bins per sub-band averaged for the peak and valley estimates
+const CONTRAST_QUANTILE: f32 = 0.2;
+// Peak/valley differences at or above this many dB map to 1.0
+const CONTRAST_RANGE_DB: f32 = 40.0;
+
+/// Frequency (as a fraction of Nyquist) below which `fraction` of the spectral energy lies
+pub fn spectral_rolloff(magnitudes: &[f32], fraction: f32) -> f32 {
+    let total: f32 = magnitudes.iter().map(|m| m * m).sum();
+    if total <= 1e-12 || magnitudes.is_empty() {
+        return 0.0;
+    }
+    let threshold = total * fraction;
+    let mut cumulative = 0.0;
+    for (i, m) in magnitudes.iter().enumerate() {
+        cumulative += m * m;
+        if cumulative >= threshold {
+            return ((i as f32 + 0.5) / magnitudes.len() as f32).clamp(0.0, 1.0);
+        }
+    }
+    1.0
+}
+
+/// Wiener entropy: geometric / arithmetic mean of the power spectrum (0 = tonal, 1 = white noise)
+pub fn spectral_flatness(magnitudes: &[f32]) -> f32 {
+    if magnitudes.is_empty() {
+        return 0.0;
+    }
+    let n = magnitudes.len() as f32;
+    let arithmetic = magnitudes.iter().map(|m| m * m).sum::<f32>() / n;
+    if arithmetic <= 1e-12 {
+        return 0.0;
+    }
+    let log_mean = magnitudes.iter().map(|m| (m * m).max(1e-12).ln()).sum::<f32>() / n;
+    (log_mean.exp() / arithmetic).clamp(0.0, 1.0)
+}
+
+/// Magnitude-weighted standard deviation around the centroid, as a fraction of Nyquist (scaled to 0-1)
+pub fn spectral_bandwidth(magnitudes: &[f32], centroid_normalized: f32) -> f32 {
+    let sum: f32 = magnitudes.iter().sum();
+    if sum <= 1e-6 || magnitudes.is_empty() {
+        return 0.0;
+    }
+    let n = magnitudes.len() as f32;
+    let variance = magnitudes
+        .iter()
+        .enumerate()
+        .map(|(i, m)| {
+            let freq = (i as f32 + 0.5) / n;
+            m * (freq - centroid_normalized).powi(2)
+        })
+        .sum::<f32>()
+        / sum;
+    // The spread of a spectrum on [0, 1] can't exceed 0.5
+    (variance.sqrt() * 2.0).clamp(0.0, 1.0)
+}
+
+/// Peak-to-valley contrast per octave sub-band, normalized to 0-1
+pub fn spectral_contrast(magnitudes: &[f32], bin_width: f32) -> Vec<f32> {
+    let mut contrast = vec![0.0; CONTRAST_OCTAVES];
+    for (octave, value) in contrast.iter_mut().enumerate() {
+        let low = CONTRAST_MIN_FREQ * 2f32.powi(octave as i32);
+        let high = low * 2.0;
+        let start = ((low / bin_width).floor() as usize).min(magnitudes.len());
+        let end = ((high / bin_width).ceil() as usize).min(magnitudes.len());
+        if end <= start {
+            continue;
+        }
+        let mut band: Vec<f32> = magnitudes[start..end].to_vec();
+        band.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        let count = ((band.len() as f32 * CONTRAST_QUANTILE).round() as usize).max(1);
+        let valley = band[..count].iter().sum::<f32>() / count as f32;
+        let peak = band[band.len() - count..].iter().sum::<f32>() / count as f32;
+        if peak <= 1e-9 {
+            continue;
+        }
+        let contrast_db = 20.0 * (peak.max(1e-9) / valley.max(1e-9)).log10();
+        *value = (contrast_db / CONTRAST_RANGE_DB).clamp(0.0, 1.0);
+    }
+    contrast
+}
+
+/// Fraction of adjacent sample pairs that change sign
+pub fn zero_crossing_rate(samples: &[f32]) -> f32 {
+    if samples.len() < 2 {
+        return 0.0;
+    }
+    let crossings = samples
+        .windows(2)
+        .filter(|w| (w[0] >= 0.0) != (w[1] >= 0.0))
+        .count();
+    crossings as f32 / (samples.len() - 1) as f32
+}
+
+/// Energy per pitch class (C, C#, ... B), normalized so the strongest class is 1
+pub fn chromagram(magnitudes: &[f32], bin_width: f32) -> [f32; 12] {
+    let mut chroma = [0.0f32; 12];
+    let reference_freq_a4 = 440.0f32;
+    // Bin 0 is DC; bin i is centred on i x bin_width
+    for (i, &magnitude) in magnitudes.iter().enumerate().skip(1) {
+        if magnitude <= 1e-6 {
+            continue;
+        }
+        let freq = i as f32 * bin_width;
+        let midi_note = 69.0 + 12.0 * (freq / reference_freq_a4).log2();
+        if midi_note < 0.0 {
+            continue;
+        }
+        chroma[(midi_note.round() as usize) % 12] += magnitude;
+    }
+    let max = chroma.iter().cloned().fold(0.0f32, f32::max);
+    if max > 1e-6 {
+        for value in chroma.iter_mut() {
+            *value /= max;
+        }
+    }
+    chroma
+}
+
+/// History-based dynamic normalization for a 0-1 descriptor, matching the band `_dynamic` fields
+pub struct DescriptorTracker {
+    pub history: HistoryState,
+    pub dynamic_smoothed: f32,
+}
+
+impl DescriptorTracker {
+    pub fn new(history_size: usize) -> Self {
+        DescriptorTracker {
+            history: HistoryState::new(history_size),
+            dynamic_smoothed: 0.5,
+        }
+    }
+
+    pub fn resize(&mut self, history_size: usize) {
+        self.history.resize(history_size);
+    }
+
+    /// Push a new value and return the smoothed dynamic variant
+    pub fn update(&mut self, value: f32, sharpness: f32, smoothing: f32) -> f32 {
+        self.history.buffer.push(value);
+        let dynamic = dynamic_normalize_with_sharpness(value, &self.history, sharpness);
+        self.dynamic_smoothed = self.dynamic_smoothed * smoothing + dynamic * (1.0 - smoothing);
+        self.dynamic_smoothed
+    }
+}
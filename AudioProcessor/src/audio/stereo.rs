@@ -0,0 +1,201 @@
+// Stereo image analysis: per-channel band energies, L/R correlation, mid/side width and per-band panning
+
+use std::sync::Arc;
+use rustfft::{Fft, FftPlanner, num_complex::Complex};
+use crate::audio::bands::{self, BandDefinition};
+
+const STEREO_WINDOW: usize = 1024;
+const STEREO_SMOOTHING: f32 = 0.2;
+const STEREO_MAX_DECAY: f32 = 0.999;
+
+/// Smoothed stereo measurements for one frame
+#[derive(Debug, Clone)]
+pub struct StereoImage {
+    /// Pearson correlation of left and right (-1 = out of phase, 0 = unrelated, 1 = mono)
+    pub correlation: f32,
+    /// Side RMS divided by mid RMS (0 = mono, 1 = equal mid and side, >1 = mostly side)
+    pub mid_side_ratio: f32,
+    /// Side energy share of the total, 0 (mono) to 1 (fully wide/out of phase)
+    pub width: f32,
+    /// Overall pan position, -1 (left) to 1 (right)
+    pub pan: f32,
+    /// Normalized left channel band energies, one per configured band
+    pub left_bands: Vec<f32>,
+    /// Normalized right channel band energies, one per configured band
+    pub right_bands: Vec<f32>,
+    /// Pan position per band, -1 (left) to 1 (right)
+    pub band_pan: Vec<f32>,
+}
+
+impl StereoImage {
+    fn centered(band_count: usize) -> Self {
+        StereoImage {
+            correlation: 1.0,
+            mid_side_ratio: 0.0,
+            width: 0.0,
+            pan: 0.0,
+            left_bands: vec![0.0; band_count],
+            right_bands: vec![0.0; band_count],
+            band_pan: vec![0.0; band_count],
+        }
+    }
+}
+
+pub struct StereoAnalyzer {
+    sample_rate: f32,
+    channels: usize,
+    left: Vec<f32>,
+    right: Vec<f32>,
+    write_index: usize,
+    fft: Arc<dyn Fft<f32>>,
+    window: Vec<f32>,
+    bands: Vec<(f32, f32)>,
+    band_max: Vec<f32>,
+    image: StereoImage,
+}
+
+impl StereoAnalyzer {
+    pub fn new(sample_rate: u32, channels: u16, definitions: &[BandDefinition]) -> Self {
+        let mut planner = FftPlanner::<f32>::new();
+        let window = (0..STEREO_WINDOW)
+            .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / (STEREO_WINDOW - 1) as f32).cos())
+            .collect();
+        let mut analyzer = StereoAnalyzer {
+            sample_rate: sample_rate.max(1) as f32,
+            channels: channels.max(1) as usize,
+            left: vec![0.0; STEREO_WINDOW],
+            right: vec![0.0; STEREO_WINDOW],
+            write_index: 0,
+            fft: planner.plan_fft_forward(STEREO_WINDOW),
+            window,
+            bands: Vec::new(),
+            band_max: Vec::new(),
+            image: StereoImage::centered(0),
+        };
+        analyzer.set_bands(definitions);
+        analyzer
+    }
+
+    /// Follow the processor's band layout; per-band state restarts when the layout changes
+    pub fn set_bands(&mut self, definitions: &[BandDefinition]) {
+        self.bands = definitions.iter().map(|band| (band.min_hz, band.max_hz)).collect();
+        self.band_max = vec![1e-6; self.bands.len()];
+        self.image = StereoImage::centered(self.bands.len());
+    }
+
+    pub fn is_stereo(&self) -> bool {
+        self.channels >= 2
+    }
+
+    /// Append interleaved samples; the first two channels are treated as left and right
+    pub fn push(&mut self, interleaved: &[f32]) {
+        for frame in interleaved.chunks_exact(self.channels) {
+            let left = frame[0];
+            let right = if self.channels >= 2 { frame[1] } else { frame[0] };
+            self.left[self.write_index] = left;
+            self.right[self.write_index] = right;
+            self.write_index = (self.write_index + 1) % STEREO_WINDOW;
+        }
+    }
+
+    /// Analyze the most recent window and return the smoothed stereo image
+    pub fn analyze(&mut self) -> StereoImage {
+        if !self.is_stereo() {
+            // Mono input: both sides carry the same signal, so mirror the band energies
+            let bands = self.band_energies(true);
+            let normalized = self.normalize_bands(&bands, &bands);
+            self.image = StereoImage {
+                left_bands: smooth_vec(&self.image.left_bands, &normalized.0),
+                right_bands: smooth_vec(&self.image.right_bands, &normalized.1),
+                ..StereoImage::centered(self.bands.len())
+            };
+            return self.image.clone();
+        }
+
+        let (mut sum_lr, mut sum_ll, mut sum_rr, mut mid_energy, mut side_energy) = (0.0f32, 0.0f32, 0.0f32, 0.0f32, 0.0f32);
+        for (&l, &r) in self.left.iter().zip(self.right.iter()) {
+            sum_lr += l * r;
+            sum_ll += l * l;
+            sum_rr += r * r;
+            let mid = 0.5 * (l + r);
+            let side = 0.5 * (l - r);
+            mid_energy += mid * mid;
+            side_energy += side * side;
+        }
+        let correlation = if sum_ll > 1e-12 && sum_rr > 1e-12 {
+            (sum_lr / (sum_ll * sum_rr).sqrt()).clamp(-1.0, 1.0)
+        } else {
+            1.0
+        };
+        let mid_side_ratio = if mid_energy > 1e-12 {
+            (side_energy / mid_energy).sqrt()
+        } else if side_energy > 1e-12 {
+            // Pure side content (fully out of phase)
+            10.0
+        } else {
+            0.0
+        };
+        let width = if mid_energy + side_energy > 1e-12 { side_energy / (mid_energy + side_energy) } else { 0.0 };
+        let pan = pan_position(sum_ll, sum_rr);
+
+        let left_bands = self.band_energies(true);
+        let right_bands = self.band_energies(false);
+        let band_pan: Vec<f32> = left_bands.iter().zip(right_bands.iter()).map(|(&l, &r)| pan_position(l, r)).collect();
+        let (left_normalized, right_normalized) = self.normalize_bands(&left_bands, &right_bands);
+
+        let previous = &self.image;
+        self.image = StereoImage {
+            correlation: smooth(previous.correlation, correlation),
+            mid_side_ratio: smooth(previous.mid_side_ratio, mid_side_ratio),
+            width: smooth(previous.width, width),
+            pan: smooth(previous.pan, pan),
+            left_bands: smooth_vec(&previous.left_bands, &left_normalized),
+            right_bands: smooth_vec(&previous.right_bands, &right_normalized),
+            band_pan: smooth_vec(&previous.band_pan, &band_pan),
+        };
+        self.image.clone()
+    }
+
+    /// Mean magnitude per band for one channel, using a Hann window over the ring buffer
+    fn band_energies(&self, left: bool) -> Vec<f32> {
+        let source = if left { &self.left } else { &self.right };
+        let mut buffer: Vec<Complex<f32>> = (0..STEREO_WINDOW)
+            .map(|i| {
+                let sample = source[(self.write_index + i) % STEREO_WINDOW];
+                Complex::new(sample * self.window[i], 0.0)
+            })
+            .collect();
+        self.fft.process(&mut buffer);
+        let bin_width = self.sample_rate / STEREO_WINDOW as f32;
+        let magnitudes: Vec<f32> = buffer.iter().take(STEREO_WINDOW / 2).map(|c| c.norm()).collect();
+        self.bands
+            .iter()
+            .map(|&(min_hz, max_hz)| bands::band_mean(&magnitudes, bin_width, min_hz, max_hz))
+            .collect()
+    }
+
+    /// Normalize both channels against a shared, slowly decaying per-band maximum so L/R stay comparable
+    fn normalize_bands(&mut self, left: &[f32], right: &[f32]) -> (Vec<f32>, Vec<f32>) {
+        for (i, max) in self.band_max.iter_mut().enumerate() {
+            *max = (*max * STEREO_MAX_DECAY).max(left[i]).max(right[i]).max(1e-6);
+        }
+        let normalize = |values: &[f32]| -> Vec<f32> {
+            values.iter().zip(self.band_max.iter()).map(|(&v, &max)| (v / max).clamp(0.0, 1.0)).collect()
+        };
+        (normalize(left), normalize(right))
+    }
+}
+
+/// Pan position from left/right energy: -1 = hard left, 0 = centre, 1 = hard right
+fn pan_position(left: f32, right: f32) -> f32 {
+    let total = left + right;
+    if total > 1e-12 { ((right - left) / total).clamp(-1.0, 1.0) } else { 0.0 }
+}
+
+fn smooth(previous: f32, current: f32) -> f32 {
+    previous * (1.0 - STEREO_SMOOTHING) + current * STEREO_SMOOTHING
+}
+
+fn smooth_vec(previous: &[f32], current: &[f32]) -> Vec<f32> {
+    previous.iter().zip(current.iter()).map(|(&p, &c)| smooth(p, c)).collect()
+}
@@ -0,0 +1,176 @@
+// Named analysis streams: each runs its own capture and processor, e.g. a DJ mixer feed next to
+// a vocal mic. Clients pick the streams they receive; frames carry the stream's name.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use serde::Deserialize;
+use crate::audio::channels::{ChannelMap, ChannelSelection};
+use crate::audio::generator::Program;
+use crate::audio::input::InputHandle;
+use crate::audio::processor::AudioProcessor;
+use crate::websocket::stats::SharedLatency;
+
+/// Name of the only stream when no streams file is given
+pub const DEFAULT_STREAM: &str = "main";
+
+/// One entry of the streams file, e.g. `{ "name": "vocals", "device": "USB Mic", "preset": "vocal" }`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StreamSpec {
+    pub name: String,
+    /// Input device (exact name or part of one); the device chosen at startup when absent
+    #[serde(default)]
+    pub device: Option<String>,
+    /// Device to use while `device` is unavailable
+    #[serde(default)]
+    pub fallback: Option<String>,
+    /// Processor config file for this stream
+    #[serde(default)]
+    pub config: Option<String>,
+    /// Preset for this stream; without `config` or `preset` the startup config is used
+    #[serde(default)]
+    pub preset: Option<String>,
+    /// Device channels to analyze; all of them when absent
+    #[serde(default)]
+    pub channels: Option<ChannelSelection>,
+    /// Test-signal program played instead of a device, e.g. `"clicks:120@4,silence@1"`
+    #[serde(default)]
+    pub generate: Option<String>,
+}
+
+impl StreamSpec {
+    /// The single stream used without a streams file
+    pub fn single() -> Self {
+        StreamSpec {
+            name: DEFAULT_STREAM.to_string(),
+            device: None,
+            fallback: None,
+            config: None,
+            preset: None,
+            channels: None,
+            generate: None,
+        }
+    }
+
+    pub fn channel_map(&self) -> ChannelMap {
+        ChannelMap::new(self.channels.as_ref())
+    }
+}
+
+/// Replace every split stream by one single-channel stream per channel, named `<stream>-<channel>`
+pub fn expand_splits(streams: Vec<StreamSpec>) -> Vec<StreamSpec> {
+    streams
+        .into_iter()
+        .flat_map(|stream| match &stream.channels {
+            Some(ChannelSelection::Split(channels)) => channels
+                .iter()
+                .map(|&channel| StreamSpec {
+                    name: format!("{}-{}", stream.name, channel),
+                    channels: Some(ChannelSelection::Pick(vec![channel])),
+                    ..stream.clone()
+                })
+                .collect(),
+            _ => vec![stream],
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+pub enum StreamsError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for StreamsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamsError::Io(e) => write!(f, "failed to read streams file: {}", e),
+            StreamsError::Parse(e) => write!(f, "failed to parse streams file: {}", e),
+            StreamsError::Invalid(msg) => write!(f, "invalid streams file: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for StreamsError {}
+
+/// Names must be unique and usable in file names; a stream takes a config or a preset, not both,
+/// and a device or a generator
+pub fn validate_streams(streams: &[StreamSpec]) -> Result<(), StreamsError> {
+    if streams.is_empty() {
+        return Err(StreamsError::Invalid("at least one stream is required".to_string()));
+    }
+    for (i, stream) in streams.iter().enumerate() {
+        if stream.name.is_empty() {
+            return Err(StreamsError::Invalid(format!("stream #{} has an empty name", i)));
+        }
+        if !stream.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(StreamsError::Invalid(format!(
+                "stream name '{}' may only use letters, digits, '-' and '_'",
+                stream.name
+            )));
+        }
+        if streams[..i].iter().any(|other| other.name == stream.name) {
+            return Err(StreamsError::Invalid(format!("duplicate stream name '{}'", stream.name)));
+        }
+        if stream.config.is_some() && stream.preset.is_some() {
+            return Err(StreamsError::Invalid(format!("stream '{}' has both a config and a preset", stream.name)));
+        }
+        if let Some(program) = &stream.generate {
+            if stream.device.is_some() || stream.fallback.is_some() {
+                return Err(StreamsError::Invalid(format!(
+                    "stream '{}' has both a device and a generator",
+                    stream.name
+                )));
+            }
+            Program::parse(program).map_err(|msg| StreamsError::Invalid(format!("stream '{}': {}", stream.name, msg)))?;
+        }
+        if let Some(selection) = &stream.channels {
+            selection
+                .validate()
+                .map_err(|msg| StreamsError::Invalid(format!("stream '{}': {}", stream.name, msg)))?;
+        }
+    }
+    Ok(())
+}
+
+/// Load a JSON stream list, either `[...]` or `{ "streams": [...] }`; split streams are expanded
+pub fn load_streams(path: &Path) -> Result<Vec<StreamSpec>, StreamsError> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StreamsFile {
+        List(Vec<StreamSpec>),
+        Wrapped { streams: Vec<StreamSpec> },
+    }
+    let text = fs::read_to_string(path).map_err(StreamsError::Io)?;
+    let streams = match serde_json::from_str(&text).map_err(StreamsError::Parse)? {
+        StreamsFile::List(streams) => streams,
+        StreamsFile::Wrapped { streams } => streams,
+    };
+    validate_streams(&streams)?;
+    let streams = expand_splits(streams);
+    // Expanded names may collide with configured ones
+    validate_streams(&streams)?;
+    Ok(streams)
+}
+
+/// `calibration.json` becomes `calibration.<stream>.json`, so each stream warm-starts from its own state
+pub fn per_stream_path(path: &Path, stream: &str) -> PathBuf {
+    let stem = path.file_stem().map_or_else(|| "calibration".into(), |stem| stem.to_string_lossy());
+    let name = match path.extension() {
+        Some(extension) => format!("{}.{}.{}", stem, stream, extension.to_string_lossy()),
+        None => format!("{}.{}", stem, stream),
+    };
+    path.with_file_name(name)
+}
+
+/// A running stream, as seen by the control handlers and the TUI
+pub struct StreamHandle {
+    pub name: String,
+    pub processor: Arc<Mutex<AudioProcessor>>,
+    /// The stream's capture; absent when its input cannot be switched
+    pub input: Option<InputHandle>,
+    pub latency: SharedLatency,
+}
@@ -0,0 +1,257 @@
+// Structural detection for dance music: tracks build-up tension from long-window energy,
+// low-band presence, spectral centroid rise and snare-roll onset density, and reports
+// drops and breakdowns with a confidence.
+
+use std::collections::VecDeque;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StructureConfig {
+    /// Time constant (seconds) of the fast feature averages
+    pub short_window: f32,
+    /// Time constant (seconds) of the slow feature averages; also the warm-up before events fire
+    pub long_window: f32,
+    /// Time constant (seconds) of the tension smoothing
+    pub tension_smoothing: f32,
+    /// Snare dynamic value that counts as a snare onset
+    pub snare_onset_threshold: f32,
+    /// Seconds of snare onsets counted for the roll density
+    pub density_window: f32,
+    /// Snare onsets per second that count as a full roll
+    pub roll_density: f32,
+    /// Relative centroid rise (fast over slow) that counts as full tension
+    pub centroid_rise: f32,
+    pub centroid_weight: f32,
+    pub density_weight: f32,
+    pub energy_weight: f32,
+    pub low_absence_weight: f32,
+    /// Recent peak tension needed before a drop can fire
+    pub drop_tension: f32,
+    /// Fast/slow low-band ratio that marks the bass coming back in
+    pub drop_low_ratio: f32,
+    /// Seconds a build-up peak stays eligible for a drop
+    pub drop_window: f32,
+    /// Fast/slow energy ratio below which a breakdown can fire
+    pub breakdown_energy_ratio: f32,
+    /// Fast/slow low-band ratio below which a breakdown can fire
+    pub breakdown_low_ratio: f32,
+    /// Minimum seconds between structural events
+    pub cooldown: f32,
+}
+
+impl Default for StructureConfig {
+    fn default() -> Self {
+        StructureConfig {
+            short_window: 0.5,
+            long_window: 8.0,
+            tension_smoothing: 1.5,
+            snare_onset_threshold: 0.7,
+            density_window: 2.0,
+            roll_density: 8.0,
+            centroid_rise: 0.5,
+            centroid_weight: 0.35,
+            density_weight: 0.35,
+            energy_weight: 0.15,
+            low_absence_weight: 0.15,
+            drop_tension: 0.4,
+            drop_low_ratio: 1.6,
+            drop_window: 4.0,
+            breakdown_energy_ratio: 0.5,
+            breakdown_low_ratio: 0.4,
+            cooldown: 8.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StructureEventKind {
+    Drop,
+    Breakdown,
+}
+
+impl StructureEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StructureEventKind::Drop => "drop",
+            StructureEventKind::Breakdown => "breakdown",
+        }
+    }
+}
+
+/// A detected drop or breakdown
+#[derive(Debug, Clone)]
+pub struct StructureEvent {
+    pub kind: StructureEventKind,
+    pub time: f64,
+    /// For a drop, how long the build-up lasted; for a breakdown, how long the previous section lasted
+    pub duration: f64,
+    pub confidence: f32,
+}
+
+/// Per-frame inputs, taken from features the processor already computes
+pub struct StructureFeatures {
+    pub energy: f32,
+    pub low: f32,
+    /// Normalized spectral centroid (0-1)
+    pub centroid: f32,
+    /// History-normalized snare band value (0-1)
+    pub snare_dynamic: f32,
+    /// False outside of `PlaybackState::Playing`; tension falls and no events fire
+    pub playing: bool,
+}
+
+pub struct StructureDetector {
+    fast_energy: f32,
+    slow_energy: f32,
+    fast_low: f32,
+    slow_low: f32,
+    fast_centroid: f32,
+    slow_centroid: f32,
+    slow_density: f32,
+    snare_above: bool,
+    snare_onsets: VecDeque<f64>,
+    tension: f32,
+    peak_tension: f32,
+    buildup_started: Option<f64>,
+    in_breakdown: bool,
+    playing_time: f32,
+    last_event: f64,
+    section_started: Option<f64>,
+}
+
+// Exponential smoothing coefficient for time constant `tau`
+fn ema_alpha(delta_time: f32, tau: f32) -> f32 {
+    1.0 - (-delta_time / tau.max(1e-3)).exp()
+}
+
+fn ratio(fast: f32, slow: f32) -> f32 {
+    if slow > 1e-9 { fast / slow } else { 1.0 }
+}
+
+impl StructureDetector {
+    pub fn new() -> Self {
+        StructureDetector {
+            fast_energy: 0.0,
+            slow_energy: 0.0,
+            fast_low: 0.0,
+            slow_low: 0.0,
+            fast_centroid: 0.0,
+            slow_centroid: 0.0,
+            slow_density: 0.0,
+            snare_above: false,
+            snare_onsets: VecDeque::new(),
+            tension: 0.0,
+            peak_tension: 0.0,
+            buildup_started: None,
+            in_breakdown: false,
+            playing_time: 0.0,
+            last_event: f64::NEG_INFINITY,
+            section_started: None,
+        }
+    }
+
+    /// Build-up tension, 0-1
+    pub fn tension(&self) -> f32 {
+        self.tension
+    }
+
+    /// Advance by one frame; returns a drop or breakdown when one is detected
+    pub fn update(&mut self, features: &StructureFeatures, delta_time: f32, now: f64, config: &StructureConfig) -> Option<StructureEvent> {
+        let fast = ema_alpha(delta_time, config.short_window);
+        let slow = ema_alpha(delta_time, config.long_window);
+        self.fast_energy += (features.energy - self.fast_energy) * fast;
+        self.slow_energy += (features.energy - self.slow_energy) * slow;
+        self.fast_low += (features.low - self.fast_low) * fast;
+        self.slow_low += (features.low - self.slow_low) * slow;
+        self.fast_centroid += (features.centroid - self.fast_centroid) * fast;
+        self.slow_centroid += (features.centroid - self.slow_centroid) * slow;
+
+        // Snare-roll density: rising edges of the snare dynamic within the window
+        let snare_above = features.snare_dynamic >= config.snare_onset_threshold;
+        if snare_above && !self.snare_above {
+            self.snare_onsets.push_back(now);
+        }
+        self.snare_above = snare_above;
+        while self.snare_onsets.front().is_some_and(|&t| now - t > config.density_window as f64) {
+            self.snare_onsets.pop_front();
+        }
+        let density = (self.snare_onsets.len() as f32 / config.density_window / config.roll_density).clamp(0.0, 1.0);
+        self.slow_density += (density - self.slow_density) * slow;
+
+        if !features.playing {
+            self.playing_time = 0.0;
+            self.tension *= 1.0 - ema_alpha(delta_time, config.tension_smoothing);
+            self.peak_tension = self.tension;
+            self.buildup_started = None;
+            self.section_started = None;
+            return None;
+        }
+        self.playing_time += delta_time;
+        let section_started = *self.section_started.get_or_insert(now);
+
+        let energy_ratio = ratio(self.fast_energy, self.slow_energy);
+        let low_ratio = ratio(self.fast_low, self.slow_low);
+        let centroid_rise = ((ratio(self.fast_centroid, self.slow_centroid) - 1.0) / config.centroid_rise).clamp(0.0, 1.0);
+        let density_rise = (density - self.slow_density).max(0.0) + density * 0.5;
+        let energy_rise = (energy_ratio - 1.0).clamp(0.0, 1.0);
+        let low_absence = (1.0 - low_ratio).clamp(0.0, 1.0);
+        let weights = config.centroid_weight + config.density_weight + config.energy_weight + config.low_absence_weight;
+        let target = (centroid_rise * config.centroid_weight
+            + density_rise.clamp(0.0, 1.0) * config.density_weight
+            + energy_rise * config.energy_weight
+            + low_absence * config.low_absence_weight)
+            / weights.max(1e-6);
+        self.tension += (target.clamp(0.0, 1.0) - self.tension) * ema_alpha(delta_time, config.tension_smoothing);
+
+        // A build-up peak stays eligible for a drop for `drop_window` seconds
+        self.peak_tension = self.tension.max(self.peak_tension - delta_time / config.drop_window.max(1e-3));
+        if self.tension >= config.drop_tension {
+            self.buildup_started.get_or_insert(now);
+        } else if self.peak_tension < config.drop_tension {
+            self.buildup_started = None;
+        }
+        if self.in_breakdown && energy_ratio > 0.8 {
+            self.in_breakdown = false;
+        }
+
+        let warmed_up = self.playing_time >= config.long_window;
+        let cooled_down = now - self.last_event >= config.cooldown as f64;
+        if !warmed_up || !cooled_down {
+            return None;
+        }
+
+        if self.peak_tension >= config.drop_tension && low_ratio >= config.drop_low_ratio && energy_ratio >= 1.0 {
+            let bass_return = ((low_ratio - 1.0) / (2.0 * (config.drop_low_ratio - 1.0).max(1e-3))).clamp(0.0, 1.0);
+            let event = StructureEvent {
+                kind: StructureEventKind::Drop,
+                time: now,
+                duration: self.buildup_started.map_or(0.0, |start| now - start),
+                confidence: (0.5 * self.peak_tension + 0.5 * bass_return).clamp(0.0, 1.0),
+            };
+            self.tension = 0.0;
+            self.peak_tension = 0.0;
+            self.buildup_started = None;
+            self.in_breakdown = false;
+            return Some(self.finish_section(event));
+        }
+
+        if !self.in_breakdown && energy_ratio <= config.breakdown_energy_ratio && low_ratio <= config.breakdown_low_ratio {
+            let event = StructureEvent {
+                kind: StructureEventKind::Breakdown,
+                time: now,
+                duration: now - section_started,
+                confidence: ((2.0 - energy_ratio - low_ratio) / 2.0).clamp(0.0, 1.0),
+            };
+            self.in_breakdown = true;
+            return Some(self.finish_section(event));
+        }
+        None
+    }
+
+    fn finish_section(&mut self, event: StructureEvent) -> StructureEvent {
+        self.last_event = event.time;
+        self.section_started = Some(event.time);
+        event
+    }
+}
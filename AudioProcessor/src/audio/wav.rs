@@ -0,0 +1,108 @@
+// Minimal WAV reader for offline analysis: RIFF/WAVE with integer PCM (8/16/24/32-bit)
+// or 32-bit float samples, downmixed to mono.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+const FORMAT_PCM: u16 = 1;
+const FORMAT_FLOAT: u16 = 3;
+const FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+#[derive(Debug)]
+pub enum WavError {
+    Io(std::io::Error),
+    Format(String),
+}
+
+impl fmt::Display for WavError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WavError::Io(e) => write!(f, "failed to read audio file: {}", e),
+            WavError::Format(msg) => write!(f, "unsupported audio file: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for WavError {}
+
+/// Decoded audio, downmixed to mono in -1..1
+pub struct MonoAudio {
+    pub sample_rate: u32,
+    pub samples: Vec<f32>,
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]])
+}
+
+pub fn read_wav(path: &Path) -> Result<MonoAudio, WavError> {
+    let bytes = fs::read(path).map_err(WavError::Io)?;
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err(WavError::Format("not a RIFF/WAVE file".to_string()));
+    }
+
+    let mut format = None;
+    let mut data = None;
+    let mut offset = 12;
+    while offset + 8 <= bytes.len() {
+        let id = &bytes[offset..offset + 4];
+        let size = read_u32(&bytes, offset + 4) as usize;
+        let body_start = offset + 8;
+        // The declared size may run past the end of a truncated file
+        let body_end = body_start.saturating_add(size).min(bytes.len());
+        match id {
+            b"fmt " => {
+                let body_len = body_end - body_start;
+                if body_len < 16 {
+                    return Err(WavError::Format(format!("fmt chunk of {} bytes, expected at least 16", body_len)));
+                }
+                let mut tag = read_u16(&bytes, body_start);
+                if tag == FORMAT_EXTENSIBLE {
+                    if body_len < 26 {
+                        return Err(WavError::Format(format!(
+                            "extensible fmt chunk of {} bytes, expected at least 26",
+                            body_len
+                        )));
+                    }
+                    // The real format tag is the first two bytes of the sub-format GUID
+                    tag = read_u16(&bytes, body_start + 24);
+                }
+                let channels = read_u16(&bytes, body_start + 2);
+                let sample_rate = read_u32(&bytes, body_start + 4);
+                let bits = read_u16(&bytes, body_start + 14);
+                format = Some((tag, channels, sample_rate, bits));
+            }
+            b"data" => data = Some(&bytes[body_start..body_end]),
+            _ => {}
+        }
+        // Chunks are padded to an even size
+        offset = body_start.saturating_add(size).saturating_add(size & 1);
+    }
+
+    let (tag, channels, sample_rate, bits) = format.ok_or_else(|| WavError::Format("missing fmt chunk".to_string()))?;
+    let data = data.ok_or_else(|| WavError::Format("missing data chunk".to_string()))?;
+    if channels == 0 || sample_rate == 0 {
+        return Err(WavError::Format("zero channels or sample rate".to_string()));
+    }
+    let decode: fn(&[u8]) -> f32 = match (tag, bits) {
+        (FORMAT_PCM, 8) => |b| (b[0] as f32 - 128.0) / 128.0,
+        (FORMAT_PCM, 16) => |b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0,
+        (FORMAT_PCM, 24) => |b| (i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8) as f32 / 8_388_608.0,
+        (FORMAT_PCM, 32) => |b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2_147_483_648.0,
+        (FORMAT_FLOAT, 32) => |b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+        _ => return Err(WavError::Format(format!("format {} with {} bits per sample", tag, bits))),
+    };
+
+    let sample_bytes = bits as usize / 8;
+    let frame_bytes = sample_bytes * channels as usize;
+    let samples = data
+        .chunks_exact(frame_bytes)
+        .map(|frame| frame.chunks_exact(sample_bytes).map(decode).sum::<f32>() / channels as f32)
+        .collect();
+    Ok(MonoAudio { sample_rate, samples })
+}
@@ -5,9 +5,31 @@ mod force_build_rs {
 mod audio {
     pub mod constants;
     pub mod processor;
+    pub mod loudness;
+    pub mod stereo;
+    pub mod spectral;
+    pub mod bands;
+    pub mod config;
+    pub mod presets;
+    pub mod calibration;
+    pub mod noise;
+    pub mod playback;
+    pub mod structure;
+    pub mod segments;
+    pub mod wav;
+    pub mod lfo;
+    pub mod modulation;
+    pub mod clock;
+    pub mod passthrough;
+    pub mod input;
+    pub mod streams;
+    pub mod channels;
+    pub mod generator;
 }
 mod websocket {
+    pub mod control;
     pub mod server;
+    pub mod stats;
 }
 mod state;
 mod tui;
@@ -18,20 +40,31 @@ use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use tokio::net::TcpListener;
 use tungstenite::Message;
 use tokio::sync::mpsc;
-use std::time::{SystemTime, UNIX_EPOCH};
 use futures_util::SinkExt;
 use crate::audio::processor::{AudioProcessor, ProtoState, DetailLevel};
+use crate::audio::config::ProcessorConfig;
+use crate::audio::presets::{PresetStore, DEFAULT_PRESET_DIR};
+use crate::websocket::control::ControlContext;
+use crate::websocket::stats::{LatencyStats, SharedLatency, Stage, STATS_INTERVAL_SECS};
+use crate::audio::calibration::{CalibrationSnapshot, CALIBRATION_SAVE_INTERVAL_SECS, DEFAULT_CALIBRATION_FILE};
+use crate::audio::noise::{NoiseProfileStore, DEFAULT_NOISE_PROFILE_FILE};
+use crate::audio::passthrough::{DelayLine, DelaySetting};
+use crate::audio::input::{find_input_device, InputError};
+use crate::audio::generator::{spawn_generator, Generator, Program, GENERATOR_CHANNELS, GENERATOR_SAMPLE_RATE};
+use crate::audio::streams::{StreamHandle, StreamSpec};
+use crate::audio::channels::ChannelMap;
 use std::io::{self, Write};
-use rustfft::{FftPlanner, num_complex::Complex, num_traits::Zero};
-use std::sync::atomic::{AtomicU64, Ordering};
-use prost::Message as ProstMessage;
+use std::sync::atomic::AtomicU64;
 
 const SPECTROGRAM_WIDTH: usize = 256;
 const SPECTROGRAM_HEIGHT: usize = 64;
+// Extra time --calibrate-noise waits for the inputs beyond the requested measurement
+const NOISE_CALIBRATION_GRACE_SECS: f32 = 5.0;
 
 pub struct AppConfig {
     pub detail_level: DetailLevel,
-    pub input_device: cpal::Device,
+    /// Absent when every stream names its own device or plays a generator
+    pub input_device: Option<cpal::Device>,
     pub update_period: f64,
     pub show_tui: bool,
 }
@@ -129,7 +162,7 @@ fn select_input_device() -> cpal::Device {
         }
         
         println!("Invalid selection, defaulting to Loopback");
-        return loopback;
+        loopback
     } else {
         println!("\nNo Loopback Audio device found.");
         println!("Available input devices:");
@@ -155,7 +188,7 @@ fn select_input_device() -> cpal::Device {
         }
         
         println!("Invalid selection, defaulting to first device");
-        return devices[0].clone();
+        devices[0].clone()
     }
 }
 
@@ -221,7 +254,7 @@ fn select_display_mode() -> bool {
     }
 }
 
-fn quick_setup() -> Option<AppConfig> {
+fn quick_setup(needs_device: bool) -> Option<AppConfig> {
     println!("AudioProcessor WebSocket Server");
     println!("================================");
     print!("Use quick setup with defaults? [Y/n]: ");
@@ -235,16 +268,18 @@ fn quick_setup() -> Option<AppConfig> {
         println!("Using quick setup defaults:");
         
         // Try to find loopback, fallback to first device
-        let device = if let Some(loopback) = find_loopback_device() {
+        let device = if !needs_device {
+            None
+        } else if let Some(loopback) = find_loopback_device() {
             println!("  - Audio Input: Loopback Audio");
-            loopback
+            Some(loopback)
         } else {
             let host = cpal::default_host();
             let devices: Vec<_> = host.input_devices().unwrap().collect();
             if !devices.is_empty() {
                 let device_name = devices[0].name().unwrap_or_else(|_| "Unknown".to_string());
                 println!("  - Audio Input: {} (default microphone)", device_name);
-                devices[0].clone()
+                Some(devices[0].clone())
             } else {
                 panic!("No input devices available");
             }
@@ -266,14 +301,14 @@ fn quick_setup() -> Option<AppConfig> {
     None
 }
 
-fn setup_configuration() -> AppConfig {
-    if let Some(config) = quick_setup() {
+fn setup_configuration(needs_device: bool) -> AppConfig {
+    if let Some(config) = quick_setup(needs_device) {
         return config;
     }
     
     println!("\nCustom setup:");
     let detail_level = select_detail_level();
-    let input_device = select_input_device();
+    let input_device = needs_device.then(select_input_device);
     let update_period = select_update_rate();
     let show_tui = select_display_mode();
     
@@ -285,10 +320,218 @@ fn setup_configuration() -> AppConfig {
     }
 }
 
+/// Value following `flag` on the command line, e.g. `--bands bands.json`
+fn cli_value(flag: &str) -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            return args.next();
+        }
+        if let Some(value) = arg.strip_prefix(flag).and_then(|rest| rest.strip_prefix('=')) {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+fn cli_flag(flag: &str) -> bool {
+    std::env::args().skip(1).any(|arg| arg == flag)
+}
+
+fn save_calibration(processor: &Mutex<AudioProcessor>, path: &std::path::Path) {
+    let snapshot = processor.lock().unwrap().calibration_snapshot();
+    if let Err(e) = snapshot.save(path) {
+        eprintln!("Could not save calibration: {}", e);
+    }
+}
+
+/// Persist a new or cleared noise profile for the current device
+fn save_noise_profile(processor: &Mutex<AudioProcessor>, store: &NoiseProfileStore, device: &str) {
+    let profile = {
+        let mut processor = processor.lock().unwrap();
+        if !processor.noise_profile_changed {
+            return;
+        }
+        processor.noise_profile_changed = false;
+        processor.noise_profile.clone()
+    };
+    if let Err(e) = store.put(device, profile.as_ref()) {
+        eprintln!("Could not save noise profile: {}", e);
+    }
+}
+
+/// Persist a stream's adaptive state and any new noise profile for its device
+fn save_stream(stream: &StreamHandle, calibration_path: &std::path::Path, noise_store: &NoiseProfileStore) {
+    save_calibration(&stream.processor, calibration_path);
+    if let Some(input) = &stream.input {
+        save_noise_profile(&stream.processor, noise_store, &input.profile_key());
+    }
+}
+
+/// What a stream captures from: an input device or the test-signal generator
+struct SourceFormat {
+    sample_rate: u32,
+    /// Interleaved channels, before the stream's channel selection
+    channels: usize,
+    /// Device name, keying the noise profile
+    name: String,
+}
+
+/// Processor for one stream on `source`, warm-started from `calibration_path` and with the
+/// noise profile of the device and channel selection
+fn build_processor(
+    processor_config: ProcessorConfig,
+    source: &SourceFormat,
+    channels: &ChannelMap,
+    detail_level: &DetailLevel,
+    calibration_path: &std::path::Path,
+    noise_store: &NoiseProfileStore,
+) -> Result<AudioProcessor, Box<dyn std::error::Error>> {
+    // Audio processor with detail level
+    let mut audio_processor = AudioProcessor::with_config(processor_config);
+    audio_processor.detail_level = detail_level.clone();
+    audio_processor.spectrogram_buffer = (0..SPECTROGRAM_WIDTH).map(|_| vec![0.0; SPECTROGRAM_HEIGHT]).collect();
+    let input_channels = channels.output_channels(source.channels);
+    audio_processor.set_input_format(source.sample_rate, input_channels as u16);
+
+    // Warm-start the adaptive state from the last run unless asked not to
+    if !cli_flag("--cold-start") && calibration_path.exists() {
+        match CalibrationSnapshot::load(calibration_path) {
+            Ok(snapshot) => {
+                audio_processor.restore_calibration(&snapshot);
+                println!("Warm-started calibration from {}", calibration_path.display());
+            }
+            Err(e) => eprintln!("Ignoring calibration snapshot: {}", e),
+        }
+    }
+
+    // Per-device noise profile for spectral subtraction and activity thresholds
+    let profile_key = channels.profile_key(&source.name);
+    match noise_store.get(&profile_key) {
+        Ok(Some(profile)) => {
+            audio_processor.set_noise_profile(profile);
+            if audio_processor.noise_profile.is_some() {
+                println!("Loaded noise profile for {}", profile_key);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => eprintln!("Ignoring noise profiles: {}", e),
+    }
+    Ok(audio_processor)
+}
+
+/// Play the captured audio on an output device through `line`. `name` picks the first output
+/// device whose name contains it; otherwise the default output is used.
+fn build_passthrough_stream(
+    name: Option<&str>,
+    sample_rate: u32,
+    line: Arc<DelayLine>,
+) -> Result<cpal::Stream, Box<dyn std::error::Error>> {
+    let host = cpal::default_host();
+    let device = match name {
+        Some(name) => {
+            let wanted = name.to_lowercase();
+            host.output_devices()?
+                .find(|device| device.name().is_ok_and(|n| n.to_lowercase().contains(&wanted)))
+                .ok_or_else(|| format!("no output device matching '{}'", name))?
+        }
+        None => host.default_output_device().ok_or("no default output device")?,
+    };
+    let device_name = device.name().unwrap_or_else(|_| "unknown".to_string());
+    // The delay line does not resample, so the output has to run at the input's rate
+    let supported = device
+        .supported_output_configs()?
+        .filter(|range| range.sample_format() == cpal::SampleFormat::F32)
+        .find(|range| range.min_sample_rate().0 <= sample_rate && sample_rate <= range.max_sample_rate().0)
+        .ok_or_else(|| format!("output device '{}' does not play 32-bit float audio at {} Hz", device_name, sample_rate))?;
+    let stream_config: cpal::StreamConfig = supported.with_sample_rate(cpal::SampleRate(sample_rate)).into();
+    let channels = stream_config.channels as usize;
+    let stream = device.build_output_stream(
+        &stream_config,
+        move |data: &mut [f32], info: &cpal::OutputCallbackInfo| {
+            let timestamp = info.timestamp();
+            let output_latency = timestamp.playback.duration_since(&timestamp.callback).map_or(0.0, |d| d.as_secs_f64());
+            line.pull(data, channels, output_latency);
+        },
+        |err| eprintln!("Pass-through stream error: {}", err),
+        None,
+    )?;
+    stream.play()?;
+    println!("Passing audio through to {}", device_name);
+    Ok(stream)
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Analysis tuning and band layout (validated before the interactive setup so mistakes surface immediately)
+    let presets = PresetStore::new(cli_value("--preset-dir").unwrap_or_else(|| DEFAULT_PRESET_DIR.to_string()));
+    let mut processor_config = match (cli_value("--config"), cli_value("--preset")) {
+        (Some(_), Some(_)) => return Err("use either --config or --preset, not both".into()),
+        (Some(path), None) => {
+            let loaded = ProcessorConfig::load(std::path::Path::new(&path))?;
+            println!("Loaded processor config from {}", path);
+            loaded
+        }
+        (None, Some(name)) => {
+            let loaded = presets.load(&name)?;
+            println!("Loaded preset '{}' from {}", name, presets.dir().display());
+            loaded
+        }
+        (None, None) => {
+            // Balanced envelope for smooth, responsive audio reactions
+            // You can choose from: Smooth, Responsive, Punchy, or Sustained
+            let mut defaults = ProcessorConfig::default();
+            audio::config::EnvelopeProfile::Responsive.apply(&mut defaults.envelope);
+            defaults
+        }
+    };
+    if let Some(path) = cli_value("--bands") {
+        processor_config.bands = audio::bands::load_bands(std::path::Path::new(&path))?;
+        println!("Loaded {} frequency bands from {}", processor_config.bands.len(), path);
+        // Custom fields may refer to bands by name
+        processor_config.validate()?;
+    }
+    if let Some(path) = cli_value("--save-config") {
+        processor_config.save(std::path::Path::new(&path))?;
+        println!("Wrote processor config to {}", path);
+        return Ok(());
+    }
+    if let Some(path) = cli_value("--analyze-file") {
+        // Offline section segmentation of a WAV file, printed as JSON
+        let audio = audio::wav::read_wav(std::path::Path::new(&path))?;
+        let segments = audio::segments::segment_offline(&audio.samples, audio.sample_rate, &processor_config.segments);
+        println!("{}", serde_json::to_string_pretty(&segments)?);
+        return Ok(());
+    }
+
+    // Named analysis streams, each with its own device and processor config
+    let stream_specs = match (cli_value("--streams"), cli_value("--generate")) {
+        (Some(_), Some(_)) => return Err("--generate cannot be combined with --streams; give the stream a \"generate\" entry instead".into()),
+        (Some(path), None) => {
+            let specs = audio::streams::load_streams(std::path::Path::new(&path))?;
+            println!("Loaded {} streams from {}", specs.len(), path);
+            specs
+        }
+        // Test signal instead of a capture device
+        (None, Some(program)) => {
+            let specs = vec![StreamSpec { generate: Some(program), ..StreamSpec::single() }];
+            audio::streams::validate_streams(&specs)?;
+            specs
+        }
+        (None, None) => vec![StreamSpec::single()],
+    };
+    let stream_configs = stream_specs
+        .iter()
+        .map(|spec| match (&spec.config, &spec.preset) {
+            (Some(path), _) => ProcessorConfig::load(std::path::Path::new(path)),
+            (None, Some(name)) => presets.load(name),
+            (None, None) => Ok(processor_config.clone()),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
     // Setup configuration
-    let config = setup_configuration();
+    let needs_device = stream_specs.iter().any(|spec| spec.device.is_none() && spec.generate.is_none());
+    let config = setup_configuration(needs_device);
     
     // WebSocket server configuration
     let addr = "127.0.0.1:8765";
@@ -296,10 +539,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("WebSocket server running on ws://{}", addr);
 
     // Shared state for connected clients
-    let clients = Arc::new(TokioMutex::new(Vec::<futures_util::stream::SplitSink<tokio_tungstenite::WebSocketStream<tokio::net::TcpStream>, Message>>::new()));
+    let clients: websocket::server::ClientList = Arc::new(TokioMutex::new(Vec::new()));
 
-    // Channel for sending messages from audio thread to async task (WebSocket)
-    let (ws_tx, mut ws_rx) = mpsc::channel::<Vec<u8>>(100);
+    // Channel for sending messages from the audio threads to async task (WebSocket), with the
+    // stream name and the server time each was queued at
+    let (ws_tx, mut ws_rx) = mpsc::channel::<(String, Vec<u8>, f64)>(100);
 
     // Channel for sending audio state to TUI
     let (tui_tx, tui_rx) = mpsc::channel::<ProtoState>(100);
@@ -307,129 +551,233 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Channel for sending client count updates to TUI
     let (client_count_tx, client_count_rx) = mpsc::channel::<usize>(10);
 
+    // Update period in microseconds (0 = unthrottled), shared by all streams and adjustable from the TUI
+    let update_period_us = Arc::new(AtomicU64::new((config.update_period * 1_000_000.0) as u64));
+
+    // Noise profiles are stored per device
+    let noise_store = Arc::new(NoiseProfileStore::new(
+        cli_value("--noise-profiles").unwrap_or_else(|| DEFAULT_NOISE_PROFILE_FILE.to_string()),
+    ));
+
+    // Calibration snapshots: one file, or one per stream when started with --streams
+    let calibration_path = std::path::PathBuf::from(
+        cli_value("--calibration").unwrap_or_else(|| DEFAULT_CALIBRATION_FILE.to_string()),
+    );
+    let calibration_paths: Vec<std::path::PathBuf> = stream_specs
+        .iter()
+        .map(|spec| match cli_value("--streams") {
+            Some(_) => audio::streams::per_stream_path(&calibration_path, &spec.name),
+            None => calibration_path.clone(),
+        })
+        .collect();
+
+    println!("✨ Enhanced amplitude smoothing enabled with adaptive attack/decay");
+
+    // Start every stream; each captures on its own thread so its device can be switched live
+    let mut streams = Vec::new();
+    let mut passthrough = None;
+    for (i, (spec, processor_config)) in stream_specs.iter().zip(stream_configs).enumerate() {
+        let program = spec.generate.as_deref().map(Program::parse).transpose()?;
+        let device = match &spec.device {
+            _ if program.is_some() => None,
+            Some(name) => Some(find_input_device(name).ok_or_else(|| InputError::NotFound(name.clone()))?),
+            None => config.input_device.clone(),
+        };
+        let source = match &device {
+            Some(device) => {
+                let device_config = device.default_input_config()?;
+                SourceFormat {
+                    sample_rate: device_config.sample_rate().0,
+                    channels: device_config.channels() as usize,
+                    name: device.name().unwrap_or_else(|_| "unknown".to_string()),
+                }
+            }
+            None => SourceFormat {
+                sample_rate: GENERATOR_SAMPLE_RATE,
+                channels: GENERATOR_CHANNELS as usize,
+                name: "generator".to_string(),
+            },
+        };
+        let channels = spec.channel_map();
+        let audio_processor = build_processor(
+            processor_config,
+            &source,
+            &channels,
+            &config.detail_level,
+            &calibration_paths[i],
+            &noise_store,
+        )?;
+        let processor: Arc<Mutex<AudioProcessor>> = Arc::new(Mutex::new(audio_processor));
+
+        // Optional pass-through of the first stream to an output device, delayed to line up with the visuals
+        if i == 0 && cli_flag("--passthrough") {
+            let setting = match cli_value("--passthrough-delay") {
+                Some(text) => DelaySetting::parse(&text)?,
+                None => DelaySetting::Auto,
+            };
+            let input_channels = channels.output_channels(source.channels);
+            passthrough = Some(Arc::new(DelayLine::new(source.sample_rate, input_channels as u16, setting)));
+        }
+
+        // Per-stage latency, recorded along the pipeline and reported to subscribed clients
+        let latency: SharedLatency = Arc::new(Mutex::new(LatencyStats::new()));
+        let pipeline = audio::input::Pipeline {
+            name: spec.name.clone(),
+            channels,
+            processor: Arc::clone(&processor),
+            ws_tx: ws_tx.clone(),
+            tui_tx: config.show_tui.then(|| tui_tx.clone()),
+            latency: Arc::clone(&latency),
+            passthrough: if i == 0 { passthrough.clone() } else { None },
+            update_period_us: Arc::clone(&update_period_us),
+            noise_store: Arc::clone(&noise_store),
+        };
+        let input = match (device, program) {
+            (Some(device), _) => {
+                let input = audio::input::spawn_input(
+                    device,
+                    spec.fallback.clone().or_else(|| cli_value("--fallback-input")),
+                    pipeline,
+                )?;
+                if stream_specs.len() > 1 {
+                    println!("Stream '{}' capturing from {}", spec.name, input.profile_key());
+                }
+                Some(input)
+            }
+            (None, Some(program)) => {
+                // Print the ground truth so the analysis can be checked against it
+                println!("Stream '{}' generating {}", spec.name, program);
+                spawn_generator(Generator::new(program, GENERATOR_SAMPLE_RATE), pipeline)?;
+                None
+            }
+            (None, None) => unreachable!("an input device is chosen at setup when a stream needs one"),
+        };
+        streams.push(StreamHandle { name: spec.name.clone(), processor, input, latency });
+    }
+    let _passthrough_stream = match &passthrough {
+        Some(line) => Some(build_passthrough_stream(
+            cli_value("--passthrough-device").as_deref(),
+            line.sample_rate(),
+            Arc::clone(line),
+        )?),
+        None => None,
+    };
+    let control = Arc::new(ControlContext { streams, presets, passthrough });
+
     // Spawn async task to handle WebSocket sending
     let clients_clone = Arc::clone(&clients);
     let client_count_tx_clone = client_count_tx.clone();
+    let control_for_send = Arc::clone(&control);
     tokio::spawn(async move {
-        while let Some(message) = ws_rx.recv().await {
+        while let Some((stream, message, queued_at)) = ws_rx.recv().await {
+            let primary = &control_for_send.primary().name;
             let mut clients = clients_clone.lock().await;
             let mut new_clients = Vec::new();
-            for mut client in clients.drain(..) {
-                // Send as binary frame
-                if client.send(Message::Binary(message.clone())).await.is_ok() {
+            for client in clients.drain(..) {
+                // Only to the clients receiving this stream; send as binary frame
+                if !client.session.receives(&stream, primary)
+                    || client.sink.lock().await.send(Message::Binary(message.clone())).await.is_ok()
+                {
                     new_clients.push(client);
                 }
             }
             let client_count = new_clients.len();
             *clients = new_clients;
+            drop(clients);
+            if let Some(handle) = control_for_send.stream(&stream) {
+                handle.latency.lock().unwrap().record(Stage::Send, audio::clock::server_time() - queued_at);
+            }
             
-            // Send client count update to TUI
-            let _ = client_count_tx_clone.send(client_count).await;
+            // Send client count update to TUI; nothing reads it without the TUI, so never wait on it
+            let _ = client_count_tx_clone.try_send(client_count);
         }
     });
 
-    // Get device config
-    let device_config = config.input_device.default_input_config().expect("No input config available");
-
-    // Audio processor with detail level
-    let mut audio_processor = AudioProcessor::new_with_detail_level(config.detail_level.clone());
-    audio_processor.spectrogram_buffer = (0..SPECTROGRAM_WIDTH).map(|_| vec![0.0; SPECTROGRAM_HEIGHT]).collect();
-
-    // Configure amplitude envelope for smooth, responsive audio reactions
-    // You can choose from: Smooth, Responsive, Punchy, or Sustained
-    audio_processor.set_amplitude_envelope_profile(audio::processor::EnvelopeProfile::Responsive);
-    
-    // Or manually configure envelope parameters for custom behavior:
-    // audio_processor.configure_amplitude_envelope(
-    //     0.2,   // attack_rate - how fast amplitude increases (0.0=instant, 1.0=very slow)
-    //     0.08,  // decay_rate - how fast amplitude decreases (0.0=instant, 1.0=very slow)  
-    //     0.85,  // momentum_factor - adds inertia for smoother motion (0.0=no momentum, 1.0=very smooth)
-    //     0.12   // peak_hold_time - how long to hold peak values in seconds
-    // );
-    
-    println!("✨ Enhanced amplitude smoothing enabled with adaptive attack/decay");
-    
-    let processor: Arc<Mutex<AudioProcessor>> = Arc::new(Mutex::new(audio_processor));
-    let processor_clone: Arc<Mutex<AudioProcessor>> = Arc::clone(&processor);
-    let ws_tx_clone = ws_tx.clone();
-    let tui_tx_clone = tui_tx.clone();
-    
-    // Throttle: shared last update time (as f64 seconds since epoch, in microseconds for atomicity)
-    let last_update_time = Arc::new(AtomicU64::new(0));
-    let last_update_time_clone = Arc::clone(&last_update_time);
-
-    // FFT setup
-    let fft_size = 1024;
-    let mut planner = FftPlanner::<f32>::new();
-    let fft = planner.plan_fft_forward(fft_size);
-    let mut fft_input = vec![Complex::zero(); fft_size];
-    let mut fft_output = vec![Complex::zero(); fft_size];
-
-    // Audio stream setup
-    let stream = config.input_device.build_input_stream(
-        &device_config.into(),
-        move |data: &[f32], _: &cpal::InputCallbackInfo| {
-            let now = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs_f64();
-            let delta_time = 1.0 / 60.0; // Approximate frame time
-
-            // Throttle logic
-            if config.update_period > 0.0 {
-                let now_us = (now * 1_000_000.0) as u64;
-                let last_us = last_update_time_clone.load(Ordering::Relaxed);
-                let period_us = (config.update_period * 1_000_000.0) as u64;
-                if now_us < last_us + period_us {
-                    return;
-                }
-                last_update_time_clone.store(now_us, Ordering::Relaxed);
-            }
-
-            // Copy or zero-pad data into fft_input
-            for (i, sample) in fft_input.iter_mut().enumerate() {
-                *sample = if i < data.len() {
-                    Complex::new(data[i], 0.0)
-                } else {
-                    Complex::zero()
-                };
-            }
-            // Run FFT
-            fft_output.copy_from_slice(&fft_input);
-            fft.process(&mut fft_output);
-            // Compute magnitude spectrum (only first N/2 bins are real for real input)
-            let magnitudes: Vec<f32> = fft_output.iter().take(fft_size / 2).map(|c| c.norm()).collect();
-            let mut processor = processor_clone.lock().unwrap();
-            if let Some(state) = processor.update_base_state(delta_time, &magnitudes, now) {
-                let proto_state = ProtoState::from(&state);
-                
-                // Send to WebSocket clients
-                let mut buf = Vec::new();
-                ProstMessage::encode(&proto_state, &mut buf).unwrap();
-                let _ = ws_tx_clone.blocking_send(buf);
-                
-                // Send to TUI if enabled
-                if config.show_tui {
-                    let _ = tui_tx_clone.blocking_send(proto_state);
-                }
-            }
-        },
-        |err| eprintln!("Audio stream error: {}", err),
-        None,
-    )?;
-
-    // Start audio stream
-    stream.play()?;
-
     // Spawn WebSocket server as a separate task (always runs regardless of TUI mode)
     let clients_for_server = Arc::clone(&clients);
     let client_count_tx_for_server = client_count_tx.clone();
+    let control_for_server = Arc::clone(&control);
     tokio::spawn(async move {
         // WebSocket server loop
         while let Ok((stream, _)) = listener.accept().await {
             let clients_clone = Arc::clone(&clients_for_server);
             let count_tx = client_count_tx_for_server.clone();
-            tokio::spawn(websocket::server::handle_connection(stream, clients_clone, count_tx));
+            tokio::spawn(websocket::server::handle_connection(stream, clients_clone, count_tx, Arc::clone(&control_for_server)));
+        }
+    });
+
+    // Latency stats for the clients that asked for them, one report per stream they receive
+    let clients_for_stats = Arc::clone(&clients);
+    let control_for_stats = Arc::clone(&control);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(STATS_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            let primary = &control_for_stats.primary().name;
+            for stream in &control_for_stats.streams {
+                let report = stream.latency.lock().unwrap().report(&stream.name);
+                let report = serde_json::to_string(&report).expect("stats serialize");
+                // Collect the sinks first so the client list isn't held while sending
+                let sinks: Vec<_> = clients_for_stats
+                    .lock()
+                    .await
+                    .iter()
+                    .filter(|client| client.session.stats && client.session.receives(&stream.name, primary))
+                    .map(|client| Arc::clone(&client.sink))
+                    .collect();
+                for sink in sinks {
+                    let _ = sink.lock().await.send(Message::Text(report.clone())).await;
+                }
+            }
+        }
+    });
+
+    // Optional noise floor measurement before the show starts
+    if let Some(seconds) = cli_value("--calibrate-noise") {
+        let seconds: f32 = seconds
+            .parse()
+            .ok()
+            .filter(|seconds: &f32| seconds.is_finite() && *seconds > 0.0)
+            .ok_or_else(|| format!("--calibrate-noise expects seconds, got '{}'", seconds))?;
+        println!("Measuring noise floor for {:.1}s, keep the input silent...", seconds);
+        for stream in &control.streams {
+            stream.processor.lock().unwrap().start_noise_calibration(seconds);
+        }
+        // Time is counted in input samples, so an input that delivers nothing never finishes
+        let deadline = tokio::time::Instant::now() + tokio::time::Duration::from_secs_f32(seconds + NOISE_CALIBRATION_GRACE_SECS);
+        while control.streams.iter().any(|stream| stream.processor.lock().unwrap().is_calibrating_noise()) {
+            if tokio::time::Instant::now() >= deadline {
+                for stream in &control.streams {
+                    stream.processor.lock().unwrap().cancel_noise_calibration();
+                }
+                return Err(format!(
+                    "noise calibration did not finish within {:.1}s; is every input delivering audio?",
+                    seconds + NOISE_CALIBRATION_GRACE_SECS
+                )
+                .into());
+            }
+            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        }
+        for stream in &control.streams {
+            if let Some(input) = &stream.input {
+                save_noise_profile(&stream.processor, &noise_store, &input.profile_key());
+                println!("Noise profile saved for {}", input.profile_key());
+            }
+        }
+    }
+
+    // Periodically snapshot the adaptive state (and any new noise profile) so a crash loses at most one interval
+    let control_for_calibration = Arc::clone(&control);
+    let calibration_paths_for_task = calibration_paths.clone();
+    let noise_store_for_task = Arc::clone(&noise_store);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(CALIBRATION_SAVE_INTERVAL_SECS));
+        interval.tick().await; // First tick fires immediately
+        loop {
+            interval.tick().await;
+            for (stream, path) in control_for_calibration.streams.iter().zip(&calibration_paths_for_task) {
+                save_stream(stream, path, &noise_store_for_task);
+            }
         }
     });
 
@@ -440,7 +788,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         tokio::time::sleep(tokio::time::Duration::from_millis(500)).await; // Brief pause
         
         // Run TUI (WebSocket server runs independently in background)
-        if let Err(e) = tui::run_tui(tui_rx, client_count_rx).await {
+        if let Err(e) = tui::run_tui(tui_rx, client_count_rx, Arc::clone(&control), update_period_us).await {
             eprintln!("TUI error: {}", e);
         }
     } else {
@@ -450,5 +798,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         println!("Shutting down...");
     }
 
+    for (stream, path) in control.streams.iter().zip(&calibration_paths) {
+        save_stream(stream, path, &noise_store);
+    }
+
     Ok(())
-}
\ No newline at end of file
+}
@@ -57,4 +57,157 @@ pub struct PrimaryFreq530State {
     pub mid_high_balance: f64,
     pub onset_strength: f64,
     pub spectrogram_data: Vec<f64>,
-}
\ No newline at end of file
+    pub momentary_lufs: f64,
+    pub short_term_lufs: f64,
+    pub integrated_lufs: f64,
+    pub loudness_range: f64,
+    pub true_peak_dbtp: f64,
+    pub stereo_correlation: f64,
+    pub stereo_width: f64,
+    pub mid_side_ratio: f64,
+    pub stereo_pan: f64,
+    pub left_band_energies: Vec<f64>,
+    pub right_band_energies: Vec<f64>,
+    pub band_pan: Vec<f64>,
+    pub spectral_rolloff_85: f64,
+    pub spectral_rolloff_95: f64,
+    pub spectral_flatness: f64,
+    pub spectral_bandwidth: f64,
+    pub spectral_contrast: Vec<f64>,
+    pub zero_crossing_rate: f64,
+    pub spectral_rolloff_85_dynamic: f64,
+    pub spectral_rolloff_95_dynamic: f64,
+    pub spectral_flatness_dynamic: f64,
+    pub spectral_bandwidth_dynamic: f64,
+    pub spectral_contrast_dynamic: Vec<f64>,
+    pub zero_crossing_rate_dynamic: f64,
+    pub bands: Vec<BandState>,
+    pub playback_state: String,
+    pub events: Vec<AnalysisEvent>,
+    pub buildup_tension: f64,
+    pub section_index: u32,
+    pub section_label: u32,
+    pub beat_clock: f64,
+    pub lfos: Vec<NamedValue>,
+    pub custom: Vec<NamedValue>,
+    pub sequence: u64,
+    pub capture_time: f64,
+    pub next_beat_time: f64,
+    pub device_status: String,
+}
+
+// Scalar fields addressable by name (from modulation expressions and field envelopes)
+macro_rules! scalar_fields {
+    (f64: [$($float:ident),* $(,)?], u32: [$($count:ident),* $(,)?] $(,)?) => {
+        pub const SCALAR_FIELDS: &[&str] = &[$(stringify!($float),)* $(stringify!($count)),*];
+        /// The scalar fields that can be rewritten in place
+        pub const FLOAT_FIELDS: &[&str] = &[$(stringify!($float)),*];
+
+        impl PrimaryFreq530State {
+            /// Scalar field by its proto name
+            pub fn scalar(&self, name: &str) -> Option<f64> {
+                match name {
+                    $(stringify!($float) => Some(self.$float),)*
+                    $(stringify!($count) => Some(self.$count as f64),)*
+                    _ => None,
+                }
+            }
+
+            fn float_mut(&mut self, name: &str) -> Option<&mut f64> {
+                match name {
+                    $(stringify!($float) => Some(&mut self.$float),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+scalar_fields!(
+    f64: [
+        time, adjusted_time, sin, cos, sin_normal, cos_normal, adjusted_sin, adjusted_cos,
+        adjusted_sin_normal, adjusted_cos_normal, low, mid, high, kick, snare, hihat, vocal_likelihood,
+        amplitude, raw_amplitude, beat_intensity, bps, low_dynamic, mid_dynamic, high_dynamic,
+        kick_dynamic, snare_dynamic, hihat_dynamic, amplitude_dynamic, raw_amplitude_dynamic,
+        spectral_flux, last_beat_time, spectral_centroid, beat_phase, low_velocity, mid_velocity,
+        high_velocity, kick_velocity, snare_velocity, hihat_velocity, low_peak_hold, mid_peak_hold,
+        high_peak_hold, kick_peak_hold, snare_peak_hold, hihat_peak_hold, amplitude_peak_hold, low_log,
+        mid_log, high_log, low_mid_balance, mid_high_balance, onset_strength, momentary_lufs,
+        short_term_lufs, integrated_lufs, loudness_range, true_peak_dbtp, stereo_correlation,
+        stereo_width, mid_side_ratio, stereo_pan, spectral_rolloff_85, spectral_rolloff_95,
+        spectral_flatness, spectral_bandwidth, zero_crossing_rate, spectral_rolloff_85_dynamic,
+        spectral_rolloff_95_dynamic, spectral_flatness_dynamic, spectral_bandwidth_dynamic,
+        zero_crossing_rate_dynamic, buildup_tension, beat_clock, capture_time, next_beat_time,
+    ],
+    u32: [section_index, section_label],
+);
+
+/// Attributes of each entry in `bands`
+pub const BAND_ATTRIBUTES: &[&str] = &["value", "dynamic", "velocity", "peak_hold", "log"];
+
+/// Float fields that stay within 0-1, the range envelope followers work in
+pub const UNIT_FIELDS: &[&str] = &[
+    "sin_normal", "cos_normal", "adjusted_sin_normal", "adjusted_cos_normal", "low", "mid", "high", "kick",
+    "snare", "hihat", "vocal_likelihood", "amplitude", "beat_intensity", "low_dynamic", "mid_dynamic",
+    "high_dynamic", "kick_dynamic", "snare_dynamic", "hihat_dynamic", "amplitude_dynamic",
+    "raw_amplitude_dynamic", "spectral_centroid", "beat_phase", "low_peak_hold", "mid_peak_hold",
+    "high_peak_hold", "kick_peak_hold", "snare_peak_hold", "hihat_peak_hold", "amplitude_peak_hold", "low_log",
+    "mid_log", "high_log", "low_mid_balance", "mid_high_balance", "onset_strength", "stereo_width",
+    "spectral_rolloff_85", "spectral_rolloff_95", "spectral_flatness", "spectral_bandwidth", "zero_crossing_rate",
+    "spectral_rolloff_85_dynamic", "spectral_rolloff_95_dynamic", "spectral_flatness_dynamic",
+    "spectral_bandwidth_dynamic", "zero_crossing_rate_dynamic", "buildup_tension",
+];
+
+/// Band attributes that stay within 0-1 (`velocity` is a rate of change)
+pub const UNIT_BAND_ATTRIBUTES: &[&str] = &["value", "dynamic", "peak_hold", "log"];
+
+impl PrimaryFreq530State {
+    /// A float scalar field, or `bands.<name>.<attribute>`, for rewriting in place
+    pub fn field_mut(&mut self, path: &str) -> Option<&mut f64> {
+        match path.split('.').collect::<Vec<_>>().as_slice() {
+            ["bands", name, attribute] => {
+                let band = self.bands.iter_mut().find(|band| band.name == *name)?;
+                match *attribute {
+                    "value" => Some(&mut band.value),
+                    "dynamic" => Some(&mut band.dynamic),
+                    "velocity" => Some(&mut band.velocity),
+                    "peak_hold" => Some(&mut band.peak_hold),
+                    "log" => Some(&mut band.log),
+                    _ => None,
+                }
+            }
+            [name] => self.float_mut(name),
+            _ => None,
+        }
+    }
+}
+
+/// Output of one configured frequency band
+#[derive(Debug, Clone)]
+pub struct BandState {
+    pub name: String,
+    pub value: f64,
+    pub dynamic: f64,
+    pub velocity: f64,
+    pub peak_hold: f64,
+    pub log: f64,
+}
+
+/// Something that happened since the previous frame (a playback state change, a drop, ...)
+#[derive(Debug, Clone)]
+pub struct AnalysisEvent {
+    pub kind: String,
+    pub time: f64,
+    pub from: String,
+    pub to: String,
+    pub duration: f64,
+    /// 0-1; always 1 for playback changes
+    pub confidence: f64,
+}
+
+/// A named scalar output, e.g. one LFO of the modulation bank
+#[derive(Debug, Clone)]
+pub struct NamedValue {
+    pub name: String,
+    pub value: f64,
+}
@@ -7,33 +7,430 @@ use ratatui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    text::Span,
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    text::{Line, Span},
+    widgets::{Bar, BarChart, BarGroup, Block, Borders, List, ListItem, Paragraph, Sparkline},
     Frame, Terminal,
 };
 use std::io;
 use tokio::sync::mpsc;
-use crate::audio::processor::ProtoState;
-use std::time::{Duration, Instant};
+use crate::audio::passthrough::{DelayLine, DelaySetting, DELAY_STEP, MAX_DELAY};
+use crate::audio::input::input_device_names;
+use crate::audio::config::{EnvelopeProfile, ProcessorConfig};
+use crate::audio::processor::{AudioProcessor, DetailLevel, ProtoState};
+use crate::audio::presets::PresetStore;
+use crate::audio::streams::StreamHandle;
+use crate::websocket::control::ControlContext;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// Frames of history kept for the sparklines and the spectrogram (wider panels show less)
+const HISTORY_LEN: usize = 256;
+// Seconds the beat indicator takes to fade after a beat
+const BEAT_FLASH_SECS: f64 = 0.15;
+const NOTE_NAMES: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+const PROFILES: [EnvelopeProfile; 4] = [
+    EnvelopeProfile::Smooth,
+    EnvelopeProfile::Responsive,
+    EnvelopeProfile::Punchy,
+    EnvelopeProfile::Sustained,
+];
+// Update rates offered by the tuning panel, in Hz (0 = unthrottled)
+const UPDATE_RATES: [u64; 7] = [10, 20, 30, 60, 100, 120, 0];
+// Taps further apart than this start a new tap tempo
+const TAP_RESET_SECS: f64 = 2.0;
+const MAX_TAPS: usize = 8;
+// How long a status message stays in the tuning panel
+const STATUS_DURATION: Duration = Duration::from_secs(4);
+// How often the open tuning panel re-reads settings changed elsewhere (presets, control messages)
+const TUNING_REFRESH: Duration = Duration::from_millis(250);
+
+/// Rows of the tuning panel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Setting {
+    InputDevice,
+    Profile,
+    AttackRate,
+    DecayRate,
+    Momentum,
+    PeakHold,
+    AdaptiveAttack,
+    AdaptiveDecay,
+    DetailLevel,
+    UpdateRate,
+    BeatThreshold,
+    FluxThreshold,
+    MinBeatInterval,
+    TapTempo,
+    SavePreset,
+}
+
+const SETTINGS: [Setting; 15] = [
+    Setting::InputDevice,
+    Setting::Profile,
+    Setting::AttackRate,
+    Setting::DecayRate,
+    Setting::Momentum,
+    Setting::PeakHold,
+    Setting::AdaptiveAttack,
+    Setting::AdaptiveDecay,
+    Setting::DetailLevel,
+    Setting::UpdateRate,
+    Setting::BeatThreshold,
+    Setting::FluxThreshold,
+    Setting::MinBeatInterval,
+    Setting::TapTempo,
+    Setting::SavePreset,
+];
+
+/// The tuned stream's settings as last read, so drawing doesn't lock the processor
+struct TuningSnapshot {
+    config: ProcessorConfig,
+    detail_level: DetailLevel,
+    read_at: Instant,
+}
+
+/// Live tuning of the shared processor: arrows select and adjust, Enter/Space toggles or acts
+pub struct TuningPanel {
+    pub open: bool,
+    selected: usize,
+    // Recent taps (UNIX-epoch seconds)
+    taps: Vec<f64>,
+    // Name being typed for "save as preset"
+    preset_name: Option<String>,
+    // Input device picked with the arrows, switched to on Enter
+    input_choice: Option<String>,
+    status: String,
+    status_at: Instant,
+    // Outcomes of work done off the TUI task, such as preset saves
+    status_tx: mpsc::UnboundedSender<String>,
+    status_rx: mpsc::UnboundedReceiver<String>,
+    snapshot: Option<TuningSnapshot>,
+}
+
+impl TuningPanel {
+    fn new() -> Self {
+        let (status_tx, status_rx) = mpsc::unbounded_channel();
+        Self {
+            open: false,
+            selected: 0,
+            taps: Vec::new(),
+            preset_name: None,
+            input_choice: None,
+            status: String::new(),
+            status_at: Instant::now(),
+            status_tx,
+            status_rx,
+            snapshot: None,
+        }
+    }
+
+    /// Whether keys should go to the preset name instead of the shortcuts
+    fn is_typing(&self) -> bool {
+        self.preset_name.is_some()
+    }
+
+    fn set_status(&mut self, status: impl Into<String>) {
+        self.status = status.into();
+        self.status_at = Instant::now();
+    }
+
+    /// The status line, until it expires; the prompt stays while a name is typed
+    fn visible_status(&self) -> &str {
+        if self.is_typing() || self.status_at.elapsed() < STATUS_DURATION {
+            &self.status
+        } else {
+            ""
+        }
+    }
+
+    /// Re-read the settings of `stream`
+    fn refresh(&mut self, stream: &StreamHandle) {
+        let processor = stream.processor.lock().unwrap();
+        self.snapshot = Some(TuningSnapshot {
+            config: processor.config.clone(),
+            detail_level: processor.detail_level.clone(),
+            read_at: Instant::now(),
+        });
+    }
+
+    /// Pick up finished background work, and refresh stale settings while open
+    fn poll(&mut self, stream: &StreamHandle) {
+        while let Ok(status) = self.status_rx.try_recv() {
+            self.set_status(status);
+        }
+        if self.open && self.snapshot.as_ref().is_none_or(|snapshot| snapshot.read_at.elapsed() >= TUNING_REFRESH) {
+            self.refresh(stream);
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyCode, presets: &PresetStore, stream: &StreamHandle, update_period_us: &AtomicU64) {
+        if let Some(name) = &mut self.preset_name {
+            match key {
+                KeyCode::Char(c) => name.push(c),
+                KeyCode::Backspace => {
+                    name.pop();
+                }
+                KeyCode::Enter => {
+                    let name = self.preset_name.take().unwrap_or_default();
+                    let config = stream.processor.lock().unwrap().config.clone();
+                    let presets = presets.clone();
+                    let status_tx = self.status_tx.clone();
+                    self.set_status(format!("Saving preset '{}'...", name));
+                    // The file is written on the blocking pool; the outcome comes back through `status_rx`
+                    tokio::task::spawn_blocking(move || {
+                        let status = match presets.save(&name, &config) {
+                            Ok(()) => format!("Saved preset '{}'", name),
+                            Err(e) => e.to_string(),
+                        };
+                        let _ = status_tx.send(status);
+                    });
+                }
+                KeyCode::Esc => {
+                    self.preset_name = None;
+                    self.set_status(String::new());
+                }
+                _ => {}
+            }
+            return;
+        }
+        match key {
+            KeyCode::Up => self.selected = (self.selected + SETTINGS.len() - 1) % SETTINGS.len(),
+            KeyCode::Down => self.selected = (self.selected + 1) % SETTINGS.len(),
+            KeyCode::Left => self.adjust(-1, stream, update_period_us),
+            KeyCode::Right => self.adjust(1, stream, update_period_us),
+            KeyCode::Enter | KeyCode::Char(' ') => self.activate(stream, update_period_us),
+            _ => {}
+        }
+    }
+
+    fn adjust(&mut self, direction: i32, stream: &StreamHandle, update_period_us: &AtomicU64) {
+        if SETTINGS[self.selected] == Setting::InputDevice {
+            self.cycle_input(direction, stream);
+            return;
+        }
+        let step = direction as f32;
+        let mut processor = stream.processor.lock().unwrap();
+        let edit = |processor: &mut AudioProcessor, change: &dyn Fn(&mut ProcessorConfig)| {
+            let mut config = processor.config.clone();
+            change(&mut config);
+            processor.apply_config(config).map_err(|e| e.to_string())
+        };
+        let result = match SETTINGS[self.selected] {
+            Setting::Profile => {
+                let current = current_profile(&processor.config).and_then(|p| PROFILES.iter().position(|&q| q == p));
+                let next = match current {
+                    Some(i) => (i as i32 + direction).rem_euclid(PROFILES.len() as i32) as usize,
+                    None => 0,
+                };
+                edit(&mut processor, &|config| PROFILES[next].apply(&mut config.envelope))
+            }
+            Setting::AttackRate => edit(&mut processor, &|config| {
+                config.envelope.attack_rate = (config.envelope.attack_rate + step * 0.01).clamp(0.0, 1.0)
+            }),
+            Setting::DecayRate => edit(&mut processor, &|config| {
+                config.envelope.decay_rate = (config.envelope.decay_rate + step * 0.01).clamp(0.0, 1.0)
+            }),
+            Setting::Momentum => edit(&mut processor, &|config| {
+                config.envelope.momentum_factor = (config.envelope.momentum_factor + step * 0.01).clamp(0.0, 1.0)
+            }),
+            Setting::PeakHold => edit(&mut processor, &|config| {
+                config.envelope.peak_hold_time = (config.envelope.peak_hold_time + step * 0.01).max(0.0)
+            }),
+            Setting::AdaptiveAttack => edit(&mut processor, &|config| config.envelope.adaptive_attack = !config.envelope.adaptive_attack),
+            Setting::AdaptiveDecay => edit(&mut processor, &|config| config.envelope.adaptive_decay = !config.envelope.adaptive_decay),
+            Setting::DetailLevel => {
+                processor.detail_level = match (&processor.detail_level, direction > 0) {
+                    (DetailLevel::Basic, true) | (DetailLevel::Full, false) => DetailLevel::Standard,
+                    (DetailLevel::Standard, true) | (DetailLevel::Basic, false) => DetailLevel::Full,
+                    (DetailLevel::Full, true) | (DetailLevel::Standard, false) => DetailLevel::Basic,
+                };
+                Ok(())
+            }
+            Setting::UpdateRate => {
+                let period = update_period_us.load(Ordering::Relaxed);
+                let current = UPDATE_RATES.iter().position(|&hz| rate_period_us(hz) == period).unwrap_or(4);
+                let next = (current as i32 + direction).rem_euclid(UPDATE_RATES.len() as i32) as usize;
+                update_period_us.store(rate_period_us(UPDATE_RATES[next]), Ordering::Relaxed);
+                Ok(())
+            }
+            Setting::BeatThreshold => edit(&mut processor, &|config| {
+                config.beat.beat_threshold = (config.beat.beat_threshold + step * 0.05).max(0.05)
+            }),
+            Setting::FluxThreshold => edit(&mut processor, &|config| {
+                config.beat.spectral_flux_threshold = (config.beat.spectral_flux_threshold + step * 0.01).max(0.0)
+            }),
+            Setting::MinBeatInterval => edit(&mut processor, &|config| {
+                config.beat.min_beat_interval = (config.beat.min_beat_interval + step * 0.01).max(0.05)
+            }),
+            Setting::InputDevice | Setting::TapTempo | Setting::SavePreset => Ok(()),
+        };
+        if let Err(message) = result {
+            self.set_status(message);
+        }
+    }
+
+    fn cycle_input(&mut self, direction: i32, stream: &StreamHandle) {
+        let Some(input) = &stream.input else {
+            self.set_status("This input cannot be switched");
+            return;
+        };
+        let names = input_device_names();
+        if names.is_empty() {
+            self.set_status("No input devices found");
+            return;
+        }
+        let shown = self.input_choice.clone().unwrap_or_else(|| input.device_name());
+        let next = match names.iter().position(|name| *name == shown) {
+            Some(i) => (i as i32 + direction).rem_euclid(names.len() as i32) as usize,
+            None => 0,
+        };
+        self.input_choice = Some(names[next].clone());
+        self.set_status("Enter to switch");
+    }
+
+    fn activate(&mut self, stream: &StreamHandle, update_period_us: &AtomicU64) {
+        match SETTINGS[self.selected] {
+            Setting::InputDevice => {
+                if let (Some(input), Some(name)) = (&stream.input, self.input_choice.take()) {
+                    let input = input.clone();
+                    let status_tx = self.status_tx.clone();
+                    self.set_status(format!("Switching input to '{}'...", name));
+                    // Opening the device blocks until the input thread replies
+                    tokio::task::spawn_blocking(move || {
+                        let status = match input.switch(&name) {
+                            Ok(name) => format!("Switched input to '{}'", name),
+                            Err(e) => e.to_string(),
+                        };
+                        let _ = status_tx.send(status);
+                    });
+                }
+            }
+            Setting::AdaptiveAttack | Setting::AdaptiveDecay => self.adjust(1, stream, update_period_us),
+            Setting::TapTempo => {
+                let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs_f64();
+                if self.taps.last().is_some_and(|&last| now - last > TAP_RESET_SECS) {
+                    self.taps.clear();
+                }
+                self.taps.push(now);
+                if self.taps.len() > MAX_TAPS {
+                    self.taps.remove(0);
+                }
+                stream.processor.lock().unwrap().apply_tap_tempo(&self.taps);
+                self.set_status(format!("Tap {}", self.taps.len()));
+            }
+            Setting::SavePreset => {
+                self.preset_name = Some(String::new());
+                self.set_status("Type a name, Enter to save, Esc to cancel");
+            }
+            _ => {}
+        }
+    }
+}
+
+// Update period in microseconds for a rate in Hz (0 = unthrottled)
+fn rate_period_us(hz: u64) -> u64 {
+    1_000_000u64.checked_div(hz).unwrap_or(0)
+}
+
+/// The profile whose values the envelope currently has, if any
+fn current_profile(config: &ProcessorConfig) -> Option<EnvelopeProfile> {
+    PROFILES.iter().copied().find(|profile| {
+        let mut envelope = config.envelope.clone();
+        profile.apply(&mut envelope);
+        envelope == config.envelope
+    })
+}
+
+/// Recent values of one meter, scaled to 0-100 for a `Sparkline`
+pub struct MeterHistory {
+    pub label: &'static str,
+    pub color: Color,
+    pub values: VecDeque<u64>,
+}
+
+impl MeterHistory {
+    fn new(label: &'static str, color: Color) -> Self {
+        Self { label, color, values: VecDeque::with_capacity(HISTORY_LEN) }
+    }
+
+    fn push(&mut self, value: f64) {
+        if self.values.len() == HISTORY_LEN {
+            self.values.pop_front();
+        }
+        self.values.push_back((value.clamp(0.0, 1.0) * 100.0) as u64);
+    }
+}
 
 pub struct TuiApp {
     pub latest_state: Option<ProtoState>,
     pub connected_clients: usize,
     pub start_time: Instant,
     pub frame_count: u64,
+    pub passthrough: Option<Arc<DelayLine>>,
+    pub control: Arc<ControlContext>,
+    /// Index into `control.streams` of the stream shown and tuned
+    pub selected_stream: usize,
+    /// Shared with the audio callback's throttle, in microseconds (0 = unthrottled)
+    pub update_period_us: Arc<AtomicU64>,
+    pub tuning: TuningPanel,
+    /// Kick, snare, hi-hat and amplitude
+    pub meters: [MeterHistory; 4],
+    /// One column of `quantized_bands` per frame, oldest first
+    pub spectrogram: VecDeque<Vec<u32>>,
 }
 
 impl TuiApp {
-    pub fn new() -> Self {
+    pub fn new(control: Arc<ControlContext>, update_period_us: Arc<AtomicU64>) -> Self {
         Self {
             latest_state: None,
             connected_clients: 0,
             start_time: Instant::now(),
             frame_count: 0,
+            passthrough: control.passthrough.clone(),
+            control,
+            selected_stream: 0,
+            update_period_us,
+            tuning: TuningPanel::new(),
+            meters: [
+                MeterHistory::new("Kick", Color::Red),
+                MeterHistory::new("Snare", Color::Yellow),
+                MeterHistory::new("HiHat", Color::Cyan),
+                MeterHistory::new("Amplitude", Color::Green),
+            ],
+            spectrogram: VecDeque::with_capacity(HISTORY_LEN),
+        }
+    }
+
+    pub fn stream(&self) -> &StreamHandle {
+        &self.control.streams[self.selected_stream]
+    }
+
+    /// Show the next stream; its histories start empty
+    pub fn next_stream(&mut self) {
+        self.selected_stream = (self.selected_stream + 1) % self.control.streams.len();
+        self.latest_state = None;
+        for meter in &mut self.meters {
+            meter.values.clear();
         }
+        self.spectrogram.clear();
+        self.tuning.input_choice = None;
+        self.tuning.snapshot = None;
     }
 
     pub fn update_state(&mut self, state: ProtoState) {
+        if state.stream != self.stream().name {
+            return;
+        }
+        let values = [state.kick_dynamic, state.snare_dynamic, state.hihat_dynamic, state.amplitude];
+        for (meter, value) in self.meters.iter_mut().zip(values) {
+            meter.push(value);
+        }
+        if self.spectrogram.len() == HISTORY_LEN {
+            self.spectrogram.pop_front();
+        }
+        self.spectrogram.push_back(state.quantized_bands.clone());
         self.latest_state = Some(state);
         self.frame_count += 1;
     }
@@ -41,9 +438,28 @@ impl TuiApp {
     pub fn update_clients(&mut self, count: usize) {
         self.connected_clients = count;
     }
+
+    /// Nudge the pass-through delay; leaves auto mode at the delay it had reached
+    pub fn adjust_delay(&mut self, steps: f32) {
+        if let Some(line) = &self.passthrough {
+            let seconds = (line.delay() + steps * DELAY_STEP).clamp(0.0, MAX_DELAY);
+            line.set(DelaySetting::Fixed(seconds));
+        }
+    }
+
+    pub fn auto_delay(&mut self) {
+        if let Some(line) = &self.passthrough {
+            line.set(DelaySetting::Auto);
+        }
+    }
 }
 
-pub async fn run_tui(mut rx: mpsc::Receiver<ProtoState>, mut client_count_rx: mpsc::Receiver<usize>) -> Result<(), Box<dyn std::error::Error>> {
+pub async fn run_tui(
+    mut rx: mpsc::Receiver<ProtoState>,
+    mut client_count_rx: mpsc::Receiver<usize>,
+    control: Arc<ControlContext>,
+    update_period_us: Arc<AtomicU64>,
+) -> Result<(), Box<dyn std::error::Error>> {
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -51,7 +467,7 @@ pub async fn run_tui(mut rx: mpsc::Receiver<ProtoState>, mut client_count_rx: mp
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let mut app = TuiApp::new();
+    let mut app = TuiApp::new(control, update_period_us);
     let mut last_tick = Instant::now();
     let tick_rate = Duration::from_millis(16); // ~60 FPS
 
@@ -65,6 +481,7 @@ pub async fn run_tui(mut rx: mpsc::Receiver<ProtoState>, mut client_count_rx: mp
         while let Ok(count) = client_count_rx.try_recv() {
             app.update_clients(count);
         }
+        app.tuning.poll(&app.control.streams[app.selected_stream]);
 
         // Draw the UI
         terminal.draw(|f| ui(f, &app))?;
@@ -77,7 +494,33 @@ pub async fn run_tui(mut rx: mpsc::Receiver<ProtoState>, mut client_count_rx: mp
         if crossterm::event::poll(timeout)? {
             if let Event::Key(key) = event::read()? {
                 match key.code {
-                    KeyCode::Char('q') | KeyCode::Esc => break,
+                    // A preset name is being typed: every key belongs to it
+                    _ if app.tuning.is_typing() => app.tuning.handle_key(
+                        key.code,
+                        &app.control.presets,
+                        &app.control.streams[app.selected_stream],
+                        &app.update_period_us,
+                    ),
+                    KeyCode::Char('q') => break,
+                    KeyCode::Esc if app.tuning.open => app.tuning.open = false,
+                    KeyCode::Esc => break,
+                    KeyCode::Char('s') => app.tuning.open = !app.tuning.open,
+                    KeyCode::Up | KeyCode::Down | KeyCode::Left | KeyCode::Right | KeyCode::Enter | KeyCode::Char(' ')
+                        if app.tuning.open =>
+                    {
+                        app.tuning.handle_key(
+                            key.code,
+                            &app.control.presets,
+                            &app.control.streams[app.selected_stream],
+                            &app.update_period_us,
+                        );
+                        // Show the change on the next frame
+                        app.tuning.refresh(&app.control.streams[app.selected_stream]);
+                    }
+                    KeyCode::Tab => app.next_stream(),
+                    KeyCode::Char('+') | KeyCode::Char('=') => app.adjust_delay(1.0),
+                    KeyCode::Char('-') => app.adjust_delay(-1.0),
+                    KeyCode::Char('a') => app.auto_delay(),
                     _ => {}
                 }
             }
@@ -112,19 +555,42 @@ fn ui(f: &mut Frame, app: &TuiApp) {
         .split(f.size());
 
     // Header
+    let playback = app.latest_state.as_ref().map_or("-", |state| state.playback_state.as_str());
+    let tension = app.latest_state.as_ref().map_or(0.0, |state| state.buildup_tension);
+    let (section_index, section_label) = app.latest_state.as_ref().map_or((0, 0), |state| (state.section_index, state.section_label));
+    let delay = app.passthrough.as_ref().map_or(String::new(), |line| {
+        let mode = if line.setting() == DelaySetting::Auto { " auto" } else { "" };
+        format!(" | Delay: {:.0}ms{}", line.delay() * 1000.0, mode)
+    });
+    let device_status = app.latest_state.as_ref().map_or("ok", |state| state.device_status.as_str());
+    let mut input = match &app.stream().input {
+        Some(input) => format!("{} ({})", input.device_name(), device_status),
+        None => format!("test signal ({})", device_status),
+    };
+    if app.control.streams.len() > 1 {
+        input = format!("[{}] {}", app.stream().name, input);
+    }
+    // Stand out while the device is down or replaced by the fallback
+    let header_color = if device_status == "ok" { Color::Green } else { Color::Red };
     let header = Paragraph::new(format!(
-        "AudioProcessor Live Monitor | Uptime: {:.1}s | Frames: {} | Clients: {}",
+        "AudioProcessor Live Monitor | Uptime: {:.1}s | Frames: {} | Clients: {} | Input: {} | Playback: {} | Tension: {:.2} | Section: {} ({}){}",
         app.start_time.elapsed().as_secs_f32(),
         app.frame_count,
-        app.connected_clients
+        app.connected_clients,
+        input,
+        playback,
+        tension,
+        section_index,
+        section_label,
+        delay
     ))
     .block(Block::default().borders(Borders::ALL).title("Status"))
-    .style(Style::default().fg(Color::Green));
+    .style(Style::default().fg(header_color));
     f.render_widget(header, chunks[0]);
 
     // Main content
     if let Some(ref state) = app.latest_state {
-        render_audio_data(f, chunks[1], state);
+        render_audio_data(f, chunks[1], app, state);
     } else {
         let waiting = Paragraph::new("Waiting for audio data...")
             .block(Block::default().borders(Borders::ALL).title("Audio Data"))
@@ -133,48 +599,155 @@ fn ui(f: &mut Frame, app: &TuiApp) {
     }
 
     // Footer
-    let footer = Paragraph::new("Press 'q' or ESC to quit")
+    let mut help = if app.tuning.open {
+        "Press 'q' to quit | ↑/↓ select, ←/→ adjust, Enter/Space toggle/tap/save, 's' or ESC close tuning".to_string()
+    } else {
+        "Press 'q' or ESC to quit | 's' tuning".to_string()
+    };
+    if app.passthrough.is_some() {
+        help.push_str(" | '+'/'-' pass-through delay, 'a' auto delay");
+    }
+    if app.control.streams.len() > 1 {
+        help.push_str(" | Tab next stream");
+    }
+    let footer = Paragraph::new(help)
         .block(Block::default().borders(Borders::ALL))
         .style(Style::default().fg(Color::Gray));
     f.render_widget(footer, chunks[2]);
 }
 
-fn render_audio_data(f: &mut Frame, area: Rect, state: &ProtoState) {
+fn render_audio_data(f: &mut Frame, area: Rect, app: &TuiApp, state: &ProtoState) {
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(33), Constraint::Percentage(33), Constraint::Percentage(34)])
+        .constraints([
+            Constraint::Percentage(22),
+            Constraint::Percentage(22),
+            Constraint::Percentage(22),
+            Constraint::Percentage(34),
+        ])
         .split(area);
 
     // Left side: Frequency and amplitude data
     render_frequency_data(f, chunks[0], state);
     
     // Middle: Beat detection and analysis
-    render_analysis_data(f, chunks[1], state);
+    if app.tuning.open {
+        render_tuning(f, chunks[1], app);
+    } else {
+        render_analysis_data(f, chunks[1], state);
+    }
     
-    // Right side: Grid visualization
+    // Grid visualization
     render_grid_data(f, chunks[2], state);
+
+    // Right side: meters and spectra
+    render_visuals(f, chunks[3], app, state);
+}
+
+fn render_tuning(f: &mut Frame, area: Rect, app: &TuiApp) {
+    let panel = &app.tuning;
+    let Some(snapshot) = &panel.snapshot else {
+        let waiting = Paragraph::new("Reading settings...").block(Block::default().borders(Borders::ALL).title("Tuning"));
+        f.render_widget(waiting, area);
+        return;
+    };
+    let envelope = &snapshot.config.envelope;
+    let beat = &snapshot.config.beat;
+    let bps = app.latest_state.as_ref().map_or(0.0, |state| state.bps);
+    let on_off = |flag: bool| if flag { "on" } else { "off" };
+    let period_us = app.update_period_us.load(Ordering::Relaxed);
+    let rows: Vec<String> = SETTINGS
+        .iter()
+        .map(|setting| match setting {
+            Setting::InputDevice => match (&panel.input_choice, &app.stream().input) {
+                (Some(choice), _) => format!("Input device:     {} (Enter)", choice),
+                (None, Some(input)) => format!("Input device:     {}", input.device_name()),
+                (None, None) => "Input device:     test signal".to_string(),
+            },
+            Setting::Profile => format!(
+                "Envelope profile: {}",
+                current_profile(&snapshot.config).map_or("custom".to_string(), |profile| format!("{:?}", profile))
+            ),
+            Setting::AttackRate => format!("Attack rate:      {:.2}", envelope.attack_rate),
+            Setting::DecayRate => format!("Decay rate:       {:.2}", envelope.decay_rate),
+            Setting::Momentum => format!("Momentum:         {:.2}", envelope.momentum_factor),
+            Setting::PeakHold => format!("Peak hold:        {:.2}s", envelope.peak_hold_time),
+            Setting::AdaptiveAttack => format!("Adaptive attack:  {}", on_off(envelope.adaptive_attack)),
+            Setting::AdaptiveDecay => format!("Adaptive decay:   {}", on_off(envelope.adaptive_decay)),
+            Setting::DetailLevel => format!("Detail level:     {:?}", snapshot.detail_level),
+            Setting::UpdateRate => {
+                if period_us == 0 {
+                    "Update rate:      unthrottled".to_string()
+                } else {
+                    format!("Update rate:      {:.0} Hz", 1_000_000.0 / period_us as f64)
+                }
+            }
+            Setting::BeatThreshold => format!("Beat threshold:   {:.2}", beat.beat_threshold),
+            Setting::FluxThreshold => format!("Flux threshold:   {:.2}", beat.spectral_flux_threshold),
+            Setting::MinBeatInterval => format!("Min beat gap:     {:.2}s", beat.min_beat_interval),
+            Setting::TapTempo => format!("Tap tempo:        {:.1} BPM", bps * 60.0),
+            Setting::SavePreset => match &panel.preset_name {
+                Some(name) => format!("Save as preset:   {}_", name),
+                None => "Save as preset...".to_string(),
+            },
+        })
+        .collect();
+
+    let mut items: Vec<ListItem> = rows
+        .into_iter()
+        .enumerate()
+        .map(|(i, row)| {
+            if i == panel.selected {
+                ListItem::new(format!("> {}", row)).style(Style::default().fg(Color::Black).bg(Color::Cyan))
+            } else {
+                ListItem::new(format!("  {}", row))
+            }
+        })
+        .collect();
+    items.push(ListItem::new(""));
+    items.push(ListItem::new(Span::styled(panel.visible_status().to_string(), Style::default().fg(Color::Yellow))));
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title("Tuning"))
+        .style(Style::default().fg(Color::White));
+    f.render_widget(list, area);
 }
 
 fn render_frequency_data(f: &mut Frame, area: Rect, state: &ProtoState) {
-    let items = vec![
+    let mut items = vec![
         ListItem::new(format!("Time: {:.3}s", state.time)),
         ListItem::new(format!("Adjusted Time: {:.3}s", state.adjusted_time)),
         ListItem::new(""),
         ListItem::new(Span::styled("FREQUENCY BANDS", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))),
-        ListItem::new(format!("Low:  {:.3} (dyn: {:.3})", state.low, state.low_dynamic)),
-        ListItem::new(format!("Mid:  {:.3} (dyn: {:.3})", state.mid, state.mid_dynamic)),
-        ListItem::new(format!("High: {:.3} (dyn: {:.3})", state.high, state.high_dynamic)),
+    ];
+    let name_width = state.bands.iter().map(|band| band.name.len()).max().unwrap_or(0);
+    for band in &state.bands {
+        items.push(ListItem::new(format!(
+            "{:<width$} {:.3} (dyn: {:.3})",
+            format!("{}:", band.name),
+            band.value,
+            band.dynamic,
+            width = name_width + 1
+        )));
+    }
+    items.extend([
         ListItem::new(""),
         ListItem::new(Span::styled("AMPLITUDE", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))),
         ListItem::new(format!("Amplitude: {:.3} (dyn: {:.3})", state.amplitude, state.amplitude_dynamic)),
         ListItem::new(format!("Raw Amplitude: {:.3} (dyn: {:.3})", state.raw_amplitude, state.raw_amplitude_dynamic)),
         ListItem::new(""),
+        ListItem::new(Span::styled("LOUDNESS (EBU R128)", Style::default().fg(Color::LightRed).add_modifier(Modifier::BOLD))),
+        ListItem::new(format!("Momentary:  {:.1} LUFS", state.momentary_lufs)),
+        ListItem::new(format!("Short-term: {:.1} LUFS", state.short_term_lufs)),
+        ListItem::new(format!("Integrated: {:.1} LUFS", state.integrated_lufs)),
+        ListItem::new(format!("Range: {:.1} LU | True Peak: {:.1} dBTP", state.loudness_range, state.true_peak_dbtp)),
+        ListItem::new(""),
         ListItem::new(Span::styled("WAVEFORM", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))),
         ListItem::new(format!("Sin: {:.3} (norm: {:.3})", state.sin, state.sin_normal)),
         ListItem::new(format!("Cos: {:.3} (norm: {:.3})", state.cos, state.cos_normal)),
         ListItem::new(format!("Adj Sin: {:.3} (norm: {:.3})", state.adjusted_sin, state.adjusted_sin_normal)),
         ListItem::new(format!("Adj Cos: {:.3} (norm: {:.3})", state.adjusted_cos, state.adjusted_cos_normal)),
-    ];
+    ]);
 
     let list = List::new(items)
         .block(Block::default().borders(Borders::ALL).title("Frequency & Amplitude"))
@@ -200,14 +773,26 @@ fn render_analysis_data(f: &mut Frame, area: Rect, state: &ProtoState) {
         ListItem::new(format!("Beat Intensity: {:.3}", state.beat_intensity)),
         ListItem::new(format!("BPS: {:.2}", state.bps)),
         ListItem::new(format!("Beat Phase: {:.3}", state.beat_phase)),
-        ListItem::new(format!("Last Beat: {:.3}s ago", state.time - state.last_beat_time)),
+        ListItem::new(match seconds_since_beat(state) {
+            Some(seconds) => format!("Last Beat: {:.3}s ago", seconds),
+            None => "Last Beat: -".to_string(),
+        }),
         ListItem::new(""),
         ListItem::new(Span::styled("SPECTRAL ANALYSIS", Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD))),
         ListItem::new(format!("Spectral Flux: {:.3}", state.spectral_flux)),
         ListItem::new(format!("Spectral Centroid: {:.1} Hz", state.spectral_centroid)),
+        ListItem::new(format!("Rolloff 85/95: {:.3} / {:.3}", state.spectral_rolloff_85, state.spectral_rolloff_95)),
+        ListItem::new(format!("Flatness: {:.3} | Bandwidth: {:.3}", state.spectral_flatness, state.spectral_bandwidth)),
+        ListItem::new(format!("Zero Crossing Rate: {:.3}", state.zero_crossing_rate)),
+        ListItem::new(format!("Contrast: {}", state.spectral_contrast.iter().map(|c| format!("{:.2}", c)).collect::<Vec<_>>().join(" "))),
         ListItem::new(format!("Vocal Likelihood: {:.3}", state.vocal_likelihood)),
         ListItem::new(format!("Freq Grid Avg: {:.3}", grid_map_avg)),
         ListItem::new(""),
+        ListItem::new(Span::styled("STEREO IMAGE", Style::default().fg(Color::LightGreen).add_modifier(Modifier::BOLD))),
+        ListItem::new(format!("Correlation: {:+.3} | Width: {:.3}", state.stereo_correlation, state.stereo_width)),
+        ListItem::new(format!("M/S Ratio: {:.3} | Pan: {:+.3}", state.mid_side_ratio, state.stereo_pan)),
+        ListItem::new(format!("Band Pan: {}", state.band_pan.iter().map(|p| format!("{:+.2}", p)).collect::<Vec<_>>().join(" "))),
+        ListItem::new(""),
         ListItem::new(Span::styled("QUANTIZED BANDS", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))),
         ListItem::new(format!("Bands: {} values", state.quantized_bands.len())),
         ListItem::new(format!("Chromagram: {} bins", state.chromagram.len())),
@@ -303,4 +888,177 @@ fn render_grid_data(f: &mut Frame, area: Rect, state: &ProtoState) {
         .block(Block::default().borders(Borders::ALL).title("Frequency Grid (16x16)"))
         .style(Style::default().fg(Color::White));
     f.render_widget(list, area);
-} 
\ No newline at end of file
+}
+
+fn render_visuals(f: &mut Frame, area: Rect, app: &TuiApp, state: &ProtoState) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),      // Beat flash
+            Constraint::Percentage(30), // Spectrum
+            Constraint::Length(5),      // Chromagram
+            Constraint::Min(6),         // Spectrogram
+            Constraint::Length(8),      // Sparklines
+        ])
+        .split(area);
+    render_beat_flash(f, chunks[0], state);
+    render_spectrum(f, chunks[1], state);
+    render_chromagram(f, chunks[2], state);
+    render_spectrogram(f, chunks[3], &app.spectrogram);
+    render_sparklines(f, chunks[4], &app.meters);
+}
+
+/// Seconds since the last beat, from the phase (`time` and `last_beat_time` run on different
+/// clocks); `None` until a tempo is detected
+fn seconds_since_beat(state: &ProtoState) -> Option<f64> {
+    (state.bps > 0.0).then(|| state.beat_phase.clamp(0.0, 1.0) / state.bps)
+}
+
+fn render_beat_flash(f: &mut Frame, area: Rect, state: &ProtoState) {
+    let level = seconds_since_beat(state).map_or(0.0, |since_beat| (1.0 - since_beat / BEAT_FLASH_SECS).clamp(0.0, 1.0));
+    let phase_cells = 16;
+    let filled = ((state.beat_phase.clamp(0.0, 1.0) * phase_cells as f64) as usize).min(phase_cells);
+    let text = format!(
+        " {}  {:.1} BPM  phase {}{}",
+        if level > 0.0 { "● BEAT" } else { "○ beat" },
+        state.bps * 60.0,
+        "█".repeat(filled),
+        "░".repeat(phase_cells - filled)
+    );
+    let red = (40.0 + 215.0 * level) as u8;
+    let flash = Paragraph::new(text)
+        .block(Block::default().borders(Borders::ALL).title("Beat"))
+        .style(Style::default().fg(Color::White).bg(Color::Rgb(red, 0, 0)).add_modifier(Modifier::BOLD));
+    f.render_widget(flash, area);
+}
+
+fn render_spectrum(f: &mut Frame, area: Rect, state: &ProtoState) {
+    let bands = &state.quantized_bands;
+    let inner_width = area.width.saturating_sub(2) as usize;
+    let count = bands.len().max(1);
+    // One-cell gaps when there is room for them
+    let (bar_width, bar_gap) = if inner_width >= count * 2 { ((inner_width / count - 1) as u16, 1) } else { (1, 0) };
+    let bars: Vec<Bar> = bands
+        .iter()
+        .map(|&value| {
+            Bar::default()
+                .value(value as u64)
+                .text_value(String::new())
+                .style(Style::default().fg(heat_color(value.min(255) as u8)))
+        })
+        .collect();
+    let chart = BarChart::default()
+        .block(Block::default().borders(Borders::ALL).title("Spectrum"))
+        .data(BarGroup::default().bars(&bars))
+        .bar_width(bar_width)
+        .bar_gap(bar_gap)
+        .max(255);
+    f.render_widget(chart, area);
+}
+
+fn render_chromagram(f: &mut Frame, area: Rect, state: &ProtoState) {
+    let inner_width = area.width.saturating_sub(2);
+    let bar_width = (inner_width / 12).saturating_sub(1).max(2);
+    let bars: Vec<Bar> = NOTE_NAMES
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let value = state.chromagram.get(i).copied().unwrap_or(0.0).clamp(0.0, 1.0);
+            Bar::default()
+                .value((value * 100.0) as u64)
+                .text_value(String::new())
+                .label(Line::from(*name))
+                .style(Style::default().fg(Color::Magenta))
+        })
+        .collect();
+    let chart = BarChart::default()
+        .block(Block::default().borders(Borders::ALL).title("Chromagram"))
+        .data(BarGroup::default().bars(&bars))
+        .bar_width(bar_width)
+        .bar_gap(1)
+        .max(100);
+    f.render_widget(chart, area);
+}
+
+/// Scrolling spectrogram, newest frame on the right and low bands at the bottom. Each cell
+/// shows two bands with a half block (upper band as foreground, lower as background).
+fn render_spectrogram(f: &mut Frame, area: Rect, history: &VecDeque<Vec<u32>>) {
+    let block = Block::default().borders(Borders::ALL).title("Spectrogram");
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    let (width, height) = (inner.width as usize, inner.height as usize);
+    if width == 0 || height == 0 {
+        return;
+    }
+    let columns: Vec<&Vec<u32>> = history.iter().skip(history.len().saturating_sub(width)).collect();
+    let pixel_rows = height * 2;
+    // Band shown at a pixel row counted from the top
+    let value = |column: &Vec<u32>, pixel_row: usize| -> u8 {
+        if column.is_empty() {
+            return 0;
+        }
+        let band = (pixel_rows - 1 - pixel_row) * column.len() / pixel_rows;
+        column[band].min(255) as u8
+    };
+    let lines: Vec<Line> = (0..height)
+        .map(|row| {
+            let spans: Vec<Span> = columns
+                .iter()
+                .map(|column| {
+                    let upper = heat_color(value(column, row * 2));
+                    let lower = heat_color(value(column, row * 2 + 1));
+                    Span::styled("▀", Style::default().fg(upper).bg(lower))
+                })
+                .collect();
+            Line::from(spans)
+        })
+        .collect();
+    f.render_widget(Paragraph::new(lines), inner);
+}
+
+fn render_sparklines(f: &mut Frame, area: Rect, meters: &[MeterHistory; 4]) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+    let cells: Vec<Rect> = rows
+        .iter()
+        .flat_map(|&row| {
+            Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .split(row)
+                .to_vec()
+        })
+        .collect();
+    for (meter, &cell) in meters.iter().zip(&cells) {
+        // Sparklines draw from the left, so pass only what fits, ending with the newest value
+        let width = cell.width.saturating_sub(2) as usize;
+        let data: Vec<u64> = meter.values.iter().skip(meter.values.len().saturating_sub(width)).copied().collect();
+        let title = format!("{} {}", meter.label, meter.values.back().copied().unwrap_or(0));
+        let sparkline = Sparkline::default()
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .data(&data)
+            .max(100)
+            .style(Style::default().fg(meter.color));
+        f.render_widget(sparkline, cell);
+    }
+}
+
+/// Black through blue, magenta, red and yellow to white for 0-255
+fn heat_color(value: u8) -> Color {
+    const STOPS: [(f32, f32, f32); 6] = [
+        (0.0, 0.0, 0.0),
+        (20.0, 20.0, 140.0),
+        (160.0, 20.0, 160.0),
+        (230.0, 40.0, 30.0),
+        (250.0, 200.0, 20.0),
+        (255.0, 255.0, 255.0),
+    ];
+    let position = value as f32 / 255.0 * (STOPS.len() - 1) as f32;
+    let index = (position as usize).min(STOPS.len() - 2);
+    let t = position - index as f32;
+    let (from, to) = (STOPS[index], STOPS[index + 1]);
+    let mix = |a: f32, b: f32| (a + (b - a) * t) as u8;
+    Color::Rgb(mix(from.0, to.0), mix(from.1, to.1), mix(from.2, to.2))
+}
@@ -0,0 +1,296 @@
+// JSON control protocol. Clients may send text frames such as
+// `{"type": "load_preset", "name": "techno"}`; the reply goes back to that client only,
+// as a text frame, so clients that only decode the binary state stream are unaffected.
+// Any message may name the analysis stream it acts on with `"stream": "vocals"`; otherwise it
+// acts on the first stream the client receives.
+
+use std::fmt;
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use crate::audio::clock;
+use crate::audio::config::{ConfigError, EnvelopeConfig, EnvelopeProfile, FieldEnvelope};
+use crate::audio::input::{input_device_names, InputError, InputHandle};
+use crate::audio::noise::DEFAULT_NOISE_CALIBRATION_SECS;
+use crate::audio::passthrough::{DelayLine, DelaySetting, MAX_DELAY};
+use crate::audio::presets::PresetStore;
+use crate::audio::streams::StreamHandle;
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ControlMessage {
+    ListPresets,
+    LoadPreset { name: String },
+    /// Save the running configuration under `name`
+    SavePreset { name: String },
+    /// Drop the learned maxima/baselines/histories and start cold
+    ResetCalibration,
+    /// Measure the input's noise spectrum; the input should be silent for `seconds`
+    CalibrateNoise { seconds: Option<f32> },
+    ClearNoiseProfile,
+    /// Attach (or replace) an envelope follower on one field
+    SetFieldEnvelope {
+        field: String,
+        profile: Option<EnvelopeProfile>,
+        envelope: Option<EnvelopeConfig>,
+    },
+    ClearFieldEnvelope { field: String },
+    /// Clock sync: `client_time` is echoed back with the server's clocks
+    Ping { client_time: f64 },
+    /// Start or stop periodic latency `stats` messages to this client
+    SubscribeStats { enabled: bool },
+    /// Pass-through delay in seconds; without `seconds` it follows the measured latency
+    SetPassthroughDelay { seconds: Option<f32> },
+    ListInputs,
+    /// Capture from another input device (exact name, or part of one)
+    SwitchInput { name: String },
+    ListStreams,
+    /// Receive frames of exactly these streams from now on
+    Subscribe { streams: Vec<String> },
+}
+
+// The optional `stream` any message may carry
+#[derive(Debug, Default, Deserialize)]
+struct Target {
+    #[serde(default)]
+    stream: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ControlReply {
+    Presets { names: Vec<String> },
+    PresetLoaded { name: String },
+    PresetSaved { name: String },
+    CalibrationReset,
+    NoiseCalibrationStarted { seconds: f32 },
+    NoiseProfileCleared,
+    FieldEnvelopeSet { field: String },
+    FieldEnvelopeCleared { field: String },
+    /// `server_time` is the clock of `capture_time`/`next_beat_time`; `server_epoch` that of `beat_times`
+    Pong { client_time: f64, server_time: f64, server_epoch: f64 },
+    StatsSubscription { enabled: bool },
+    /// `seconds` is the delay now targeted
+    PassthroughDelay { seconds: f32, auto: bool },
+    Inputs { names: Vec<String>, current: String },
+    InputSwitched { name: String },
+    /// `subscribed` are the streams this client receives
+    Streams { names: Vec<String>, subscribed: Vec<String> },
+    Subscribed { streams: Vec<String> },
+    Error { message: String },
+}
+
+/// Why a control message failed
+#[derive(Debug)]
+pub enum ControlError {
+    Config(ConfigError),
+    Input(InputError),
+    /// Out of range, or not available on this server
+    Rejected(String),
+}
+
+impl fmt::Display for ControlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ControlError::Config(e) => write!(f, "{}", e),
+            ControlError::Input(e) => write!(f, "{}", e),
+            ControlError::Rejected(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl From<ConfigError> for ControlError {
+    fn from(e: ConfigError) -> Self {
+        ControlError::Config(e)
+    }
+}
+
+impl From<InputError> for ControlError {
+    fn from(e: InputError) -> Self {
+        ControlError::Input(e)
+    }
+}
+
+/// Per-connection settings a client can change for itself
+#[derive(Debug, Clone, Default)]
+pub struct ClientSession {
+    /// Receives the periodic latency `stats` messages
+    pub stats: bool,
+    /// Streams whose frames this client receives; `None` means the first stream only
+    pub streams: Option<Vec<String>>,
+}
+
+impl ClientSession {
+    /// Whether frames of `stream` go to this client; `primary` is the first stream's name
+    pub fn receives(&self, stream: &str, primary: &str) -> bool {
+        match &self.streams {
+            Some(streams) => streams.iter().any(|name| name == stream),
+            None => stream == primary,
+        }
+    }
+}
+
+/// Shared state the control handlers act on
+pub struct ControlContext {
+    /// The running analysis streams; the first is the default
+    pub streams: Vec<StreamHandle>,
+    pub presets: PresetStore,
+    /// Present when started with `--passthrough`; plays the first stream
+    pub passthrough: Option<Arc<DelayLine>>,
+}
+
+impl ControlContext {
+    pub fn primary(&self) -> &StreamHandle {
+        &self.streams[0]
+    }
+
+    pub fn stream(&self, name: &str) -> Option<&StreamHandle> {
+        self.streams.iter().find(|stream| stream.name == name)
+    }
+
+    fn stream_names(&self) -> Vec<String> {
+        self.streams.iter().map(|stream| stream.name.clone()).collect()
+    }
+
+    /// Parse and execute one text frame from the client owning `session`
+    pub fn handle_text(&self, text: &str, session: &mut ClientSession) -> ControlReply {
+        match serde_json::from_str::<ControlMessage>(text) {
+            Ok(message) => {
+                let target = serde_json::from_str::<Target>(text).unwrap_or_default();
+                self.handle(message, target.stream.as_deref(), session)
+            }
+            Err(e) => ControlReply::Error { message: format!("invalid control message: {}", e) },
+        }
+    }
+
+    /// `handle_text` on the blocking pool, since handlers lock the processors and read and
+    /// write preset files; the session comes back with the reply
+    pub async fn handle_text_async(self: &Arc<Self>, text: String, mut session: ClientSession) -> (ControlReply, ClientSession) {
+        let control = Arc::clone(self);
+        tokio::task::spawn_blocking(move || {
+            let reply = control.handle_text(&text, &mut session);
+            (reply, session)
+        })
+        .await
+        .expect("control handler panicked")
+    }
+
+    /// Execute `message` on the stream named `stream`, or on the client's first stream
+    pub fn handle(&self, message: ControlMessage, stream: Option<&str>, session: &mut ClientSession) -> ControlReply {
+        self.target(stream, session)
+            .and_then(|target| self.execute(message, target, session))
+            .unwrap_or_else(|e| ControlReply::Error { message: e.to_string() })
+    }
+
+    fn target(&self, stream: Option<&str>, session: &ClientSession) -> Result<&StreamHandle, ControlError> {
+        match stream {
+            Some(name) => self.stream(name).ok_or_else(|| ControlError::Rejected(format!("no stream named '{}'", name))),
+            None => Ok(session
+                .streams
+                .iter()
+                .flatten()
+                .find_map(|name| self.stream(name))
+                .unwrap_or_else(|| self.primary())),
+        }
+    }
+
+    fn execute(
+        &self,
+        message: ControlMessage,
+        target: &StreamHandle,
+        session: &mut ClientSession,
+    ) -> Result<ControlReply, ControlError> {
+        match message {
+            ControlMessage::ListPresets => Ok(ControlReply::Presets { names: self.presets.list()? }),
+            ControlMessage::LoadPreset { name } => {
+                let config = self.presets.load(&name)?;
+                target.processor.lock().unwrap().apply_config(config)?;
+                Ok(ControlReply::PresetLoaded { name })
+            }
+            ControlMessage::SavePreset { name } => {
+                let config = target.processor.lock().unwrap().config.clone();
+                self.presets.save(&name, &config)?;
+                Ok(ControlReply::PresetSaved { name })
+            }
+            ControlMessage::ResetCalibration => {
+                target.processor.lock().unwrap().reset_calibration();
+                Ok(ControlReply::CalibrationReset)
+            }
+            ControlMessage::CalibrateNoise { seconds } => {
+                let seconds = seconds.unwrap_or(DEFAULT_NOISE_CALIBRATION_SECS);
+                if seconds.is_finite() && seconds > 0.0 {
+                    target.processor.lock().unwrap().start_noise_calibration(seconds);
+                    Ok(ControlReply::NoiseCalibrationStarted { seconds })
+                } else {
+                    Err(ControlError::Rejected("seconds must be positive".to_string()))
+                }
+            }
+            ControlMessage::ClearNoiseProfile => {
+                target.processor.lock().unwrap().clear_noise_profile();
+                Ok(ControlReply::NoiseProfileCleared)
+            }
+            ControlMessage::Ping { client_time } => Ok(ControlReply::Pong {
+                client_time,
+                server_time: clock::server_time(),
+                server_epoch: clock::epoch_time(),
+            }),
+            ControlMessage::SubscribeStats { enabled } => {
+                session.stats = enabled;
+                Ok(ControlReply::StatsSubscription { enabled })
+            }
+            ControlMessage::ListInputs => Ok(ControlReply::Inputs {
+                names: input_device_names(),
+                current: target.input.as_ref().map_or(String::new(), InputHandle::device_name),
+            }),
+            ControlMessage::ListStreams => Ok(ControlReply::Streams {
+                names: self.stream_names(),
+                subscribed: self
+                    .stream_names()
+                    .into_iter()
+                    .filter(|name| session.receives(name, &self.primary().name))
+                    .collect(),
+            }),
+            ControlMessage::Subscribe { streams } => {
+                if let Some(unknown) = streams.iter().find(|name| self.stream(name).is_none()) {
+                    return Err(ControlError::Rejected(format!("no stream named '{}'", unknown)));
+                }
+                session.streams = Some(streams.clone());
+                Ok(ControlReply::Subscribed { streams })
+            }
+            ControlMessage::SwitchInput { name } => match &target.input {
+                Some(input) => Ok(ControlReply::InputSwitched { name: input.switch(&name)? }),
+                None => Err(ControlError::Rejected("this input cannot be switched".to_string())),
+            },
+            ControlMessage::SetPassthroughDelay { seconds } => match (&self.passthrough, seconds) {
+                (None, _) => Err(ControlError::Rejected("pass-through is not enabled (start with --passthrough)".to_string())),
+                (Some(_), Some(seconds)) if !(0.0..=MAX_DELAY).contains(&seconds) => {
+                    Err(ControlError::Rejected(format!("seconds must be within 0..={}", MAX_DELAY)))
+                }
+                (Some(line), seconds) => {
+                    line.set(seconds.map_or(DelaySetting::Auto, DelaySetting::Fixed));
+                    Ok(ControlReply::PassthroughDelay { seconds: line.delay(), auto: seconds.is_none() })
+                }
+            },
+            ControlMessage::SetFieldEnvelope { field, profile, envelope } => {
+                let mut processor = target.processor.lock().unwrap();
+                let mut config = processor.config.clone();
+                let follower = FieldEnvelope { field: field.clone(), profile, envelope };
+                match config.field_envelopes.iter_mut().find(|existing| existing.field == field) {
+                    Some(existing) => *existing = follower,
+                    None => config.field_envelopes.push(follower),
+                }
+                processor.apply_config(config)?;
+                Ok(ControlReply::FieldEnvelopeSet { field })
+            }
+            ControlMessage::ClearFieldEnvelope { field } => {
+                let mut processor = target.processor.lock().unwrap();
+                let mut config = processor.config.clone();
+                if !config.field_envelopes.iter().any(|existing| existing.field == field) {
+                    return Err(ControlError::Rejected(format!("no envelope on field '{}'", field)));
+                }
+                config.field_envelopes.retain(|existing| existing.field != field);
+                processor.apply_config(config)?;
+                Ok(ControlReply::FieldEnvelopeCleared { field })
+            }
+        }
+    }
+}
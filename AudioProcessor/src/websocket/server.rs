@@ -1,37 +1,69 @@
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use tokio::net::TcpStream;
 use tokio::sync::Mutex as TokioMutex;
 use tokio::sync::mpsc;
-use futures_util::StreamExt;
+use futures_util::{SinkExt, StreamExt};
 use tungstenite::Message;
-use tokio::time;
-use std::time::Duration;
+use crate::websocket::control::{ClientSession, ControlContext};
+
+pub type ClientSink = futures_util::stream::SplitSink<tokio_tungstenite::WebSocketStream<TcpStream>, Message>;
+pub type ClientList = Arc<TokioMutex<Vec<Client>>>;
+/// Locked per client, so writing to one client doesn't hold the client list
+pub type SharedSink = Arc<TokioMutex<ClientSink>>;
+
+static NEXT_CLIENT_ID: AtomicU64 = AtomicU64::new(1);
+
+pub struct Client {
+    pub id: u64,
+    pub sink: SharedSink,
+    /// Copy of the session the connection task owns, for the tasks sending to this client
+    pub session: ClientSession,
+}
 
 pub async fn handle_connection(
     stream: TcpStream,
-    clients: Arc<TokioMutex<Vec<futures_util::stream::SplitSink<tokio_tungstenite::WebSocketStream<tokio::net::TcpStream>, Message>>>>,
+    clients: ClientList,
     client_count_tx: mpsc::Sender<usize>,
+    control: Arc<ControlContext>,
 ) {
     let ws_stream = tokio_tungstenite::accept_async(stream).await.expect("WebSocket handshake failed");
     let (write, mut read) = ws_stream.split();
+    let id = NEXT_CLIENT_ID.fetch_add(1, Ordering::Relaxed);
     
     // Add client and notify TUI
     {
         let mut clients_guard = clients.lock().await;
-        clients_guard.push(write);
-        let _ = client_count_tx.send(clients_guard.len()).await;
+        clients_guard.push(Client { id, sink: Arc::new(TokioMutex::new(write)), session: ClientSession::default() });
+        let _ = client_count_tx.try_send(clients_guard.len());
     }
     
-    // Wait for client to disconnect by reading messages (and ignoring them)
+    // Handle control messages until the client disconnects. Only this task changes the
+    // session, so it is handled without holding the client list and copied back after
+    let mut session = ClientSession::default();
     while let Some(msg) = read.next().await {
-        if msg.is_err() {
-            break; // Client disconnected
+        match msg {
+            Ok(Message::Text(text)) => {
+                let (reply, handled) = control.handle_text_async(text, session).await;
+                session = handled;
+                let reply = serde_json::to_string(&reply).expect("control replies serialize");
+                let sink = clients.lock().await.iter_mut().find(|client| client.id == id).map(|client| {
+                    client.session = session.clone();
+                    Arc::clone(&client.sink)
+                });
+                if let Some(sink) = sink {
+                    let _ = sink.lock().await.send(Message::Text(reply)).await;
+                }
+            }
+            Ok(Message::Close(_)) | Err(_) => break, // Client disconnected
+            Ok(_) => {}
         }
     }
     
     // Client disconnected, notify TUI of new count
     {
-        let clients_guard = clients.lock().await;
-        let _ = client_count_tx.send(clients_guard.len()).await;
+        let mut clients_guard = clients.lock().await;
+        clients_guard.retain(|client| client.id != id);
+        let _ = client_count_tx.try_send(clients_guard.len());
     }
-} 
\ No newline at end of file
+}
@@ -0,0 +1,140 @@
+// End-to-end latency bookkeeping: each stage between sound reaching the input and the
+// frame leaving the socket records its delay here, and subscribed clients get a periodic
+// `{"type": "stats", ...}` text frame with the per-stage figures.
+
+use std::sync::{Arc, Mutex};
+use serde::Serialize;
+
+/// Seconds between stats messages
+pub const STATS_INTERVAL_SECS: u64 = 1;
+// Smoothing of the per-stage means
+const MEAN_ALPHA: f64 = 0.05;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Driver-reported time between capture and the input callback
+    Capture,
+    /// Length of the callback buffer; its first sample is this much older than its last
+    Buffer,
+    /// Half the FFT window: where the analyzed spectrum is centred in time
+    FftWindow,
+    /// Time spent in the analysis itself
+    Analysis,
+    /// Estimated lag of the amplitude envelope's attack
+    Smoothing,
+    /// Average wait for the next sent frame when updates are rate limited
+    Throttle,
+    /// Protobuf encoding
+    Encode,
+    /// Queue to the send task plus writing to every client
+    Send,
+}
+
+const STAGES: [Stage; 8] = [
+    Stage::Capture,
+    Stage::Buffer,
+    Stage::FftWindow,
+    Stage::Analysis,
+    Stage::Smoothing,
+    Stage::Throttle,
+    Stage::Encode,
+    Stage::Send,
+];
+
+impl Stage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Stage::Capture => "capture",
+            Stage::Buffer => "buffer",
+            Stage::FftWindow => "fft_window",
+            Stage::Analysis => "analysis",
+            Stage::Smoothing => "smoothing",
+            Stage::Throttle => "throttle",
+            Stage::Encode => "encode",
+            Stage::Send => "send",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct StageStats {
+    mean: f64,
+    max: f64,
+    samples: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StageReport {
+    pub stage: &'static str,
+    pub mean_ms: f64,
+    pub max_ms: f64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename = "stats")]
+pub struct StatsReport {
+    /// Stream the figures belong to
+    pub stream: String,
+    pub stages: Vec<StageReport>,
+    /// Sum of the stage means
+    pub total_ms: f64,
+    /// Look-ahead currently applied to the beat fields
+    pub lookahead_ms: f64,
+}
+
+pub struct LatencyStats {
+    stages: [StageStats; STAGES.len()],
+    lookahead: f64,
+}
+
+pub type SharedLatency = Arc<Mutex<LatencyStats>>;
+
+impl LatencyStats {
+    pub fn new() -> Self {
+        LatencyStats {
+            stages: [StageStats::default(); STAGES.len()],
+            lookahead: 0.0,
+        }
+    }
+
+    pub fn record(&mut self, stage: Stage, seconds: f64) {
+        let index = STAGES.iter().position(|&s| s == stage).unwrap_or(0);
+        let stats = &mut self.stages[index];
+        stats.mean = if stats.samples == 0 { seconds } else { stats.mean + (seconds - stats.mean) * MEAN_ALPHA };
+        stats.max = stats.max.max(seconds);
+        stats.samples += 1;
+    }
+
+    /// Mean capture-to-send latency in seconds
+    pub fn total(&self) -> f64 {
+        self.stages.iter().map(|stats| stats.mean).sum()
+    }
+
+    pub fn set_lookahead(&mut self, seconds: f64) {
+        self.lookahead = seconds;
+    }
+
+    /// Current figures; maxima restart for the next report
+    pub fn report(&mut self, stream: &str) -> StatsReport {
+        let total = self.total();
+        let stages = STAGES
+            .iter()
+            .zip(self.stages.iter_mut())
+            .map(|(stage, stats)| {
+                let report = StageReport {
+                    stage: stage.as_str(),
+                    mean_ms: stats.mean * 1000.0,
+                    max_ms: stats.max * 1000.0,
+                };
+                stats.max = 0.0;
+                report
+            })
+            .collect();
+        StatsReport {
+            stream: stream.to_string(),
+            stages,
+            total_ms: total * 1000.0,
+            lookahead_ms: self.lookahead * 1000.0,
+        }
+    }
+}